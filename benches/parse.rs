@@ -0,0 +1,102 @@
+use std::io::Cursor;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use ics_parser::{lossless::LosslessComponent, parser::Component, reader::ComponentReader};
+
+/// Generate a calendar with the given number of events, with a realistic mix
+/// of parameters, quoting and folded lines.
+fn synthetic_feed(events: usize) -> String {
+    let mut feed = String::from(
+        "BEGIN:VCALENDAR\r\n\
+        VERSION:2.0\r\n\
+        PRODID:-//Example Corp//Synthetic Feed//EN\r\n\
+        BEGIN:VTIMEZONE\r\n\
+        TZID:Europe/London\r\n\
+        BEGIN:DAYLIGHT\r\n\
+        TZOFFSETFROM:+0000\r\n\
+        RRULE:FREQ=YEARLY;BYDAY=-1SU;BYMONTH=3\r\n\
+        DTSTART:19810329T010000\r\n\
+        TZNAME:BST\r\n\
+        TZOFFSETTO:+0100\r\n\
+        END:DAYLIGHT\r\n\
+        BEGIN:STANDARD\r\n\
+        TZOFFSETFROM:+0100\r\n\
+        RRULE:FREQ=YEARLY;BYDAY=-1SU;BYMONTH=10\r\n\
+        DTSTART:19961027T020000\r\n\
+        TZNAME:GMT\r\n\
+        TZOFFSETTO:+0000\r\n\
+        END:STANDARD\r\n\
+        END:VTIMEZONE\r\n",
+    );
+
+    for i in 0..events {
+        feed.push_str(&format!(
+            "BEGIN:VEVENT\r\n\
+            UID:{i}-synthetic@example.com\r\n\
+            DTSTAMP:20200730T163634Z\r\n\
+            DTSTART;TZID=Europe/London:20200722T140000\r\n\
+            DTEND;TZID=Europe/London:20200722T150000\r\n\
+            RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=WE\r\n\
+            SUMMARY;LANGUAGE=en:Synthetic event number {i}\\, with escapes\\;\r\n\
+            DESCRIPTION:This is a long description for event {i} which has been \r\n \
+            folded over several lines\\, as is common for real world feeds \r\n \
+            that contain lots of text.\r\n\
+            ORGANIZER;CN=\"Organizer, Charlie\":mailto:charlie@example.com\r\n\
+            ATTENDEE;CUTYPE=INDIVIDUAL;ROLE=REQ-PARTICIPANT;PARTSTAT=NEEDS-ACTION;\r\n \
+            RSVP=TRUE;CN=Alice:mailto:alice@example.com\r\n\
+            ATTENDEE;CUTYPE=INDIVIDUAL;ROLE=OPT-PARTICIPANT;PARTSTAT=ACCEPTED;CN=\r\n \
+            \"Bob ^'The Builder^'\":mailto:bob@example.com\r\n\
+            CATEGORIES:WORK,MEETING,SYNTHETIC\r\n\
+            LOCATION:Room {i}\r\n\
+            URL:https://example.com/events/{i}\r\n\
+            SEQUENCE:0\r\n\
+            X-CUSTOM-PROPERTY;X-PARAM=a,b,c:custom value\r\n\
+            BEGIN:VALARM\r\n\
+            ACTION:DISPLAY\r\n\
+            DESCRIPTION:Reminder\r\n\
+            TRIGGER:-PT15M\r\n\
+            END:VALARM\r\n\
+            END:VEVENT\r\n",
+            i = i
+        ));
+    }
+
+    feed.push_str("END:VCALENDAR\r\n");
+    feed
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+
+    for &events in &[100, 2_000] {
+        let feed = synthetic_feed(events);
+        group.throughput(Throughput::Bytes(feed.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("strict", events), &feed, |b, feed| {
+            b.iter(|| Component::from_str_to_stream(feed).unwrap())
+        });
+
+        group.bench_with_input(BenchmarkId::new("lenient", events), &feed, |b, feed| {
+            b.iter(|| Component::from_str_lenient(feed))
+        });
+
+        group.bench_with_input(BenchmarkId::new("reader", events), &feed, |b, feed| {
+            b.iter(|| {
+                ComponentReader::new(Cursor::new(feed.as_bytes()))
+                    .stream_sub_components(true)
+                    .collect::<Result<Vec<_>, _>>()
+                    .unwrap()
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("lossless", events), &feed, |b, feed| {
+            b.iter(|| LosslessComponent::parse(feed).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
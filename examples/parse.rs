@@ -13,9 +13,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut data = String::new();
     file.read_to_string(&mut data)?;
 
-    let re = regex::Regex::new(r"\n[\t ]+").unwrap();
-    let _data = re.replace_all(&data, "");
-
     let components = parser::Component::from_str_to_stream(&data)?;
     for comp in components {
         let calendar: VCalendar = comp.try_into()?;
@@ -31,8 +28,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             let times = event
                 .recur_iter(&calendar)?
                 .take(10)
-                .map(|(d, _)| d)
-                .collect::<Vec<_>>();
+                .map(|instance| instance.map(|(d, _)| d))
+                .collect::<Result<Vec<_>, _>>()?;
             // let times = event
             //     .base_event
             //     .recur_iter(&calendar)?
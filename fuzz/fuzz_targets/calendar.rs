@@ -0,0 +1,45 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use ics_parser::{components::VCalendar, limits::ParseLimits, parser::Component};
+
+/// How many occurrences of each event to expand, as rules may recur forever.
+const MAX_OCCURRENCES: usize = 100;
+
+/// How many periods a rule may step through between occurrences. This is
+/// lower than the default so that each input stays quick to run.
+const MAX_RECURRENCE_STEPS: usize = 10_000;
+
+fuzz_target!(|data: &str| {
+    let limits = ParseLimits {
+        max_recurrence_steps: MAX_RECURRENCE_STEPS,
+        ..Default::default()
+    };
+
+    let components = match Component::from_str_to_stream_with_limits(data, &limits) {
+        Ok(components) => components,
+        Err(_) => return,
+    };
+
+    for component in components {
+        let calendar = match VCalendar::try_from_with_limits(component, &limits) {
+            Ok(calendar) => calendar,
+            Err(_) => continue,
+        };
+
+        for collection in calendar.events.values() {
+            if let Ok(iter) = collection.recur_iter(&calendar) {
+                iter.take(MAX_OCCURRENCES).for_each(drop);
+            }
+
+            let event = &collection.base_event;
+            if let Ok(iter) = event.recur_iter(&calendar) {
+                iter.take(MAX_OCCURRENCES).for_each(drop);
+            }
+            if let Ok(iter) = event.recur_period_iter(&calendar) {
+                iter.take(MAX_OCCURRENCES).for_each(drop);
+            }
+        }
+    }
+});
@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use ics_parser::{lossless::LosslessComponent, parser::Component};
+
+fuzz_target!(|data: &str| {
+    let _ = Component::from_str_to_stream(data);
+    let _ = Component::from_str_lenient(data);
+    let _ = LosslessComponent::parse(data);
+});
@@ -0,0 +1,43 @@
+#![no_main]
+
+use std::str::FromStr;
+
+use chrono::{FixedOffset, NaiveDate, NaiveDateTime};
+use libfuzzer_sys::fuzz_target;
+
+use ics_parser::{limits::ParseLimits, property::RecurRule};
+
+/// How many occurrences to expand, as rules may recur forever.
+const MAX_OCCURRENCES: usize = 100;
+
+/// How many periods a rule may step through between occurrences. This is
+/// lower than the default so that each input stays quick to run.
+const MAX_RECURRENCE_STEPS: usize = 10_000;
+
+fuzz_target!(|data: &str| {
+    let rule = match RecurRule::from_str(data) {
+        Ok(rule) => rule,
+        Err(_) => return,
+    };
+
+    let limits = ParseLimits {
+        max_recurrence_steps: MAX_RECURRENCE_STEPS,
+        ..Default::default()
+    };
+
+    // Use a leap day so that month and year expansions hit invalid dates.
+    let start: NaiveDateTime = NaiveDate::from_ymd_opt(2020, 2, 29)
+        .and_then(|d| d.and_hms_opt(12, 30, 0))
+        .unwrap();
+
+    if let Ok(iter) = rule.from_date(start, &FixedOffset::east_opt(3600).unwrap(), &limits) {
+        iter.take(MAX_OCCURRENCES).for_each(drop);
+    }
+
+    let rdates: Vec<NaiveDate> = Vec::new();
+    let exdates: &[NaiveDate] = &[];
+    let utc = FixedOffset::east_opt(0).unwrap();
+    if let Ok(iter) = rule.from_date_with_extras(start.date(), rdates, exdates, utc, &limits) {
+        iter.take(MAX_OCCURRENCES).for_each(drop);
+    }
+});
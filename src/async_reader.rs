@@ -0,0 +1,231 @@
+//! Reading components from an async stream, c.f. [`crate::reader`].
+
+use std::{
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use futures_core::Stream;
+use tokio::io::AsyncBufRead;
+
+use crate::{
+    error::Error,
+    limits::ParseLimits,
+    parser::Component,
+    reader::{StreamItem, StreamState},
+};
+
+/// Reads components from an async stream, one content line at a time.
+///
+/// This is a [`Stream`] of components, each of which is returned as soon as it
+/// has been fully read, so that e.g. the events in a calendar can be processed
+/// while it is still being downloaded (see
+/// [`stream_sub_components`](AsyncComponentReader::stream_sub_components)).
+///
+/// As with [`ComponentReader`](crate::reader::ComponentReader), only the
+/// component currently being read is held in memory.
+pub struct AsyncComponentReader<R> {
+    reader: R,
+    state: StreamState,
+    buffer: Vec<u8>,
+    finished: bool,
+    failed: bool,
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncComponentReader<R> {
+    pub fn new(reader: R) -> AsyncComponentReader<R> {
+        AsyncComponentReader {
+            reader,
+            state: StreamState::default(),
+            buffer: Vec::new(),
+            finished: false,
+            failed: false,
+        }
+    }
+
+    /// Set the limits on the data that will be accepted, c.f.
+    /// [`ParseLimits`].
+    pub fn limits(mut self, limits: ParseLimits) -> Self {
+        self.state.set_limits(limits);
+        self
+    }
+
+    /// Whether to return the direct sub-components of each top-level
+    /// component as soon as they have been read, rather than as part of the
+    /// top-level component.
+    ///
+    /// When used as a [`Stream`] the sub-components are returned before
+    /// their (now empty) parent. Use
+    /// [`next_item`](AsyncComponentReader::next_item) to tell them apart.
+    pub fn stream_sub_components(mut self, stream: bool) -> Self {
+        self.state.set_stream_sub_components(stream);
+        self
+    }
+
+    /// Read the next item from the stream, returning `None` once the end of
+    /// the stream has been reached.
+    pub async fn next_item(&mut self) -> Result<Option<StreamItem>, Error> {
+        std::future::poll_fn(|cx| self.poll_item(cx)).await
+    }
+
+    fn poll_item(&mut self, cx: &mut Context<'_>) -> Poll<Result<Option<StreamItem>, Error>> {
+        while !self.finished {
+            let item = if ready!(self.poll_line(cx))? {
+                self.finished = true;
+                self.state.push(None)?
+            } else {
+                self.state.push(Some(&self.buffer))?
+            };
+            self.buffer.clear();
+
+            if item.is_some() {
+                return Poll::Ready(Ok(item));
+            }
+        }
+
+        self.state.finish()?;
+
+        Poll::Ready(Ok(None))
+    }
+
+    /// Read the next physical line into the buffer, returning whether the end
+    /// of the stream has been reached instead.
+    ///
+    /// The buffer is only cleared once the line has been handled, so this can
+    /// be safely called again if the stream isn't ready.
+    fn poll_line(&mut self, cx: &mut Context<'_>) -> Poll<Result<bool, Error>> {
+        let max_read = self.state.max_read();
+
+        loop {
+            let available = ready!(Pin::new(&mut self.reader).poll_fill_buf(cx))?;
+            if available.is_empty() {
+                return Poll::Ready(Ok(self.buffer.is_empty()));
+            }
+
+            let (mut used, mut done) = match available.iter().position(|&b| b == b'\n') {
+                Some(idx) => (idx + 1, true),
+                None => (available.len(), false),
+            };
+
+            // Don't read any more of a line than we'd accept.
+            let remaining = max_read - self.buffer.len();
+            if used > remaining {
+                used = remaining;
+                done = false;
+            }
+
+            self.buffer.extend_from_slice(&available[..used]);
+            Pin::new(&mut self.reader).consume(used);
+
+            if done || self.buffer.len() >= max_read {
+                return Poll::Ready(Ok(false));
+            }
+        }
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> Stream for AsyncComponentReader<R> {
+    type Item = Result<Component<'static>, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        // We don't try and carry on after an error.
+        if this.failed {
+            return Poll::Ready(None);
+        }
+
+        let result = ready!(this.poll_item(cx))
+            .map(|item| item.map(StreamItem::into_component))
+            .transpose();
+        this.failed = matches!(result, Some(Err(_)));
+
+        Poll::Ready(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncWriteExt, BufReader};
+
+    use super::*;
+    use crate::reader::ComponentReader;
+
+    async fn next<S: Stream + Unpin>(stream: &mut S) -> Option<S::Item> {
+        std::future::poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)).await
+    }
+
+    #[tokio::test]
+    async fn read_matches_blocking() {
+        let input = include_str!("../example.ics");
+
+        let mut reader = AsyncComponentReader::new(input.as_bytes()).stream_sub_components(true);
+        let mut components = Vec::new();
+        while let Some(component) = next(&mut reader).await {
+            components.push(component.unwrap());
+        }
+
+        let expected = ComponentReader::new(input.as_bytes())
+            .stream_sub_components(true)
+            .map(|item| item.map(StreamItem::into_component))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(components, expected);
+    }
+
+    #[tokio::test]
+    async fn streams_before_end() {
+        let (mut writer, reader) = tokio::io::duplex(1024);
+        let mut reader =
+            AsyncComponentReader::new(BufReader::new(reader)).stream_sub_components(true);
+
+        writer
+            .write_all(
+                b"BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:1\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\n",
+            )
+            .await
+            .unwrap();
+
+        match reader.next_item().await.unwrap() {
+            Some(StreamItem::SubComponent(event)) => assert_eq!(event.properties[0].value, "1"),
+            item => panic!("unexpected item: {:?}", item),
+        }
+
+        writer
+            .write_all(b"UID:2\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n")
+            .await
+            .unwrap();
+        drop(writer);
+
+        match reader.next_item().await.unwrap() {
+            Some(StreamItem::SubComponent(event)) => assert_eq!(event.properties[0].value, "2"),
+            item => panic!("unexpected item: {:?}", item),
+        }
+        assert!(matches!(
+            reader.next_item().await.unwrap(),
+            Some(StreamItem::Component(_))
+        ));
+        assert!(reader.next_item().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn errors() {
+        let mut reader = AsyncComponentReader::new(&b"BEGIN:A\r\nX:1\r\nEND:A\r\nEND:B\r\n"[..]);
+        assert!(next(&mut reader).await.unwrap().is_ok());
+        assert!(next(&mut reader).await.unwrap().is_err());
+        assert!(next(&mut reader).await.is_none());
+
+        let limits = ParseLimits {
+            max_line_length: 10,
+            ..Default::default()
+        };
+        let mut reader = AsyncComponentReader::new(
+            &b"BEGIN:A\r\nX:a\xc3\xa9\xc3\xa9\xc3\xa9\xc3\xa9\xc3\xa9\xc3\xa9\r\nEND:A\r\n"[..],
+        )
+        .limits(limits);
+        let err = next(&mut reader).await.unwrap().unwrap_err();
+        assert!(matches!(err, Error::LimitExceeded { .. }), "{:?}", err);
+        assert_eq!(err.context().span.unwrap().start.line, 2);
+    }
+}
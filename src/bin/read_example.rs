@@ -1,21 +1,12 @@
-use std::{
-    convert::TryInto,
-    io::{stdin, Read},
-};
+use std::{convert::TryInto, io::stdin};
 
-use ics_parser::{components::VCalendar, parser};
+use ics_parser::{components::VCalendar, reader::ComponentReader};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut file = stdin();
-    let mut data = String::new();
-    file.read_to_string(&mut data)?;
+    let file = stdin();
 
-    // let re = regex::Regex::new(r"\n[\t ]+").unwrap();
-    // let _data = re.replace_all(&data, "");
-
-    let components = parser::Component::from_str_to_stream(&data)?;
-    for comp in components {
-        let calendar: VCalendar = comp.try_into()?;
+    for item in ComponentReader::new(file.lock()) {
+        let calendar: VCalendar = item?.into_component().try_into()?;
 
         println!("Found {} events", calendar.events.len());
     }
@@ -1,14 +1,16 @@
 use crate::{
-    parser::{self, Component},
+    color::Color,
+    error::{Error, ErrorContext},
+    limits::ParseLimits,
+    parser,
     property::{
-        DateDateTimeOrPeriod, DateOrDateTime, EndCondition, IcalDateTime, Offseter, Property,
-        RecurRule, ToNaive, ToNaivePeriod,
+        CalendarUser, ClassEnum, DateDateTimeOrPeriod, DateOrDateTime, EndCondition, IcalDateTime,
+        Offseter, Property, RecurRule, StatusEnum, ToNaive, ToNaivePeriod,
     },
 };
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::{TryFrom, TryInto};
 
-use anyhow::{bail, ensure, format_err, Context, Error};
 use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
 use itertools::Itertools;
 
@@ -22,31 +24,104 @@ pub struct VCalendar {
     pub timezones: Vec<VTimeZone>,
 
     pub properties: Vec<Property>,
+
+    /// The limits the calendar was checked against, which also bound the
+    /// expansion of its recurrence rules.
+    pub limits: ParseLimits,
 }
 
 impl VCalendar {
+    /// The name of the calendar (RFC 7986), preferring the one without a
+    /// `LANGUAGE` if there are several.
+    pub fn name(&self) -> Option<&str> {
+        let mut names = self
+            .properties
+            .iter()
+            .filter_map(|property| match property {
+                Property::Name(value) => Some(value),
+                _ => None,
+            });
+
+        names
+            .clone()
+            .find(|value| value.parameters.get_language().is_none())
+            .or_else(|| names.next())
+            .map(|value| value.value.as_str())
+    }
+
+    /// The description of the calendar (RFC 7986).
+    pub fn description(&self) -> Option<&str> {
+        self.properties.iter().find_map(|property| match property {
+            Property::Description(value) => Some(value.value.as_str()),
+            _ => None,
+        })
+    }
+
+    /// The color to display the calendar with (RFC 7986).
+    pub fn color(&self) -> Option<&Color> {
+        self.properties.iter().find_map(|property| match property {
+            Property::Color(value) => Some(&value.value),
+            _ => None,
+        })
+    }
+
+    /// How often the calendar should be refreshed (RFC 7986).
+    pub fn refresh_interval(&self) -> Option<Duration> {
+        self.properties.iter().find_map(|property| match property {
+            Property::RefreshInterval(value) => Some(value.value),
+            _ => None,
+        })
+    }
+
     pub fn get_time(&self, date: &IcalDateTime) -> Result<DateTime<FixedOffset>, Error> {
         match *date {
-            IcalDateTime::Local(_) => bail!("Local time"),
+            IcalDateTime::Local(_) => Err(Error::timezone_mismatch("Local time")),
             IcalDateTime::Utc(d) => Ok(d.into()),
             IcalDateTime::TZ { date, ref tzid } => {
                 let tz = if let Some(tz) = self.timezones.iter().find(|tz| &tz.id == tzid) {
                     tz.clone()
                 } else {
-                    bail!("Referenced timezone {} not in calendar", tzid);
+                    return Err(Error::unknown_timezone(tzid));
                 };
 
-                Ok(tz.to_instance(date))
+                tz.to_instance(date)
             }
         }
     }
 }
 
-impl TryFrom<parser::Component> for VCalendar {
+impl TryFrom<parser::Component<'_>> for VCalendar {
     type Error = Error;
 
+    /// Converts the component, checking it against the default
+    /// [`ParseLimits`].
     fn try_from(component: parser::Component) -> Result<Self, Self::Error> {
-        ensure!(component.name.to_ascii_uppercase() == "VCALENDAR");
+        VCalendar::try_from_with_limits(component, &ParseLimits::default())
+    }
+}
+
+impl VCalendar {
+    /// Convert the component, failing if it exceeds the given limits, e.g.
+    /// if it has a binary attachment that would be too large once decoded.
+    pub fn try_from_with_limits(
+        component: parser::Component,
+        limits: &ParseLimits,
+    ) -> Result<Self, Error> {
+        let context = ErrorContext::for_component(&component);
+
+        limits
+            .check_tree(&component)
+            .and_then(|_| VCalendar::from_component(component, limits))
+            .map_err(|e| e.within(&context))
+    }
+
+    fn from_component(component: parser::Component, limits: &ParseLimits) -> Result<Self, Error> {
+        if component.name.to_ascii_uppercase() != "VCALENDAR" {
+            return Err(Error::invalid_component(format!(
+                "Expected VCALENDAR, found {}",
+                component.name
+            )));
+        }
 
         let mut vevents = Vec::new();
         let mut timezones = Vec::new();
@@ -58,7 +133,9 @@ impl TryFrom<parser::Component> for VCalendar {
                     vevents.push(component);
                 }
                 "VTIMEZONE" => {
-                    timezones.push(component.try_into().with_context(|| "parsing VTIMEZONE")?)
+                    let mut timezone: VTimeZone = component.try_into()?;
+                    timezone.limits = *limits;
+                    timezones.push(timezone);
                 }
                 _ => {} // TODO: Handle other components
             }
@@ -79,17 +156,17 @@ impl TryFrom<parser::Component> for VCalendar {
         }
 
         let mut vcalendar = VCalendar {
-            prodid: prodid.ok_or_else(|| format_err!("Missing PRODID field in offset rule"))?,
-            version: version.ok_or_else(|| format_err!("Missing VERSION field in offset rule"))?,
+            prodid: prodid.ok_or_else(|| Error::missing_property("PRODID"))?,
+            version: version.ok_or_else(|| Error::missing_property("VERSION"))?,
             events: BTreeMap::new(),
             timezones,
             properties,
+            limits: *limits,
         };
 
         let mut events: BTreeMap<String, Vec<VEvent>> = BTreeMap::new();
         for component in vevents {
-            let event = VEvent::try_from_component(component, &vcalendar)
-                .with_context(|| "parsing VEVENT")?;
+            let event = VEvent::try_from_component(component, &vcalendar)?;
             events.entry(event.uid.clone()).or_default().push(event);
         }
 
@@ -102,7 +179,7 @@ impl TryFrom<parser::Component> for VCalendar {
     }
 }
 
-//// Purpose: Provide a grouping of component properties that describe an event.
+/// Purpose: Provide a grouping of component properties that describe an event.
 ///
 /// Description:  A "VEVENT" calendar component is a grouping of component
 /// properties, possibly including "VALARM" calendar components, that represents
@@ -145,6 +222,13 @@ pub struct VEvent {
     pub summary: Option<String>,
     pub description: Option<String>,
     pub location: Option<String>,
+
+    /// The status as given, which may not be one allowed for events (e.g.
+    /// `NEEDS-ACTION` copied from a `VTODO`), c.f.
+    /// [`StatusEnum::is_valid_for`].
+    pub status: Option<StatusEnum>,
+    pub class: Option<ClassEnum>,
+    pub geo: Option<(f64, f64)>,
     pub sequence: Option<u32>,
     pub recur: Option<RecurRule>,
     pub timings: Option<Timings>,
@@ -179,171 +263,257 @@ impl VEvent {
         )
     }
 
+    /// The attendees of the event, in the order they were given.
+    pub fn attendees(&self) -> impl Iterator<Item = CalendarUser<'_>> {
+        self.properties
+            .iter()
+            .filter_map(|property| match property {
+                Property::Attendee(value) => Some(CalendarUser::new(value)),
+                _ => None,
+            })
+    }
+
+    /// The organizer of the event, if it has one.
+    pub fn organizer(&self) -> Option<CalendarUser<'_>> {
+        self.properties.iter().find_map(|property| match property {
+            Property::Organizer(value) => Some(CalendarUser::new(value)),
+            _ => None,
+        })
+    }
+
+    /// Add the details of the event to the error.
+    fn annotate(&self, err: Error) -> Error {
+        err.with_component("VEVENT").with_uid(Some(&self.uid))
+    }
+
     /// Get an iterator over all instances of the event, with timezone
     /// information.
     ///
     /// This will fail if it is a floating event or if there is a referenced
-    /// timezone that can't be found in the given `VCalendar`.
+    /// timezone that can't be found in the given `VCalendar`. The iterator
+    /// returns an error if the recurrence rule exceeds the calendar's
+    /// [`ParseLimits`], e.g. if it steps through too many periods without
+    /// finding an occurrence.
     ///
     /// Note: This may be an infinite iterator if the event recurs forever.
     pub fn recur_iter<'a>(
         &'a self,
         calendar: &'a VCalendar,
-    ) -> Result<impl Iterator<Item = DateTime<FixedOffset>> + 'a, Error> {
+    ) -> Result<impl Iterator<Item = Result<DateTime<FixedOffset>, Error>> + 'a, Error> {
+        let limits = &calendar.limits;
+
         let recur = if let Some(recur) = &self.recur {
             recur
         } else {
             return match &self.timings {
-                Some(Timings::Utc(inner)) => Ok(Box::new(std::iter::once(
-                    FixedOffset::east(0).from_utc_datetime(&inner.start.naive_utc()),
-                ))
-                    as Box<dyn Iterator<Item = DateTime<FixedOffset>>>),
+                Some(Timings::Utc(inner)) => Ok(Box::new(std::iter::once(Ok(
+                    FixedOffset::east(0).from_utc_datetime(&inner.start.naive_utc())
+                )))
+                    as Box<dyn Iterator<Item = Result<DateTime<FixedOffset>, Error>>>),
                 Some(Timings::Tz { tzid, inner }) => {
                     let tz = if let Some(tz) = calendar.timezones.iter().find(|tz| &tz.id == tzid) {
                         tz.clone()
                     } else {
-                        bail!("Referenced timezone {} not in calendar", tzid);
+                        return Err(self.annotate(Error::unknown_timezone(tzid)));
                     };
 
-                    Ok(Box::new(std::iter::once(tz.to_instance(inner.start)))
-                        as Box<dyn Iterator<Item = DateTime<FixedOffset>>>)
+                    let start = tz.to_instance(inner.start).map_err(|e| self.annotate(e))?;
+
+                    Ok(Box::new(std::iter::once(Ok(start)))
+                        as Box<
+                            dyn Iterator<Item = Result<DateTime<FixedOffset>, Error>>,
+                        >)
                 }
-                Some(Timings::PerioidUtc(inner)) => Ok(Box::new(std::iter::once(
+                Some(Timings::PerioidUtc(inner)) => Ok(Box::new(std::iter::once(Ok(
                     FixedOffset::east(0).from_utc_datetime(&inner.start.start.naive_utc()),
-                ))
-                    as Box<dyn Iterator<Item = DateTime<FixedOffset>>>),
+                )))
+                    as Box<dyn Iterator<Item = Result<DateTime<FixedOffset>, Error>>>),
                 Some(Timings::PerioidTz { tzid, inner }) => {
                     let tz = if let Some(tz) = calendar.timezones.iter().find(|tz| &tz.id == tzid) {
                         tz.clone()
                     } else {
-                        bail!("Referenced timezone {} not in calendar", tzid);
+                        return Err(self.annotate(Error::unknown_timezone(tzid)));
                     };
 
-                    Ok(Box::new(std::iter::once(tz.to_instance(inner.start.start)))
-                        as Box<dyn Iterator<Item = DateTime<FixedOffset>>>)
+                    let start = tz
+                        .to_instance(inner.start.start)
+                        .map_err(|e| self.annotate(e))?;
+
+                    Ok(Box::new(std::iter::once(Ok(start)))
+                        as Box<
+                            dyn Iterator<Item = Result<DateTime<FixedOffset>, Error>>,
+                        >)
                 }
-                _ => bail!("Not a datetime event"),
+                _ => return Err(self.annotate(Error::invalid_component("Not a datetime event"))),
             };
         };
 
-        match &self.timings {
-            Some(Timings::Utc(inner)) => Ok(Box::new(
+        let iter = match &self.timings {
+            Some(Timings::Utc(inner)) => Box::new(
                 recur
                     .from_date_with_extras(
                         inner.start,
                         inner.rdates.iter().cloned(),
                         &inner.exdates,
                         FixedOffset::east(0),
+                        limits,
                     )
-                    .map(|d| d.into()),
+                    .map_err(|e| self.annotate(e))?
+                    .map(|d| d.map(Into::into)),
             )
-                as Box<dyn Iterator<Item = DateTime<FixedOffset>>>),
+                as Box<dyn Iterator<Item = Result<DateTime<FixedOffset>, Error>>>,
             Some(Timings::Tz { tzid, inner }) => {
                 let tz = if let Some(tz) = calendar.timezones.iter().find(|tz| &tz.id == tzid) {
                     tz.clone()
                 } else {
-                    bail!("Referenced timezone {} not in calendar", tzid);
+                    return Err(self.annotate(Error::unknown_timezone(tzid)));
                 };
 
-                Ok(Box::new(recur.from_naive_date_with_extras(
-                    inner.start,
-                    inner.rdates.iter().cloned(),
-                    &inner.exdates,
-                    tz,
-                ))
-                    as Box<dyn Iterator<Item = DateTime<FixedOffset>>>)
+                // Make sure the start can be converted, so that an invalid
+                // timezone is reported rather than giving no occurrences.
+                tz.to_instance(inner.start).map_err(|e| self.annotate(e))?;
+
+                Box::new(
+                    recur
+                        .from_naive_date_with_extras(
+                            inner.start,
+                            inner.rdates.iter().cloned(),
+                            &inner.exdates,
+                            tz,
+                            limits,
+                        )
+                        .map_err(|e| self.annotate(e))?,
+                ) as Box<dyn Iterator<Item = Result<DateTime<FixedOffset>, Error>>>
             }
-            Some(Timings::PerioidUtc(inner)) => Ok(Box::new(
+            Some(Timings::PerioidUtc(inner)) => Box::new(
                 recur
                     .from_date_with_extras(
                         inner.start.start,
                         inner.rdates.iter().map(|d| d.start),
                         &inner.exdates,
                         FixedOffset::east(0),
+                        limits,
                     )
-                    .map(|d| d.into()),
+                    .map_err(|e| self.annotate(e))?
+                    .map(|d| d.map(Into::into)),
             )
-                as Box<dyn Iterator<Item = DateTime<FixedOffset>>>),
+                as Box<dyn Iterator<Item = Result<DateTime<FixedOffset>, Error>>>,
             Some(Timings::PerioidTz { tzid, inner }) => {
                 let tz = if let Some(tz) = calendar.timezones.iter().find(|tz| &tz.id == tzid) {
                     tz.clone()
                 } else {
-                    bail!("Referenced timezone {} not in calendar", tzid);
+                    return Err(self.annotate(Error::unknown_timezone(tzid)));
                 };
 
-                Ok(Box::new(recur.from_naive_date_with_extras(
-                    inner.start.start,
-                    inner.rdates.iter().map(|d| d.start),
-                    &inner.exdates,
-                    tz,
-                ))
-                    as Box<dyn Iterator<Item = DateTime<FixedOffset>>>)
+                tz.to_instance(inner.start.start)
+                    .map_err(|e| self.annotate(e))?;
+
+                Box::new(
+                    recur
+                        .from_naive_date_with_extras(
+                            inner.start.start,
+                            inner.rdates.iter().map(|d| d.start),
+                            &inner.exdates,
+                            tz,
+                            limits,
+                        )
+                        .map_err(|e| self.annotate(e))?,
+                ) as Box<dyn Iterator<Item = Result<DateTime<FixedOffset>, Error>>>
             }
-            _ => bail!("Not a datetime event"),
-        }
+            _ => return Err(self.annotate(Error::invalid_component("Not a datetime event"))),
+        };
+
+        Ok(Box::new(iter.map(move |d| d.map_err(|e| self.annotate(e))))
+            as Box<dyn Iterator<Item = _>>)
     }
 
+    /// Get an iterator over all instances of the event as periods, failing
+    /// (or returning an error) as [`VEvent::recur_iter`].
     pub fn recur_period_iter<'a>(
         &'a self,
         calendar: &'a VCalendar,
-    ) -> Result<impl Iterator<Item = ToNaivePeriod<DateTime<FixedOffset>>> + 'a, Error> {
+    ) -> Result<impl Iterator<Item = Result<ToNaivePeriod<DateTime<FixedOffset>>, Error>> + 'a, Error>
+    {
+        let limits = &calendar.limits;
+
         let recur = if let Some(recur) = &self.recur {
             recur
         } else {
             return match &self.timings {
-                Some(Timings::PerioidUtc(inner)) => Ok(Box::new(std::iter::once(ToNaivePeriod {
-                    duration: inner.start.duration,
-                    start: FixedOffset::east(0).from_utc_datetime(&inner.start.start.naive_utc()),
-                }))
-                    as Box<dyn Iterator<Item = _>>),
+                Some(Timings::PerioidUtc(inner)) => {
+                    Ok(Box::new(std::iter::once(Ok(ToNaivePeriod {
+                        duration: inner.start.duration,
+                        start: FixedOffset::east(0)
+                            .from_utc_datetime(&inner.start.start.naive_utc()),
+                    }))) as Box<dyn Iterator<Item = _>>)
+                }
                 Some(Timings::PerioidTz { tzid, inner }) => {
                     let tz = if let Some(tz) = calendar.timezones.iter().find(|tz| &tz.id == tzid) {
                         tz.clone()
                     } else {
-                        bail!("Referenced timezone {} not in calendar", tzid);
+                        return Err(self.annotate(Error::unknown_timezone(tzid)));
                     };
 
-                    Ok(Box::new(std::iter::once(ToNaivePeriod {
+                    let start = tz
+                        .to_instance(inner.start.start)
+                        .map_err(|e| self.annotate(e))?;
+
+                    Ok(Box::new(std::iter::once(Ok(ToNaivePeriod {
                         duration: inner.start.duration,
-                        start: tz.to_instance(inner.start.start),
-                    })) as Box<dyn Iterator<Item = _>>)
+                        start,
+                    }))) as Box<dyn Iterator<Item = _>>)
                 }
-                _ => bail!("Not a datetime event"),
+                _ => return Err(self.annotate(Error::invalid_component("Not a datetime event"))),
             };
         };
 
-        match &self.timings {
-            Some(Timings::PerioidUtc(inner)) => Ok(Box::new(
-                recur
-                    .from_date_with_extras(
-                        inner.start,
-                        inner.rdates.iter().cloned(),
-                        &inner.exdates,
-                        FixedOffset::east(0),
-                    )
-                    .map(|d| ToNaivePeriod {
-                        duration: d.duration,
-                        start: d.start.into(),
-                    }),
-            ) as Box<dyn Iterator<Item = _>>),
-            Some(Timings::PerioidTz { tzid, inner }) => {
-                let tz = if let Some(tz) = calendar.timezones.iter().find(|tz| &tz.id == tzid) {
-                    tz.clone()
-                } else {
-                    bail!("Referenced timezone {} not in calendar", tzid);
-                };
-
-                Ok(
-                    Box::new(recur.from_naive_date_with_extras::<ToNaivePeriod<DateTime<FixedOffset>>, NaiveDateTime, _, _>(
-                        inner.start.to_naive(),
-                        inner.rdates.iter().map(ToNaive::to_naive),
-                        &inner.exdates,
-                        tz,
-                    )) as Box<dyn Iterator<Item = _>>,
+        let iter: Box<dyn Iterator<Item = Result<ToNaivePeriod<DateTime<FixedOffset>>, Error>>> =
+            match &self.timings {
+                Some(Timings::PerioidUtc(inner)) => Box::new(
+                    recur
+                        .from_date_with_extras(
+                            inner.start,
+                            inner.rdates.iter().cloned(),
+                            &inner.exdates,
+                            FixedOffset::east(0),
+                            limits,
+                        )
+                        .map_err(|e| self.annotate(e))?
+                        .map(|d| {
+                            d.map(|d| ToNaivePeriod {
+                                duration: d.duration,
+                                start: d.start.into(),
+                            })
+                        }),
                 )
-            }
-            _ => bail!("Not a datetime event"),
-        }
+                    as Box<dyn Iterator<Item = _>>,
+                Some(Timings::PerioidTz { tzid, inner }) => {
+                    let tz = if let Some(tz) = calendar.timezones.iter().find(|tz| &tz.id == tzid) {
+                        tz.clone()
+                    } else {
+                        return Err(self.annotate(Error::unknown_timezone(tzid)));
+                    };
+
+                    tz.to_instance(inner.start.start)
+                        .map_err(|e| self.annotate(e))?;
+
+                    Box::new(
+                    recur
+                        .from_naive_date_with_extras::<ToNaivePeriod<DateTime<FixedOffset>>, NaiveDateTime, _, _>(
+                            inner.start.to_naive(),
+                            inner.rdates.iter().map(ToNaive::to_naive),
+                            &inner.exdates,
+                            tz,
+                            limits,
+                        )
+                        .map_err(|e| self.annotate(e))?,
+                ) as Box<dyn Iterator<Item = _>>
+                }
+                _ => return Err(self.annotate(Error::invalid_component("Not a datetime event"))),
+            };
+
+        Ok(Box::new(iter.map(move |d| d.map_err(|e| self.annotate(e))))
+            as Box<dyn Iterator<Item = _>>)
     }
 }
 
@@ -380,10 +550,24 @@ impl VEvent {
     ///
     /// We pass in the calendar mainly so they have access to timezone information.
     fn try_from_component(
-        component: parser::Component,
+        component: parser::Component<'_>,
+        calendar: &VCalendar,
+    ) -> Result<Self, Error> {
+        let context = ErrorContext::for_component(&component);
+
+        VEvent::from_component(component, calendar).map_err(|e| e.within(&context))
+    }
+
+    fn from_component(
+        component: parser::Component<'_>,
         calendar: &VCalendar,
     ) -> Result<Self, Error> {
-        ensure!(component.name.to_ascii_uppercase() == "VEVENT");
+        if component.name.to_ascii_uppercase() != "VEVENT" {
+            return Err(Error::invalid_component(format!(
+                "Expected VEVENT, found {}",
+                component.name
+            )));
+        }
 
         // TODO: Handle sub compontents
 
@@ -399,6 +583,9 @@ impl VEvent {
         let mut summary = None;
         let mut description = None;
         let mut location = None;
+        let mut status = None;
+        let mut class = None;
+        let mut geo = None;
         let mut sequence = None;
 
         let mut properties = Vec::new();
@@ -410,36 +597,47 @@ impl VEvent {
                 Property::UID(value) => uid = Some(value.value),
                 Property::DateTimeStamp(value) => dtstamp = Some(value.value),
                 Property::Start(value) => dtstart = Some(value.value),
-                Property::RecurrenceDateTimes(value) => rdates.push(value.value),
-                Property::ExceptionDateTimes(value) => exdates.push(value.value),
+                Property::RecurrenceDateTimes(value) => rdates.extend(value.value),
+                Property::ExceptionDateTimes(value) => exdates.extend(value.value),
                 Property::Duration(value) => duration = Some(value.value),
                 Property::End(value) => dtend = Some(value.value),
                 Property::RecurrenceID(value) => recur_id = Some(value.value),
                 Property::Summary(value) => summary = Some(value.value),
                 Property::Description(value) => description = Some(value.value),
                 Property::Location(value) => location = Some(value.value),
+                Property::Status(value) => status = Some(value.value),
+                Property::Class(value) => class = Some(value.value),
+                Property::Geo(value) => geo = Some(value.value),
                 Property::SequenceNumber(value) => sequence = Some(value.value),
                 p => properties.push(p),
             }
         }
 
-        let uid = uid.ok_or_else(|| format_err!("Missing UID field in offset rule"))?;
+        let uid = uid.ok_or_else(|| Error::missing_property("UID"))?;
 
         if duration.is_some() && dtend.is_some() {
-            bail!("VEVENT has both DURATION and DTEND");
+            return Err(Error::invalid_component(
+                "VEVENT has both DURATION and DTEND",
+            ));
         }
 
         if let Some(dtend) = dtend {
             if let Some(dtstart) = &dtstart {
                 duration = Some(match (dtstart.clone(), dtend) {
                     (DateOrDateTime::Date(start), DateOrDateTime::Date(end)) => end - start,
-                    (DateOrDateTime::DateTime(start), DateOrDateTime::DateTime(end)) => end
-                        .sub(&start, Some(calendar))
-                        .with_context(|| format!("calculating duration for {}", uid))?,
-                    _ => bail!("VEVENT has different types for DTSTART and DTEND"),
+                    (DateOrDateTime::DateTime(start), DateOrDateTime::DateTime(end)) => {
+                        end.sub(&start, Some(calendar))?
+                    }
+                    _ => {
+                        return Err(Error::invalid_component(
+                            "VEVENT has different types for DTSTART and DTEND",
+                        ))
+                    }
                 });
             } else {
-                bail!("VEVENT has a DTEND without DTSTART")
+                return Err(Error::invalid_component(
+                    "VEVENT has a DTEND without DTSTART",
+                ));
             }
         };
 
@@ -453,13 +651,19 @@ impl VEvent {
             if let Some(dtstart) = &dtstart {
                 recur_offset = Some(match (dtstart.clone(), recur_id) {
                     (DateOrDateTime::Date(start), DateOrDateTime::Date(recur)) => recur - start,
-                    (DateOrDateTime::DateTime(start), DateOrDateTime::DateTime(recur)) => recur
-                        .sub(&start, Some(calendar))
-                        .with_context(|| format!("calculating recur ID offset for {}", uid))?,
-                    _ => bail!("VEVENT has different types for DTSTART and RECURRENCE-ID"),
+                    (DateOrDateTime::DateTime(start), DateOrDateTime::DateTime(recur)) => {
+                        recur.sub(&start, Some(calendar))?
+                    }
+                    _ => {
+                        return Err(Error::invalid_component(
+                            "VEVENT has different types for DTSTART and RECURRENCE-ID",
+                        ))
+                    }
                 });
             } else {
-                bail!("VEVENT has a RECURRENCE-ID without DTSTART")
+                return Err(Error::invalid_component(
+                    "VEVENT has a RECURRENCE-ID without DTSTART",
+                ));
             }
         }
 
@@ -536,11 +740,14 @@ impl VEvent {
 
         Ok(VEvent {
             uid,
-            dtstamp: dtstamp.ok_or_else(|| format_err!("Missing DTSTAMP field in offset rule"))?,
+            dtstamp: dtstamp.ok_or_else(|| Error::missing_property("DTSTAMP"))?,
             recur,
             summary,
             description,
             location,
+            status,
+            class,
+            geo,
             sequence,
             timings,
             properties,
@@ -549,6 +756,12 @@ impl VEvent {
     }
 }
 
+/// Sort the extra dates of a recurrence set, as they are merged with the
+/// (ordered) occurrences of the recurrence rule.
+fn sort_dates<D: PartialOrd>(dates: &mut [D]) {
+    dates.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+}
+
 fn try_tz_to_dates(
     expected_tzid: &str,
     vec: Vec<DateOrDateTime>,
@@ -559,11 +772,11 @@ fn try_tz_to_dates(
         match d {
             DateOrDateTime::DateTime(IcalDateTime::TZ { tzid, date }) => {
                 if tzid != expected_tzid {
-                    bail!("TZ mismatch")
+                    return Err(Error::timezone_mismatch("TZ mismatch"));
                 }
                 dates.push(date)
             }
-            _ => bail!("DateTime mismatch"),
+            _ => return Err(Error::timezone_mismatch("DateTime mismatch")),
         }
     }
 
@@ -590,18 +803,20 @@ fn try_period_tz_to_dates(
         match d {
             DateDateTimeOrPeriod::DateTime(IcalDateTime::TZ { tzid, date }) => {
                 if tzid != expected_tzid {
-                    bail!("TZ mismatch")
+                    return Err(Error::timezone_mismatch("TZ mismatch"));
                 }
                 dates.push(date)
             }
-            _ => bail!("DateTime mismatch"),
+            _ => return Err(Error::timezone_mismatch("DateTime mismatch")),
         }
     }
 
+    sort_dates(&mut dates);
+
     Ok(dates)
 }
 
-fn try_from_period_to_dates<D: TryFrom<DateDateTimeOrPeriod>>(
+fn try_from_period_to_dates<D: TryFrom<DateDateTimeOrPeriod> + PartialOrd>(
     vec: Vec<DateDateTimeOrPeriod>,
 ) -> Result<Vec<D>, D::Error> {
     let mut dates = Vec::with_capacity(vec.len());
@@ -610,15 +825,18 @@ fn try_from_period_to_dates<D: TryFrom<DateDateTimeOrPeriod>>(
         dates.push(d.try_into()?);
     }
 
+    sort_dates(&mut dates);
+
     Ok(dates)
 }
 
-fn try_from_period_to_periods<D: TryFrom<DateDateTimeOrPeriod>>(
+fn try_from_period_to_periods<D>(
     duration: Duration,
     vec: Vec<DateDateTimeOrPeriod>,
 ) -> Result<Vec<ToNaivePeriod<D>>, D::Error>
 where
-    D: ToNaive,
+    D: TryFrom<DateDateTimeOrPeriod> + ToNaive,
+    ToNaivePeriod<D>: PartialOrd,
 {
     let mut dates = Vec::with_capacity(vec.len());
 
@@ -635,6 +853,8 @@ where
         }
     }
 
+    sort_dates(&mut dates);
+
     Ok(dates)
 }
 
@@ -651,30 +871,32 @@ fn try_tz_from_period_to_periods(
                 match period.start {
                     IcalDateTime::TZ { date, tzid } => {
                         if tzid != expected_tzid {
-                            bail!("TZ mismatch")
+                            return Err(Error::timezone_mismatch("TZ mismatch"));
                         }
                         dates.push(ToNaivePeriod {
                             start: date,
                             duration: period.duration,
                         })
                     }
-                    _ => bail!("DateTime mismatch"),
+                    _ => return Err(Error::timezone_mismatch("DateTime mismatch")),
                 };
             }
 
             DateDateTimeOrPeriod::DateTime(IcalDateTime::TZ { tzid, date }) => {
                 if tzid != expected_tzid {
-                    bail!("TZ mismatch")
+                    return Err(Error::timezone_mismatch("TZ mismatch"));
                 }
                 dates.push(ToNaivePeriod {
                     start: date,
                     duration,
                 })
             }
-            _ => bail!("DateTime mismatch"),
+            _ => return Err(Error::timezone_mismatch("DateTime mismatch")),
         }
     }
 
+    sort_dates(&mut dates);
+
     Ok(dates)
 }
 
@@ -690,17 +912,30 @@ pub struct OffsetRule {
     pub properties: Vec<Property>,
 }
 
-impl TryFrom<parser::Component> for OffsetRule {
+impl TryFrom<parser::Component<'_>> for OffsetRule {
     type Error = Error;
 
     fn try_from(component: parser::Component) -> Result<Self, Self::Error> {
-        ensure!(
-            &component.name.to_ascii_uppercase() == "DAYLIGHT"
-                || &component.name.to_ascii_uppercase() == "STANDARD"
-        );
+        let context = ErrorContext::for_component(&component);
+
+        OffsetRule::from_component(component).map_err(|e| e.within(&context))
+    }
+}
+
+impl OffsetRule {
+    fn from_component(component: parser::Component) -> Result<Self, Error> {
+        let name = component.name.to_ascii_uppercase();
+        if name != "DAYLIGHT" && name != "STANDARD" {
+            return Err(Error::invalid_component(format!(
+                "Expected DAYLIGHT or STANDARD, found {}",
+                component.name
+            )));
+        }
 
         if !component.sub_components.is_empty() {
-            bail!("Neither DAYLIGHT nor STANDARD can have sub components");
+            return Err(Error::invalid_component(
+                "Neither DAYLIGHT nor STANDARD can have sub components",
+            ));
         }
 
         let mut offset_from = None;
@@ -723,41 +958,46 @@ impl TryFrom<parser::Component> for OffsetRule {
                     if let DateOrDateTime::DateTime(IcalDateTime::Local(datetime)) = value.value {
                         start = Some(datetime)
                     } else {
-                        bail!("Invalid timezone start time, must be local time")
+                        return Err(Error::invalid_value(
+                            "Invalid timezone start time, must be local time",
+                        )
+                        .with_property("DTSTART"));
                     }
                 }
                 Property::RecurrenceRule(value) => recur = Some(value.value),
                 Property::TimeZoneName(value) => name = Some(value.value),
                 Property::RecurrenceDateTimes(value) => {
-                    if let DateDateTimeOrPeriod::DateTime(IcalDateTime::Local(d)) = value.value {
-                        rdates.push(d)
-                    } else {
-                        bail!(
-                            "Unexpected type for RDATE in {}",
-                            component.name.to_ascii_uppercase()
-                        )
+                    for value in value.value {
+                        if let DateDateTimeOrPeriod::DateTime(IcalDateTime::Local(d)) = value {
+                            rdates.push(d)
+                        } else {
+                            return Err(Error::invalid_value("Unexpected type for RDATE")
+                                .with_property("RDATE"));
+                        }
                     }
                 }
                 Property::ExceptionDateTimes(value) => {
-                    if let DateOrDateTime::DateTime(IcalDateTime::Local(d)) = value.value {
-                        exdates.push(d)
-                    } else {
-                        bail!(
-                            "Unexpected type for EXDATE in {}",
-                            component.name.to_ascii_uppercase()
-                        )
+                    for value in value.value {
+                        if let DateOrDateTime::DateTime(IcalDateTime::Local(d)) = value {
+                            exdates.push(d)
+                        } else {
+                            return Err(Error::invalid_value("Unexpected type for EXDATE")
+                                .with_property("EXDATE"));
+                        }
                     }
                 }
                 p => properties.push(p),
             }
         }
 
+        // The extra dates are merged into the recurrence set, so must be in
+        // order.
+        rdates.sort();
+
         Ok(OffsetRule {
-            offset_from: offset_from
-                .ok_or_else(|| format_err!("Missing TZOFFSETFROM field in offset rule"))?,
-            offset_to: offset_to
-                .ok_or_else(|| format_err!("Missing TZOFFSETTO field in offset rule"))?,
-            start: start.ok_or_else(|| format_err!("Missing DTSTART field in offset rule"))?,
+            offset_from: offset_from.ok_or_else(|| Error::missing_property("TZOFFSETFROM"))?,
+            offset_to: offset_to.ok_or_else(|| Error::missing_property("TZOFFSETTO"))?,
+            start: start.ok_or_else(|| Error::missing_property("DTSTART"))?,
             recur,
             rdates,
             exdates,
@@ -774,13 +1014,29 @@ pub struct VTimeZone {
     pub daylight: Vec<OffsetRule>,
 
     pub properties: Vec<Property>,
+
+    /// The limits on expanding the observances' recurrence rules.
+    pub limits: ParseLimits,
 }
 
-impl TryFrom<parser::Component> for VTimeZone {
+impl TryFrom<parser::Component<'_>> for VTimeZone {
     type Error = Error;
 
     fn try_from(component: parser::Component) -> Result<Self, Self::Error> {
-        ensure!(component.name.to_ascii_uppercase() == "VTIMEZONE");
+        let context = ErrorContext::for_component(&component);
+
+        VTimeZone::from_component(component).map_err(|e| e.within(&context))
+    }
+}
+
+impl VTimeZone {
+    fn from_component(component: parser::Component) -> Result<Self, Error> {
+        if component.name.to_ascii_uppercase() != "VTIMEZONE" {
+            return Err(Error::invalid_component(format!(
+                "Expected VTIMEZONE, found {}",
+                component.name
+            )));
+        }
 
         let mut standard = Vec::new();
         let mut daylight = Vec::new();
@@ -793,7 +1049,9 @@ impl TryFrom<parser::Component> for VTimeZone {
         }
 
         if standard.is_empty() && daylight.is_empty() {
-            bail!("VTIMEZONE must have one of DAYLIGHT or STANDARD components");
+            return Err(Error::invalid_component(
+                "VTIMEZONE must have one of DAYLIGHT or STANDARD components",
+            ));
         }
 
         let mut id = None;
@@ -809,10 +1067,11 @@ impl TryFrom<parser::Component> for VTimeZone {
         }
 
         Ok(VTimeZone {
-            id: id.ok_or_else(|| format_err!("Missing TZID field in offset rule"))?,
+            id: id.ok_or_else(|| Error::missing_property("TZID"))?,
             standard,
             daylight,
             properties,
+            limits: ParseLimits::default(),
         })
     }
 }
@@ -820,7 +1079,9 @@ impl TryFrom<parser::Component> for VTimeZone {
 impl VTimeZone {
     /// Find the offset for the given date. Date should either be in local time,
     /// or at UTC.
-    pub fn get_offset(&self, date: NaiveDateTime, local: bool) -> FixedOffset {
+    ///
+    /// Fails if none of the timezone's observances apply at the date.
+    pub fn get_offset(&self, date: NaiveDateTime, local: bool) -> Result<FixedOffset, Error> {
         let effective_standard = get_effective_offset(&self.standard, date, local);
         let effective_daylight = get_effective_offset(&self.daylight, date, local);
 
@@ -828,68 +1089,83 @@ impl VTimeZone {
             (Some(standard), Some(daylight)) => {
                 // We iterate over recurrence until we find a period that matches.
                 let last_standard_before = if let Some(recur) = &standard.recur {
-                    recur
-                        .from_date_with_extras(
+                    last_occurrence_before(
+                        recur.from_date_with_extras(
                             standard.start,
                             standard.rdates.iter().cloned(),
                             &standard.exdates,
                             standard.offset_from,
-                        )
-                        .take_while(|&d| {
-                            d <= if local {
-                                date
-                            } else {
-                                date + standard.offset_from
-                            }
-                        })
-                        .last()
-                        .unwrap_or(standard.start)
+                            &self.limits,
+                        )?,
+                        if local {
+                            date
+                        } else {
+                            date + standard.offset_from
+                        },
+                    )?
+                    .unwrap_or(standard.start)
                 } else {
                     standard.start
                 };
 
                 let last_daylight_before = if let Some(recur) = &daylight.recur {
-                    recur
-                        .from_date(daylight.start, &daylight.offset_from)
-                        .take_while(|&d| {
-                            d <= if local {
-                                date
-                            } else {
-                                date + daylight.offset_from
-                            }
-                        })
-                        .last()
-                        .unwrap_or(daylight.start)
+                    last_occurrence_before(
+                        recur.from_date(daylight.start, &daylight.offset_from, &self.limits)?,
+                        if local {
+                            date
+                        } else {
+                            date + daylight.offset_from
+                        },
+                    )?
+                    .unwrap_or(daylight.start)
                 } else {
                     daylight.start
                 };
 
                 if last_daylight_before < last_standard_before {
-                    standard.offset_to
+                    Ok(standard.offset_to)
                 } else {
-                    daylight.offset_to
+                    Ok(daylight.offset_to)
                 }
             }
-            (Some(standard), None) => standard.offset_to,
-            (None, Some(daylight)) => daylight.offset_to,
-            (None, None) => panic!("invalid timezone"), // TODO: don't panic
+            (Some(standard), None) => Ok(standard.offset_to),
+            (None, Some(daylight)) => Ok(daylight.offset_to),
+            (None, None) => Err(Error::invalid_component(format!(
+                "No observance of timezone {} applies at {}",
+                self.id, date
+            ))
+            .with_component("VTIMEZONE")),
         }
     }
 }
 
 impl Offseter for VTimeZone {
-    fn to_instance(&self, d: NaiveDateTime) -> DateTime<FixedOffset> {
-        self.get_offset(d, true)
-            .from_local_datetime(&d)
-            .earliest()
-            .expect("valid timezone date")
+    fn to_instance(&self, d: NaiveDateTime) -> Result<DateTime<FixedOffset>, Error> {
+        self.get_offset(d, true)?.to_instance(d)
     }
 
-    fn from_instance(&self, d: DateTime<FixedOffset>) -> NaiveDateTime {
-        d.naive_utc() + self.get_offset(d.naive_utc(), false)
+    fn from_instance(&self, d: DateTime<FixedOffset>) -> Result<NaiveDateTime, Error> {
+        self.get_offset(d.naive_utc(), false)?.from_instance(d)
     }
 }
 
+/// The last of the occurrences that is no later than the given date.
+fn last_occurrence_before(
+    occurrences: impl Iterator<Item = Result<NaiveDateTime, Error>>,
+    date: NaiveDateTime,
+) -> Result<Option<NaiveDateTime>, Error> {
+    let mut last = None;
+    for occurrence in occurrences {
+        let occurrence = occurrence?;
+        if occurrence > date {
+            break;
+        }
+        last = Some(occurrence);
+    }
+
+    Ok(last)
+}
+
 fn get_effective_offset(
     slice: &[OffsetRule],
     date: NaiveDateTime,
@@ -907,12 +1183,8 @@ fn get_effective_offset(
                 // If there is a recur rule with an end condition (*must* be
                 // UntilUtc) then we need to check that the date is valid.
                 if let EndCondition::UntilUtc(until) = &recur.end_condition {
-                    let offset_time = from
-                        .offset_from
-                        .from_local_datetime(&date)
-                        .earliest()
-                        .expect("valid datetime"); // This can't fail in FixedOffset
-                    if *until < offset_time {
+                    let offset_time = from.offset_from.from_local_datetime(&date).earliest();
+                    if offset_time.is_none_or(|offset_time| *until < offset_time) {
                         continue;
                     }
                 }
@@ -932,13 +1204,9 @@ fn get_effective_offset(
                 // If there is a recur rule with an end condition (*must* be
                 // UntilUtc) then we need to check that the date is valid.
                 if let EndCondition::UntilUtc(until) = &recur.end_condition {
-                    let offset_time = last
-                        .offset_from
-                        .from_local_datetime(&date)
-                        .earliest()
-                        .expect("valid datetime"); // This can't fail in FixedOffset
+                    let offset_time = last.offset_from.from_local_datetime(&date).earliest();
 
-                    if *until < offset_time {
+                    if offset_time.is_none_or(|offset_time| *until < offset_time) {
                         effective = None;
                     }
                 }
@@ -968,7 +1236,9 @@ impl EventCollection {
             } else if let Some(timings) = &event.timings {
                 let date = match timings {
                     Timings::Date(d) => d.recur_id.map(DateOrDateTime::Date),
-                    Timings::Local(_) => todo!(),
+                    Timings::Local(d) => d
+                        .recur_id
+                        .map(|d| DateOrDateTime::DateTime(IcalDateTime::Local(d))),
                     Timings::Utc(d) => d
                         .recur_id
                         .map(|d| DateOrDateTime::DateTime(IcalDateTime::Utc(d))),
@@ -979,7 +1249,9 @@ impl EventCollection {
                         })
                     }),
                     Timings::PerioidDate(d) => d.recur_id.map(DateOrDateTime::Date),
-                    Timings::PerioidLocal(_) => todo!(),
+                    Timings::PerioidLocal(d) => d
+                        .recur_id
+                        .map(|d| DateOrDateTime::DateTime(IcalDateTime::Local(d))),
                     Timings::PerioidUtc(d) => d
                         .recur_id
                         .map(|d| DateOrDateTime::DateTime(IcalDateTime::Utc(d))),
@@ -997,7 +1269,11 @@ impl EventCollection {
             }
         }
 
-        let base_event = base_event.with_context(|| format!("missing base event: {}", event_id))?;
+        let base_event = base_event.ok_or_else(|| {
+            Error::invalid_component("Missing base event")
+                .with_component("VEVENT")
+                .with_uid(Some(&event_id))
+        })?;
 
         Ok(EventCollection {
             base_event,
@@ -1008,7 +1284,8 @@ impl EventCollection {
     pub fn recur_iter<'a>(
         &'a self,
         calendar: &'a VCalendar,
-    ) -> Result<impl Iterator<Item = (DateTime<FixedOffset>, &'a VEvent)> + 'a, Error> {
+    ) -> Result<impl Iterator<Item = Result<(DateTime<FixedOffset>, &'a VEvent), Error>> + 'a, Error>
+    {
         let mut overrides: BTreeMap<_, _> = self
             .overrides
             .iter()
@@ -1022,13 +1299,15 @@ impl EventCollection {
                     None
                 }
             })
-            .collect::<Result<_, _>>()?;
+            .collect::<Result<_, _>>()
+            .map_err(|e| self.base_event.annotate(e))?;
 
         let exceptions: BTreeSet<_> = overrides.keys().copied().collect();
 
         let mut to_remove = exceptions.clone();
         if let Some(max_date) = exceptions.iter().max() {
             for date in self.base_event.recur_iter(calendar)? {
+                let date = date?;
                 if max_date < &date {
                     break;
                 }
@@ -1044,35 +1323,60 @@ impl EventCollection {
         let base_iter = self
             .base_event
             .recur_iter(calendar)?
-            .filter(move |date| !exceptions.contains(date))
-            .map(move |date| (date, &self.base_event));
+            .filter(move |date| !matches!(date, Ok(date) if exceptions.contains(date)))
+            .map(move |date| date.map(|date| (date, &self.base_event)));
 
         // TODO: Handle the case of recurrence ID being THISANDFUTURE?
 
         let exception_iters: Vec<_> = overrides
-            .into_iter()
-            .map(|(_, v)| {
+            .into_values()
+            .map(|v| {
                 v.recur_iter(calendar)
-                    .map(|iter| iter.map(move |date| (date, v)))
+                    .map(|iter| iter.map(move |date| date.map(|date| (date, v))))
             })
             .collect::<Result<_, Error>>()?;
 
-        let exception_iter = exception_iters.into_iter().kmerge_by(|a, b| a.0 < b.0);
+        let exception_iter = exception_iters.into_iter().kmerge_by(occurs_before);
 
-        Ok(Box::new(
-            base_iter.merge_by(exception_iter, |a, b| a.0 < b.0),
-        ))
+        Ok(Box::new(base_iter.merge_by(exception_iter, occurs_before)))
+    }
+}
+
+/// The order to merge instances in, with errors coming out as soon as they
+/// are hit.
+fn occurs_before(
+    a: &Result<(DateTime<FixedOffset>, &VEvent), Error>,
+    b: &Result<(DateTime<FixedOffset>, &VEvent), Error>,
+) -> bool {
+    match (a, b) {
+        (Ok(a), Ok(b)) => a.0 < b.0,
+        (Err(_), _) => true,
+        (_, Err(_)) => false,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parameters::{CuType, DisplayType, PartStat, Role};
+    use crate::property::AttachEnum;
 
     fn make_naive_date(s: &str) -> NaiveDateTime {
         NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
     }
 
+    fn parse_calendar(input: &str) -> VCalendar {
+        parse_calendar_with_limits(input, &ParseLimits::default()).unwrap()
+    }
+
+    fn parse_calendar_with_limits(input: &str, limits: &ParseLimits) -> Result<VCalendar, Error> {
+        let component = parser::Component::from_str_to_stream(input)
+            .unwrap()
+            .pop()
+            .unwrap();
+        VCalendar::try_from_with_limits(component, limits)
+    }
+
     #[test]
     fn simple_london() {
         let timezone = VTimeZone {
@@ -1098,17 +1402,27 @@ mod tests {
                 properties: vec![],
             }],
             properties: vec![],
+            limits: ParseLimits::default(),
         };
 
         assert_eq!(
-            timezone.get_offset(make_naive_date("2020-08-23 00:00:00"), true),
+            timezone
+                .get_offset(make_naive_date("2020-08-23 00:00:00"), true)
+                .unwrap(),
             FixedOffset::east(3600)
         );
 
         assert_eq!(
-            timezone.get_offset(make_naive_date("2020-01-01 00:00:00"), true),
+            timezone
+                .get_offset(make_naive_date("2020-01-01 00:00:00"), true)
+                .unwrap(),
             FixedOffset::east(0)
         );
+
+        // Before any of the observances.
+        assert!(timezone
+            .get_offset(make_naive_date("1970-01-01 00:00:00"), true)
+            .is_err());
     }
 
     #[test]
@@ -1168,39 +1482,427 @@ mod tests {
                 },
             ],
             properties: vec![],
+            limits: ParseLimits::default(),
         };
 
         assert_eq!(
-            timezone.get_offset(make_naive_date("1997-11-01 00:00:00"), true),
+            timezone
+                .get_offset(make_naive_date("1997-11-01 00:00:00"), true)
+                .unwrap(),
             FixedOffset::west(5 * 3600)
         );
 
         assert_eq!(
-            timezone.get_offset(make_naive_date("1998-07-23 00:00:00"), true),
+            timezone
+                .get_offset(make_naive_date("1998-07-23 00:00:00"), true)
+                .unwrap(),
             FixedOffset::west(4 * 3600)
         );
 
         assert_eq!(
-            timezone.get_offset(make_naive_date("1998-01-01 00:00:00"), true),
+            timezone
+                .get_offset(make_naive_date("1998-01-01 00:00:00"), true)
+                .unwrap(),
             FixedOffset::west(5 * 3600)
         );
 
         assert_eq!(
-            timezone.get_offset(make_naive_date("2020-07-23 00:00:00"), true),
+            timezone
+                .get_offset(make_naive_date("2020-07-23 00:00:00"), true)
+                .unwrap(),
             FixedOffset::west(4 * 3600)
         );
 
         assert_eq!(
-            timezone.get_offset(make_naive_date("2020-01-01 00:00:00"), true),
+            timezone
+                .get_offset(make_naive_date("2020-01-01 00:00:00"), true)
+                .unwrap(),
             FixedOffset::west(5 * 3600)
         );
 
         assert_eq!(
-            timezone.get_offset(make_naive_date("1998-01-01 00:00:00"), true),
+            timezone
+                .get_offset(make_naive_date("1998-01-01 00:00:00"), true)
+                .unwrap(),
             FixedOffset::west(5 * 3600)
         );
     }
 
+    #[test]
+    fn error_location() {
+        let input = "BEGIN:VCALENDAR\r\n\
+            PRODID:-//Test//EN\r\n\
+            VERSION:2.0\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:test\r\n\
+            DTSTAMP:not a date\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+
+        let err = parse_calendar_with_limits(input, &ParseLimits::default()).unwrap_err();
+        assert!(matches!(err, Error::InvalidValue { .. }), "{:?}", err);
+
+        let context = err.context();
+        assert_eq!(context.component.as_deref(), Some("VEVENT"));
+        assert_eq!(context.property.as_deref(), Some("DTSTAMP"));
+        assert_eq!(context.uid.as_deref(), Some("test"));
+
+        let span = context.span.unwrap();
+        assert_eq!((span.start.line, span.start.column), (6, 1));
+
+        assert!(
+            err.to_string()
+                .ends_with(", in DTSTAMP property, in VEVENT with UID test, at line 6, column 1"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn error_kinds() {
+        let calendar = |event: &str| {
+            format!(
+                "BEGIN:VCALENDAR\r\nPRODID:-//Test//EN\r\nVERSION:2.0\r\n\
+                BEGIN:VEVENT\r\nUID:test\r\n{}END:VEVENT\r\nEND:VCALENDAR\r\n",
+                event
+            )
+        };
+
+        // Unknown timezones are only noticed once we need the offsets.
+        let calendar_with_tz = parse_calendar(&calendar(
+            "DTSTAMP:20200101T100000Z\r\nDTSTART;TZID=Nowhere:20200101T100000\r\n",
+        ));
+        let event = calendar_with_tz.events.values().next().unwrap();
+        let err = event.recur_iter(&calendar_with_tz).err().unwrap();
+        assert!(matches!(err, Error::UnknownTimezone { ref tzid, .. } if tzid == "Nowhere"));
+        assert_eq!(err.context().uid.as_deref(), Some("test"));
+
+        let convert =
+            |input: &str| parse_calendar_with_limits(input, &ParseLimits::default()).unwrap_err();
+
+        let err = convert(&calendar(
+            "DTSTAMP:20200101T100000Z\r\nRRULE:FREQ=SOMETIMES\r\n",
+        ));
+        assert!(matches!(err, Error::InvalidRecurRule { .. }));
+        assert_eq!(err.context().property.as_deref(), Some("RRULE"));
+
+        let err = convert(&calendar(""));
+        assert!(matches!(err, Error::MissingProperty { .. }));
+        assert_eq!(err.context().property.as_deref(), Some("DTSTAMP"));
+        assert_eq!(err.context().component.as_deref(), Some("VEVENT"));
+
+        let err = parser::Component::from_str_to_stream("BEGIN:VCALENDAR\r\n").unwrap_err();
+        assert!(matches!(err, Error::Syntax { .. }));
+    }
+
+    #[test]
+    fn event_duration() {
+        let input = "BEGIN:VCALENDAR\r\nPRODID:-//Test//EN\r\nVERSION:2.0\r\n\
+            BEGIN:VEVENT\r\nUID:test\r\nDTSTAMP:20200101T100000Z\r\n\
+            DTSTART:20200101T100000Z\r\nDURATION:PT1H30M\r\n\
+            END:VEVENT\r\nEND:VCALENDAR\r\n";
+        let calendar = parse_calendar(input);
+
+        match &calendar.events["test"].base_event.timings {
+            Some(Timings::PerioidUtc(inner)) => {
+                assert_eq!(inner.start.duration, Duration::minutes(90))
+            }
+            timings => panic!("Unexpected timings {:?}", timings),
+        }
+    }
+
+    #[test]
+    fn event_duration_across_timezones() {
+        let input = "BEGIN:VCALENDAR\r\nPRODID:-//Test//EN\r\nVERSION:2.0\r\n\
+            BEGIN:VTIMEZONE\r\nTZID:Plus1\r\nBEGIN:STANDARD\r\nDTSTART:19700101T000000\r\n\
+            TZOFFSETFROM:+0100\r\nTZOFFSETTO:+0100\r\nEND:STANDARD\r\nEND:VTIMEZONE\r\n\
+            BEGIN:VTIMEZONE\r\nTZID:Plus2\r\nBEGIN:STANDARD\r\nDTSTART:19700101T000000\r\n\
+            TZOFFSETFROM:+0200\r\nTZOFFSETTO:+0200\r\nEND:STANDARD\r\nEND:VTIMEZONE\r\n\
+            BEGIN:VEVENT\r\nUID:test\r\nDTSTAMP:20200101T100000Z\r\n\
+            DTSTART;TZID=Plus1:20200101T100000\r\nDTEND;TZID=Plus2:20200101T130000\r\n\
+            END:VEVENT\r\nEND:VCALENDAR\r\n";
+        let calendar = parse_calendar(input);
+
+        let start = IcalDateTime::TZ {
+            date: "2020-01-01T10:00:00".parse().unwrap(),
+            tzid: "Plus1".to_string(),
+        };
+        let end = IcalDateTime::TZ {
+            date: "2020-01-01T13:00:00".parse().unwrap(),
+            tzid: "Plus2".to_string(),
+        };
+        assert_eq!(
+            end.sub(&start, Some(&calendar)).unwrap(),
+            Duration::hours(2)
+        );
+        assert_eq!(
+            start.sub(&end, Some(&calendar)).unwrap(),
+            Duration::hours(-2)
+        );
+
+        let end_utc = IcalDateTime::Utc("2020-01-01T11:00:00Z".parse().unwrap());
+        assert_eq!(
+            end_utc.sub(&start, Some(&calendar)).unwrap(),
+            Duration::hours(2)
+        );
+
+        match &calendar.events["test"].base_event.timings {
+            Some(Timings::PerioidTz { inner, .. }) => {
+                assert_eq!(inner.start.duration, Duration::hours(2))
+            }
+            timings => panic!("Unexpected timings {:?}", timings),
+        }
+    }
+
+    #[test]
+    fn event_multi_valued_dates() {
+        let input = "BEGIN:VCALENDAR\r\nPRODID:-//Test//EN\r\nVERSION:2.0\r\n\
+            BEGIN:VEVENT\r\nUID:test\r\nDTSTAMP:20200101T100000Z\r\n\
+            DTSTART:20200101T100000Z\r\nRRULE:FREQ=DAILY;COUNT=4\r\n\
+            EXDATE:20200102T100000Z,20200103T100000Z\r\n\
+            RDATE:20200110T100000Z,20200105T100000Z\r\nRDATE:20200101T120000Z\r\n\
+            END:VEVENT\r\nEND:VCALENDAR\r\n";
+        let calendar = parse_calendar(input);
+
+        let occurrences = calendar.events["test"]
+            .base_event
+            .recur_iter(&calendar)
+            .unwrap()
+            .map(|date| date.unwrap().naive_utc().to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            occurrences,
+            [
+                "2020-01-01 10:00:00",
+                "2020-01-01 12:00:00",
+                "2020-01-04 10:00:00",
+                "2020-01-05 10:00:00",
+                "2020-01-10 10:00:00",
+            ]
+        );
+    }
+
+    #[test]
+    fn calendar_properties() {
+        let input = "BEGIN:VCALENDAR\r\nPRODID:-//Test//EN\r\nVERSION:2.0\r\n\
+            NAME;LANGUAGE=fr:Vacances\r\nNAME:Holidays\r\nDESCRIPTION:Public holidays\r\n\
+            COLOR:darkOrange\r\nREFRESH-INTERVAL;VALUE=DURATION:P1W\r\n\
+            SOURCE;VALUE=URI:https://example.com/holidays.ics\r\n\
+            IMAGE;VALUE=URI;DISPLAY=BADGE,THUMBNAIL;FMTTYPE=image/png:https://example.com/logo.png\r\n\
+            END:VCALENDAR\r\n";
+        let calendar = parse_calendar(input);
+
+        assert_eq!(calendar.name(), Some("Holidays"));
+        assert_eq!(calendar.description(), Some("Public holidays"));
+        assert_eq!(
+            calendar.color().unwrap().to_hex().as_deref(),
+            Some("#ff8c00")
+        );
+        assert_eq!(calendar.refresh_interval(), Some(Duration::weeks(1)));
+
+        let image = calendar
+            .properties
+            .iter()
+            .find_map(|property| match property {
+                Property::Image(image) => Some(image),
+                _ => None,
+            })
+            .unwrap();
+        assert!(matches!(image.value, AttachEnum::Url(_)));
+        assert_eq!(
+            image.parameters.get_display(),
+            [DisplayType::Badge, DisplayType::Thumbnail]
+        );
+
+        let input = "BEGIN:VCALENDAR\r\nPRODID:-//Test//EN\r\nVERSION:2.0\r\n\
+            COLOR:#ff0000\r\nEND:VCALENDAR\r\n";
+        let calendar = parse_calendar(input);
+        let color = calendar.color().unwrap();
+        assert_eq!(color.name(), "#ff0000");
+        assert_eq!(color.rgb(), None);
+    }
+
+    #[test]
+    fn attendees() {
+        let input = "BEGIN:VCALENDAR\r\nPRODID:-//Test//EN\r\nVERSION:2.0\r\n\
+            BEGIN:VEVENT\r\nUID:test\r\nDTSTAMP:20200101T100000Z\r\n\
+            ORGANIZER;CN=Alice;SENT-BY=\"mailto:pa@example.com\":mailto:Alice@Example.com\r\n\
+            ATTENDEE;ROLE=CHAIR;PARTSTAT=ACCEPTED:mailto:alice@example.com\r\n\
+            ATTENDEE;RSVP=TRUE;PARTSTAT=DELEGATED;DELEGATED-TO=\"mailto:c%40d@example.com\",\r\n \
+             \"mailto:e@example.com\":MAILTO:bob@example.com?subject=Hi\r\n\
+            ATTENDEE;CUTYPE=ROOM;ROLE=NON-PARTICIPANT;PARTSTAT=X-MAYBE;MEMBER=\"mailto:rooms@example.com\"\r\n \
+             :urn:uuid:b2c1e6a0-3f1a-4d6e-9b8a-0c7d2f1e5a4b\r\n\
+            END:VEVENT\r\nEND:VCALENDAR\r\n";
+        let calendar = parse_calendar(input);
+        let event = &calendar.events["test"].base_event;
+
+        let organizer = event.organizer().unwrap();
+        assert_eq!(organizer.common_name(), Some("Alice"));
+        assert_eq!(organizer.email().as_deref(), Some("alice@example.com"));
+        assert_eq!(organizer.sent_by(), Some("mailto:pa@example.com"));
+
+        let attendees = event.attendees().collect::<Vec<_>>();
+        assert_eq!(attendees.len(), 3);
+
+        assert_eq!(attendees[0].role(), Role::Chair);
+        assert_eq!(attendees[0].participation_status(), PartStat::Accepted);
+        assert_eq!(attendees[0].user_type(), CuType::Individual);
+        assert!(!attendees[0].rsvp());
+
+        assert_eq!(attendees[1].email().as_deref(), Some("bob@example.com"));
+        assert_eq!(attendees[1].role(), Role::ReqParticipant);
+        assert_eq!(attendees[1].participation_status(), PartStat::Delegated);
+        assert_eq!(
+            attendees[1].delegated_to(),
+            ["mailto:c%40d@example.com", "mailto:e@example.com"]
+        );
+        assert!(attendees[1].rsvp());
+
+        assert_eq!(attendees[2].email(), None);
+        assert_eq!(attendees[2].user_type(), CuType::Room);
+        assert_eq!(attendees[2].role(), Role::NonParticipant);
+        assert!(attendees[2].participation_status().is_pending());
+        assert_eq!(attendees[2].member(), ["mailto:rooms@example.com"]);
+    }
+
+    #[test]
+    fn status_class_geo() {
+        let input = "BEGIN:VCALENDAR\r\nPRODID:-//Test//EN\r\nVERSION:2.0\r\n\
+            BEGIN:VEVENT\r\nUID:test\r\nDTSTAMP:20200101T100000Z\r\n\
+            STATUS:CANCELLED\r\nCLASS:X-UNKNOWN\r\nGEO:51.5;-0.12\r\n\
+            END:VEVENT\r\nEND:VCALENDAR\r\n";
+        let calendar = parse_calendar(input);
+        let event = &calendar.events["test"].base_event;
+
+        assert_eq!(event.status, Some(StatusEnum::Cancelled));
+        assert!(event.class.as_ref().unwrap().is_private());
+        assert_eq!(event.geo, Some((51.5, -0.12)));
+
+        // A status meant for another component type is kept rather than
+        // rejecting the whole calendar.
+        let input = "BEGIN:VCALENDAR\r\nPRODID:-//Test//EN\r\nVERSION:2.0\r\n\
+            BEGIN:VEVENT\r\nUID:test\r\nDTSTAMP:20200101T100000Z\r\n\
+            STATUS:NEEDS-ACTION\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let calendar = parse_calendar(input);
+        let status = calendar.events["test"].base_event.status.as_ref().unwrap();
+
+        assert_eq!(status, &StatusEnum::NeesAction);
+        assert!(!status.is_valid_for("VEVENT"));
+    }
+
+    #[test]
+    fn malformed_input() {
+        // The event starts before any of the timezone's observances.
+        let calendar = parse_calendar(
+            "BEGIN:VCALENDAR\r\nPRODID:-//Test//EN\r\nVERSION:2.0\r\n\
+            BEGIN:VTIMEZONE\r\nTZID:Late\r\n\
+            BEGIN:STANDARD\r\nDTSTART:20000101T000000\r\n\
+            TZOFFSETFROM:+0100\r\nTZOFFSETTO:+0100\r\nEND:STANDARD\r\n\
+            END:VTIMEZONE\r\n\
+            BEGIN:VEVENT\r\nUID:test\r\nDTSTAMP:20200101T100000Z\r\n\
+            DTSTART;TZID=Late:19900101T100000\r\nRRULE:FREQ=DAILY\r\nEND:VEVENT\r\n\
+            END:VCALENDAR\r\n",
+        );
+        let event = calendar.events.values().next().unwrap();
+        let err = event.base_event.recur_iter(&calendar).err().unwrap();
+        assert!(matches!(err, Error::InvalidComponent { .. }), "{:?}", err);
+        assert_eq!(err.context().uid.as_deref(), Some("test"));
+
+        // Floating events can have overrides.
+        let calendar = parse_calendar(
+            "BEGIN:VCALENDAR\r\nPRODID:-//Test//EN\r\nVERSION:2.0\r\n\
+            BEGIN:VEVENT\r\nUID:test\r\nDTSTAMP:20200101T100000Z\r\n\
+            DTSTART:20200101T100000\r\nRRULE:FREQ=DAILY\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nUID:test\r\nDTSTAMP:20200101T100000Z\r\n\
+            RECURRENCE-ID:20200102T100000\r\nDTSTART:20200102T110000\r\nEND:VEVENT\r\n\
+            END:VCALENDAR\r\n",
+        );
+        assert_eq!(calendar.events.values().next().unwrap().overrides.len(), 1);
+    }
+
+    #[test]
+    fn limits() {
+        let input = "BEGIN:VCALENDAR\r\nPRODID:-//Test//EN\r\nVERSION:2.0\r\n\
+            BEGIN:VEVENT\r\nUID:test\r\nDTSTAMP:20200101T100000Z\r\n\
+            DTSTART:20200101T100000\r\n\
+            ATTACH;VALUE=BINARY;ENCODING=BASE64:VGhpcyBpcyBhIHRlc3Q=\r\n\
+            END:VEVENT\r\nEND:VCALENDAR\r\n";
+        assert!(parse_calendar_with_limits(input, &ParseLimits::default()).is_ok());
+
+        let limits = ParseLimits {
+            max_attachment_size: 10,
+            ..Default::default()
+        };
+        let err = parse_calendar_with_limits(input, &limits).unwrap_err();
+        assert!(matches!(err, Error::LimitExceeded { .. }), "{:?}", err);
+        assert_eq!(err.context().property.as_deref(), Some("ATTACH"));
+        assert_eq!(err.context().span.unwrap().start.line, 8);
+    }
+
+    #[test]
+    fn recurrence_limits() {
+        // There is never a 30th of February, so the rule steps through every
+        // second until the end of time without finding an occurrence.
+        let input = "BEGIN:VCALENDAR\r\nPRODID:-//Test//EN\r\nVERSION:2.0\r\n\
+            BEGIN:VEVENT\r\nUID:test\r\nDTSTAMP:20200101T100000Z\r\n\
+            DTSTART:20200101T100000Z\r\nRRULE:FREQ=SECONDLY;BYMONTH=2;BYMONTHDAY=30\r\n\
+            END:VEVENT\r\nEND:VCALENDAR\r\n";
+        let calendar = parse_calendar(input);
+
+        let event = &calendar.events["test"];
+        let mut iter = event.base_event.recur_iter(&calendar).unwrap();
+        let err = iter.next().unwrap().unwrap_err();
+        assert!(matches!(err, Error::LimitExceeded { .. }), "{:?}", err);
+        assert_eq!(err.context().property.as_deref(), Some("RRULE"));
+        assert_eq!(err.context().uid.as_deref(), Some("test"));
+        assert!(iter.next().is_none());
+
+        let err = event
+            .recur_iter(&calendar)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert!(matches!(err, Error::LimitExceeded { .. }), "{:?}", err);
+
+        // The limit is on the steps between occurrences, not in total.
+        let input = "BEGIN:VCALENDAR\r\nPRODID:-//Test//EN\r\nVERSION:2.0\r\n\
+            BEGIN:VTIMEZONE\r\nTZID:Odd\r\n\
+            BEGIN:STANDARD\r\nDTSTART:20000101T000000\r\nRRULE:FREQ=DAILY;BYMONTHDAY=30\r\n\
+            TZOFFSETFROM:+0100\r\nTZOFFSETTO:+0100\r\nEND:STANDARD\r\n\
+            BEGIN:DAYLIGHT\r\nDTSTART:20000101T000000\r\nRRULE:FREQ=DAILY;BYMONTH=2;BYMONTHDAY=30\r\n\
+            TZOFFSETFROM:+0100\r\nTZOFFSETTO:+0200\r\nEND:DAYLIGHT\r\n\
+            END:VTIMEZONE\r\n\
+            BEGIN:VEVENT\r\nUID:test\r\nDTSTAMP:20200101T100000Z\r\n\
+            DTSTART:20200101T100000Z\r\nRRULE:FREQ=DAILY;BYMONTHDAY=30\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\nUID:tz\r\nDTSTAMP:20200101T100000Z\r\n\
+            DTSTART;TZID=Odd:20200101T100000\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        let limits = ParseLimits {
+            max_recurrence_steps: 70,
+            ..Default::default()
+        };
+        let calendar = parse_calendar_with_limits(input, &limits).unwrap();
+
+        let occurrences = calendar.events["test"]
+            .base_event
+            .recur_iter(&calendar)
+            .unwrap()
+            .take(12)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(occurrences.len(), 12);
+
+        // Timezone observances are limited too.
+        let err = calendar.events["tz"]
+            .base_event
+            .recur_iter(&calendar)
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::LimitExceeded { .. }), "{:?}", err);
+    }
+
     #[test]
     fn parse_vcalendar() {
         let input = include_str!("../example.ics");
@@ -1216,7 +1918,8 @@ mod tests {
             .recur_iter(&calendar)
             .unwrap()
             .take(3)
-            .collect::<Vec<_>>();
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
         let expected_times: Vec<_> = vec![
             "2020-07-22T14:00:00+01:00",
             "2020-08-05T14:00:00+01:00",
@@ -1230,7 +1933,7 @@ mod tests {
 
         // Check the times around clock changes are correct.
         let iter = event.recur_iter(&calendar).unwrap().skip(6);
-        let times = iter.take(3).collect::<Vec<_>>();
+        let times = iter.take(3).collect::<Result<Vec<_>, _>>().unwrap();
         let expected_times: Vec<_> = vec![
             "2020-10-14T14:00:00+01:00",
             "2020-10-28T14:00:00+00:00",
@@ -1244,7 +1947,7 @@ mod tests {
 
         // Test that iterating over periods work.
         let iter = event.recur_period_iter(&calendar).unwrap().skip(6);
-        let times = iter.take(3).collect::<Vec<_>>();
+        let times = iter.take(3).collect::<Result<Vec<_>, _>>().unwrap();
         let expected_times: Vec<_> = vec![
             "2020-10-14T14:00:00+01:00",
             "2020-10-28T14:00:00+00:00",
@@ -0,0 +1,342 @@
+use std::fmt::{self, Display};
+
+use crate::parser::{self, Span};
+
+/// Where an error occurred, as far as is known.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// The name of the component being converted, e.g. `VEVENT`.
+    pub component: Option<String>,
+
+    /// The name of the property being converted, e.g. `DTSTART`.
+    pub property: Option<String>,
+
+    /// The UID of the component being converted, if it has one.
+    pub uid: Option<String>,
+
+    /// Where in the parsed data the error occurred.
+    pub span: Option<Span>,
+}
+
+impl ErrorContext {
+    /// The context for errors when converting the given component.
+    pub(crate) fn for_component(component: &parser::Component<'_>) -> ErrorContext {
+        let uid = component
+            .properties
+            .iter()
+            .find(|property| property.name.eq_ignore_ascii_case("UID"))
+            .map(|property| property.value.to_string());
+
+        ErrorContext {
+            component: Some(component.name.to_ascii_uppercase()),
+            property: None,
+            uid,
+            span: component.span,
+        }
+    }
+}
+
+/// The errors that can occur when parsing or converting calendar data.
+///
+/// Each variant carries an [`ErrorContext`] describing where the error
+/// occurred, which gets filled in as the error propagates. (It's boxed to keep
+/// the error, and so every `Result`, small.)
+#[derive(Debug)]
+pub enum Error {
+    /// The data isn't syntactically valid.
+    Syntax {
+        message: String,
+        context: Box<ErrorContext>,
+    },
+
+    /// A required property is missing. The name of the property is in the
+    /// context.
+    MissingProperty { context: Box<ErrorContext> },
+
+    /// A property or parameter has an invalid value.
+    InvalidValue {
+        message: String,
+        context: Box<ErrorContext>,
+    },
+
+    /// A component is invalid, e.g. it has an unexpected name or conflicting
+    /// properties.
+    InvalidComponent {
+        message: String,
+        context: Box<ErrorContext>,
+    },
+
+    /// A `TZID` was referenced that isn't defined in the calendar.
+    UnknownTimezone {
+        tzid: String,
+        context: Box<ErrorContext>,
+    },
+
+    /// Date-times that should be in the same timezone (or both local, or both
+    /// UTC) aren't.
+    TimezoneMismatch {
+        message: String,
+        context: Box<ErrorContext>,
+    },
+
+    /// A recurrence rule is invalid.
+    InvalidRecurRule {
+        message: String,
+        context: Box<ErrorContext>,
+    },
+
+    /// The data exceeds one of the configured
+    /// [`ParseLimits`](crate::limits::ParseLimits).
+    LimitExceeded {
+        message: String,
+        context: Box<ErrorContext>,
+    },
+
+    /// Reading the data failed.
+    Io {
+        source: std::io::Error,
+        context: Box<ErrorContext>,
+    },
+}
+
+impl Error {
+    pub(crate) fn syntax(message: impl Display) -> Error {
+        Error::Syntax {
+            message: message.to_string(),
+            context: Box::default(),
+        }
+    }
+
+    pub(crate) fn missing_property(property: &str) -> Error {
+        Error::MissingProperty {
+            context: Box::new(ErrorContext {
+                property: Some(property.to_string()),
+                ..Default::default()
+            }),
+        }
+    }
+
+    pub(crate) fn invalid_value(message: impl Display) -> Error {
+        Error::InvalidValue {
+            message: message.to_string(),
+            context: Box::default(),
+        }
+    }
+
+    pub(crate) fn invalid_component(message: impl Display) -> Error {
+        Error::InvalidComponent {
+            message: message.to_string(),
+            context: Box::default(),
+        }
+    }
+
+    pub(crate) fn unknown_timezone(tzid: &str) -> Error {
+        Error::UnknownTimezone {
+            tzid: tzid.to_string(),
+            context: Box::default(),
+        }
+    }
+
+    pub(crate) fn timezone_mismatch(message: impl Display) -> Error {
+        Error::TimezoneMismatch {
+            message: message.to_string(),
+            context: Box::default(),
+        }
+    }
+
+    pub(crate) fn invalid_recur_rule(message: impl Display) -> Error {
+        Error::InvalidRecurRule {
+            message: message.to_string(),
+            context: Box::default(),
+        }
+    }
+
+    pub(crate) fn limit_exceeded(message: impl Display) -> Error {
+        Error::LimitExceeded {
+            message: message.to_string(),
+            context: Box::default(),
+        }
+    }
+
+    /// Where the error occurred.
+    pub fn context(&self) -> &ErrorContext {
+        match self {
+            Error::Syntax { context, .. }
+            | Error::MissingProperty { context }
+            | Error::InvalidValue { context, .. }
+            | Error::InvalidComponent { context, .. }
+            | Error::UnknownTimezone { context, .. }
+            | Error::TimezoneMismatch { context, .. }
+            | Error::InvalidRecurRule { context, .. }
+            | Error::LimitExceeded { context, .. }
+            | Error::Io { context, .. } => context,
+        }
+    }
+
+    fn context_mut(&mut self) -> &mut ErrorContext {
+        match self {
+            Error::Syntax { context, .. }
+            | Error::MissingProperty { context }
+            | Error::InvalidValue { context, .. }
+            | Error::InvalidComponent { context, .. }
+            | Error::UnknownTimezone { context, .. }
+            | Error::TimezoneMismatch { context, .. }
+            | Error::InvalidRecurRule { context, .. }
+            | Error::LimitExceeded { context, .. }
+            | Error::Io { context, .. } => context,
+        }
+    }
+
+    // The following only fill in the context if it isn't already known, as
+    // the innermost (i.e. first) context is the most specific.
+
+    pub(crate) fn with_component(mut self, component: &str) -> Error {
+        let context = self.context_mut();
+        if context.component.is_none() {
+            context.component = Some(component.to_string());
+        }
+        self
+    }
+
+    pub(crate) fn with_property(mut self, property: &str) -> Error {
+        let context = self.context_mut();
+        if context.property.is_none() {
+            context.property = Some(property.to_string());
+        }
+        self
+    }
+
+    pub(crate) fn with_uid(mut self, uid: Option<&str>) -> Error {
+        let context = self.context_mut();
+        if context.uid.is_none() {
+            context.uid = uid.map(str::to_string);
+        }
+        self
+    }
+
+    pub(crate) fn with_span(mut self, span: Option<Span>) -> Error {
+        let context = self.context_mut();
+        if context.span.is_none() {
+            context.span = span;
+        }
+        self
+    }
+
+    /// Fill in any unknown context from the given, outer, context.
+    pub(crate) fn within(mut self, outer: &ErrorContext) -> Error {
+        let context = self.context_mut();
+        if context.component.is_none() {
+            context.component = outer.component.clone();
+        }
+        if context.property.is_none() {
+            context.property = outer.property.clone();
+        }
+        if context.uid.is_none() {
+            context.uid = outer.uid.clone();
+        }
+        if context.span.is_none() {
+            context.span = outer.span;
+        }
+        self
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Syntax { message, .. } => write!(f, "syntax error: {}", message)?,
+            Error::MissingProperty { context } => match &context.property {
+                Some(property) => write!(f, "missing {} property", property)?,
+                None => write!(f, "missing property")?,
+            },
+            Error::InvalidValue { message, .. } => write!(f, "invalid value: {}", message)?,
+            Error::InvalidComponent { message, .. } => write!(f, "invalid component: {}", message)?,
+            Error::UnknownTimezone { tzid, .. } => {
+                write!(f, "referenced timezone {} not in calendar", tzid)?
+            }
+            Error::TimezoneMismatch { message, .. } => write!(f, "timezone mismatch: {}", message)?,
+            Error::InvalidRecurRule { message, .. } => {
+                write!(f, "invalid recurrence rule: {}", message)?
+            }
+            Error::LimitExceeded { message, .. } => write!(f, "limit exceeded: {}", message)?,
+            Error::Io { source, .. } => write!(f, "failed to read data: {}", source)?,
+        }
+
+        let context = self.context();
+
+        if let (Some(property), false) = (
+            &context.property,
+            matches!(self, Error::MissingProperty { .. }),
+        ) {
+            write!(f, ", in {} property", property)?;
+        }
+
+        if let Some(component) = &context.component {
+            write!(f, ", in {}", component)?;
+        }
+
+        if let Some(uid) = &context.uid {
+            write!(f, " with UID {}", uid)?;
+        }
+
+        if let Some(span) = &context.span {
+            write!(f, ", at {}", span)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(source: std::io::Error) -> Self {
+        Error::Io {
+            source,
+            context: Box::default(),
+        }
+    }
+}
+
+impl From<chrono::ParseError> for Error {
+    fn from(err: chrono::ParseError) -> Self {
+        Error::invalid_value(err)
+    }
+}
+
+impl From<std::num::ParseIntError> for Error {
+    fn from(err: std::num::ParseIntError) -> Self {
+        Error::invalid_value(err)
+    }
+}
+
+impl From<url::ParseError> for Error {
+    fn from(err: url::ParseError) -> Self {
+        Error::invalid_value(err)
+    }
+}
+
+impl From<base64::DecodeError> for Error {
+    fn from(err: base64::DecodeError) -> Self {
+        Error::invalid_value(err)
+    }
+}
+
+impl From<std::convert::Infallible> for Error {
+    fn from(err: std::convert::Infallible) -> Self {
+        match err {}
+    }
+}
+
+impl From<quick_xml::Error> for Error {
+    fn from(err: quick_xml::Error) -> Self {
+        Error::syntax(err)
+    }
+}
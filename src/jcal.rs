@@ -0,0 +1,678 @@
+//! jCal, the JSON format for iCalendar data defined in RFC 7265.
+//!
+//! A component is represented as `[name, properties, components]` and a
+//! property as `[name, parameters, type, value...]`, where the type is taken
+//! from the `VALUE` parameter or the default for the property. Values are
+//! converted into their structured JSON form, e.g. dates use the extended
+//! ISO 8601 format and recurrence rules become objects.
+//!
+//! A [`VCalendar`] can be read from jCal, but not written to it: a calendar
+//! doesn't keep everything it was parsed from (e.g. components other than
+//! events, or the parameters of the properties it extracts), so there is no
+//! way back to a component. To convert a calendar to jCal, keep the
+//! [`parser::Component`] it was parsed from and pass that to [`to_jcal`].
+
+use std::{borrow::Cow, convert::TryFrom};
+
+use serde_json::{Map, Number, Value};
+
+use crate::{
+    components::VCalendar,
+    error::Error,
+    parser,
+    property::default_value_type,
+    unescape::{escape, split_escaped, unescape_lenient},
+};
+
+/// Properties whose values are comma separated lists, which jCal represents
+/// as multiple values.
+const MULTI_VALUED: &[&str] = &["CATEGORIES", "RESOURCES", "FREEBUSY", "EXDATE", "RDATE"];
+
+/// Recurrence rule parts with integer values.
+const INTEGER_RULE_PARTS: &[&str] = &[
+    "count",
+    "interval",
+    "bysecond",
+    "byminute",
+    "byhour",
+    "bymonthday",
+    "byyearday",
+    "byweekno",
+    "bymonth",
+    "bysetpos",
+];
+
+/// Convert a component, including all of its sub-components, into jCal.
+pub fn to_jcal(component: &parser::Component<'_>) -> Result<Value, Error> {
+    let name = component.name.to_ascii_uppercase();
+
+    let properties = component
+        .properties
+        .iter()
+        .map(property_to_jcal)
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.with_component(&name))?;
+
+    let sub_components = component
+        .sub_components
+        .iter()
+        .map(to_jcal)
+        .collect::<Result<_, _>>()?;
+
+    Ok(Value::Array(vec![
+        Value::String(name.to_ascii_lowercase()),
+        Value::Array(properties),
+        Value::Array(sub_components),
+    ]))
+}
+
+/// Convert jCal back into a component.
+pub fn from_jcal(value: &Value) -> Result<parser::Component<'static>, Error> {
+    let (name, properties, sub_components) = match value.as_array().map(Vec::as_slice) {
+        Some([Value::String(name), Value::Array(properties), Value::Array(sub_components)]) => {
+            (name.to_ascii_uppercase(), properties, sub_components)
+        }
+        _ => {
+            return Err(Error::syntax(
+                "Expected a jCal component of the form [name, properties, components]",
+            ))
+        }
+    };
+
+    let properties = properties
+        .iter()
+        .map(property_from_jcal)
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.with_component(&name))?;
+
+    let sub_components = sub_components
+        .iter()
+        .map(from_jcal)
+        .collect::<Result<_, _>>()?;
+
+    Ok(parser::Component {
+        name: Cow::Owned(name),
+        sub_components,
+        properties,
+        span: None,
+    })
+}
+
+impl VCalendar {
+    /// Parse a calendar from its jCal representation.
+    ///
+    /// There is no inverse, see the [module documentation](self).
+    pub fn from_jcal(value: &Value) -> Result<VCalendar, Error> {
+        VCalendar::try_from(from_jcal(value)?)
+    }
+}
+
+/// A property in its jCal form. xCal uses the same value representations, so
+/// shares this.
+pub(crate) struct JcalProperty {
+    /// The uppercased property name.
+    pub name: String,
+    pub parameters: Map<String, Value>,
+    /// The uppercased value type, or `UNKNOWN`.
+    pub value_type: String,
+    pub values: Vec<Value>,
+}
+
+impl JcalProperty {
+    pub(crate) fn from_property(property: &parser::Property<'_>) -> Result<JcalProperty, Error> {
+        let name = property.name.to_ascii_uppercase();
+
+        let mut parameters = Map::new();
+        let mut value_type = None;
+
+        if let Some(group) = &property.group {
+            parameters.insert("group".to_string(), Value::String(group.to_string()));
+        }
+
+        for parameter in &property.parameters {
+            // The value type is given separately, rather than as a parameter.
+            if parameter.name.eq_ignore_ascii_case("VALUE") {
+                value_type = parameter.values.first().map(|v| v.to_ascii_uppercase());
+                continue;
+            }
+
+            let mut values = parameter
+                .values
+                .iter()
+                .map(|v| Value::String(v.to_string()))
+                .collect::<Vec<_>>();
+
+            let value = if values.len() == 1 {
+                values.remove(0)
+            } else {
+                Value::Array(values)
+            };
+
+            parameters.insert(parameter.name.to_ascii_lowercase(), value);
+        }
+
+        let value_type = value_type
+            .or_else(|| default_value_type(&name).map(str::to_string))
+            .unwrap_or_else(|| "UNKNOWN".to_string());
+
+        let values = values_to_jcal(&name, &value_type, &property.value)
+            .map_err(|e| e.with_property(&name))?;
+
+        Ok(JcalProperty {
+            name,
+            parameters,
+            value_type,
+            values,
+        })
+    }
+
+    pub(crate) fn into_property(self) -> Result<parser::Property<'static>, Error> {
+        let name = self.name.clone();
+
+        self.to_property().map_err(|e| e.with_property(&name))
+    }
+
+    fn to_property(&self) -> Result<parser::Property<'static>, Error> {
+        let mut group = None;
+        let mut parameters = Vec::new();
+
+        if self.value_type != "UNKNOWN"
+            && default_value_type(&self.name) != Some(&self.value_type as &str)
+        {
+            parameters.push(parser::Parameter {
+                name: Cow::Borrowed("VALUE"),
+                values: vec![Cow::Owned(self.value_type.clone())],
+                span: None,
+            });
+        }
+
+        for (parameter_name, value) in &self.parameters {
+            let values = match value {
+                Value::String(value) => vec![Cow::Owned(value.clone())],
+                Value::Array(values) => values
+                    .iter()
+                    .map(|v| match v {
+                        Value::String(v) => Ok(Cow::Owned(v.clone())),
+                        _ => Err(invalid_parameter(parameter_name)),
+                    })
+                    .collect::<Result<_, _>>()?,
+                _ => return Err(invalid_parameter(parameter_name)),
+            };
+
+            if parameter_name == "group" {
+                group = values.into_iter().next();
+                continue;
+            }
+
+            parameters.push(parser::Parameter {
+                name: Cow::Owned(parameter_name.to_ascii_uppercase()),
+                values,
+                span: None,
+            });
+        }
+
+        let value = self
+            .values
+            .iter()
+            .map(|v| value_from_jcal(&self.value_type, v))
+            .collect::<Result<Vec<_>, _>>()?
+            .join(",");
+
+        Ok(parser::Property {
+            group,
+            name: Cow::Owned(self.name.clone()),
+            value: Cow::Owned(value),
+            parameters,
+            span: None,
+        })
+    }
+}
+
+fn property_to_jcal(property: &parser::Property<'_>) -> Result<Value, Error> {
+    let property = JcalProperty::from_property(property)?;
+
+    let mut jcal = vec![
+        Value::String(property.name.to_ascii_lowercase()),
+        Value::Object(property.parameters),
+        Value::String(property.value_type.to_ascii_lowercase()),
+    ];
+    jcal.extend(property.values);
+
+    Ok(Value::Array(jcal))
+}
+
+fn property_from_jcal(property: &Value) -> Result<parser::Property<'static>, Error> {
+    match property.as_array().map(Vec::as_slice) {
+        Some(
+            [Value::String(name), Value::Object(parameters), Value::String(value_type), values @ ..],
+        ) if !values.is_empty() => JcalProperty {
+            name: name.to_ascii_uppercase(),
+            parameters: parameters.clone(),
+            value_type: value_type.to_ascii_uppercase(),
+            values: values.to_vec(),
+        }
+        .into_property(),
+        _ => Err(Error::syntax(
+            "Expected a jCal property of the form [name, parameters, type, value...]",
+        )),
+    }
+}
+
+/// Convert a property's value into its jCal values, splitting it where the
+/// property has multiple values.
+fn values_to_jcal(name: &str, value_type: &str, value: &str) -> Result<Vec<Value>, Error> {
+    let values = match (name, value_type) {
+        ("GEO", "FLOAT") => {
+            let coordinates = value
+                .split(';')
+                .map(|v| value_to_jcal("FLOAT", v))
+                .collect::<Result<_, _>>()?;
+
+            vec![Value::Array(coordinates)]
+        }
+        ("REQUEST-STATUS", "TEXT") => {
+            let status = split_escaped(value, ';')
+                .into_iter()
+                .map(|v| value_to_jcal("TEXT", v))
+                .collect::<Result<_, _>>()?;
+
+            vec![Value::Array(status)]
+        }
+        _ if MULTI_VALUED.contains(&name) => split_escaped(value, ',')
+            .into_iter()
+            .map(|v| value_to_jcal(value_type, v))
+            .collect::<Result<_, _>>()?,
+        _ => vec![value_to_jcal(value_type, value)?],
+    };
+
+    Ok(values)
+}
+
+fn invalid_parameter(name: &str) -> Error {
+    Error::invalid_value(format!(
+        "Parameter {} must be a string or an array of strings",
+        name
+    ))
+}
+
+/// Convert a single value of the given type into its jCal form.
+fn value_to_jcal(value_type: &str, value: &str) -> Result<Value, Error> {
+    let jcal = match value_type {
+        "TEXT" => Value::String(unescape_lenient(value).into_owned()),
+        "INTEGER" => Value::Number(value.parse::<i64>()?.into()),
+        "FLOAT" => value
+            .parse()
+            .ok()
+            .and_then(Number::from_f64)
+            .map(Value::Number)
+            .ok_or_else(|| Error::invalid_value(format!("Invalid float: {}", value)))?,
+        "BOOLEAN" => match &value.to_ascii_uppercase() as &str {
+            "TRUE" => Value::Bool(true),
+            "FALSE" => Value::Bool(false),
+            _ => return Err(Error::invalid_value(format!("Invalid boolean: {}", value))),
+        },
+        "DATE" => Value::String(date_to_jcal(value)?),
+        "DATE-TIME" => Value::String(date_time_to_jcal(value)?),
+        "TIME" => Value::String(time_to_jcal(value)?),
+        "UTC-OFFSET" => Value::String(offset_to_jcal(value)?),
+        "PERIOD" => {
+            let (start, end) = split_period(value)?;
+
+            let end = if is_duration(end) {
+                end.to_string()
+            } else {
+                date_time_to_jcal(end)?
+            };
+
+            Value::Array(vec![
+                Value::String(date_time_to_jcal(start)?),
+                Value::String(end),
+            ])
+        }
+        "RECUR" => recur_to_jcal(value)?,
+        _ => Value::String(value.to_string()),
+    };
+
+    Ok(jcal)
+}
+
+/// Convert a single jCal value of the given type back into its text form.
+fn value_from_jcal(value_type: &str, value: &Value) -> Result<String, Error> {
+    let text = match (value_type, value) {
+        ("TEXT", Value::String(value)) => escape(value).into_owned(),
+        ("DATE", Value::String(value)) | ("DATE-TIME", Value::String(value)) => {
+            value.replace(&['-', ':'] as &[_], "")
+        }
+        ("TIME", Value::String(value)) | ("UTC-OFFSET", Value::String(value)) => {
+            value.replace(':', "")
+        }
+        ("PERIOD", Value::Array(parts)) => match parts.as_slice() {
+            [Value::String(start), Value::String(end)] => {
+                let end = if is_duration(end) {
+                    end.clone()
+                } else {
+                    end.replace(&['-', ':'] as &[_], "")
+                };
+
+                format!("{}/{}", start.replace(&['-', ':'] as &[_], ""), end)
+            }
+            _ => return Err(Error::invalid_value("Expected a period of [start, end]")),
+        },
+        ("RECUR", Value::Object(rule)) => recur_from_jcal(rule)?,
+        (_, Value::String(value)) => value.clone(),
+        (_, Value::Number(value)) => value.to_string(),
+        (_, Value::Bool(true)) => "TRUE".to_string(),
+        (_, Value::Bool(false)) => "FALSE".to_string(),
+        // Structured values, e.g. `GEO` and `REQUEST-STATUS`.
+        (_, Value::Array(parts)) => parts
+            .iter()
+            .map(|part| match part {
+                Value::Array(_) | Value::Object(_) => {
+                    Err(Error::invalid_value("Unexpected nested structured value"))
+                }
+                part => value_from_jcal(value_type, part),
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .join(";"),
+        _ => {
+            return Err(Error::invalid_value(format!(
+                "Invalid jCal value for type {}: {}",
+                value_type, value
+            )))
+        }
+    };
+
+    Ok(text)
+}
+
+fn is_duration(value: &str) -> bool {
+    value
+        .trim_start_matches(&['+', '-'] as &[_])
+        .starts_with('P')
+}
+
+fn split_period(value: &str) -> Result<(&str, &str), Error> {
+    let mut parts = value.splitn(2, '/');
+
+    match (parts.next(), parts.next()) {
+        (Some(start), Some(end)) => Ok((start, end)),
+        _ => Err(Error::invalid_value(format!("Invalid period: {}", value))),
+    }
+}
+
+fn all_digits(value: &str) -> bool {
+    value.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn date_to_jcal(value: &str) -> Result<String, Error> {
+    if value.len() != 8 || !all_digits(value) {
+        return Err(Error::invalid_value(format!("Invalid date: {}", value)));
+    }
+
+    Ok(format!("{}-{}-{}", &value[..4], &value[4..6], &value[6..]))
+}
+
+fn time_to_jcal(value: &str) -> Result<String, Error> {
+    let (time, utc) = match value.strip_suffix('Z') {
+        Some(time) => (time, "Z"),
+        None => (value, ""),
+    };
+
+    if time.len() != 6 || !all_digits(time) {
+        return Err(Error::invalid_value(format!("Invalid time: {}", value)));
+    }
+
+    Ok(format!(
+        "{}:{}:{}{}",
+        &time[..2],
+        &time[2..4],
+        &time[4..],
+        utc
+    ))
+}
+
+fn date_time_to_jcal(value: &str) -> Result<String, Error> {
+    let mut parts = value.splitn(2, 'T');
+
+    match (parts.next(), parts.next()) {
+        (Some(date), Some(time)) => Ok(format!("{}T{}", date_to_jcal(date)?, time_to_jcal(time)?)),
+        _ => Err(Error::invalid_value(format!(
+            "Invalid date-time: {}",
+            value
+        ))),
+    }
+}
+
+fn offset_to_jcal(value: &str) -> Result<String, Error> {
+    let digits = value.get(1..).unwrap_or_default();
+
+    if !value.starts_with(&['+', '-'] as &[_])
+        || !(digits.len() == 4 || digits.len() == 6)
+        || !all_digits(digits)
+    {
+        return Err(Error::invalid_value(format!(
+            "Invalid UTC offset: {}",
+            value
+        )));
+    }
+
+    let mut jcal = format!("{}:{}", &value[..3], &digits[2..4]);
+    if digits.len() == 6 {
+        jcal.push(':');
+        jcal.push_str(&digits[4..]);
+    }
+
+    Ok(jcal)
+}
+
+/// Convert a recurrence rule into an object keyed by the lowercased rule part
+/// names. Parts with multiple values become arrays.
+fn recur_to_jcal(value: &str) -> Result<Value, Error> {
+    let mut rule = Map::new();
+
+    for part in value.split(';').filter(|p| !p.is_empty()) {
+        let mut split = part.splitn(2, '=');
+        let (name, values) = match (split.next(), split.next()) {
+            (Some(name), Some(values)) => (name.to_ascii_lowercase(), values),
+            _ => {
+                return Err(Error::invalid_recur_rule(format!(
+                    "Invalid rule part: {}",
+                    part
+                )))
+            }
+        };
+
+        let mut values = values
+            .split(',')
+            .map(|v| {
+                if name == "until" {
+                    if v.contains('T') {
+                        date_time_to_jcal(v).map(Value::String)
+                    } else {
+                        date_to_jcal(v).map(Value::String)
+                    }
+                } else if INTEGER_RULE_PARTS.contains(&(&name as &str)) {
+                    Ok(Value::Number(v.parse::<i64>()?.into()))
+                } else {
+                    Ok(Value::String(v.to_string()))
+                }
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let value = if values.len() == 1 {
+            values.remove(0)
+        } else {
+            Value::Array(values)
+        };
+
+        rule.insert(name, value);
+    }
+
+    Ok(Value::Object(rule))
+}
+
+fn recur_from_jcal(rule: &Map<String, Value>) -> Result<String, Error> {
+    let part = |name: &str, value: &Value| -> Result<String, Error> {
+        let values = match value {
+            Value::Array(values) => values.iter().collect(),
+            value => vec![value],
+        };
+
+        let values = values
+            .into_iter()
+            .map(|v| match v {
+                Value::String(v) if name == "until" => Ok(v.replace(&['-', ':'] as &[_], "")),
+                Value::String(v) => Ok(v.clone()),
+                Value::Number(v) => Ok(v.to_string()),
+                _ => Err(Error::invalid_recur_rule(format!(
+                    "Invalid value for {}",
+                    name
+                ))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(format!(
+            "{}={}",
+            name.to_ascii_uppercase(),
+            values.join(",")
+        ))
+    };
+
+    // `FREQ` should come first for compatibility with parsers written for
+    // RFC 2445.
+    let freq = rule
+        .get("freq")
+        .ok_or_else(|| Error::invalid_recur_rule("Missing FREQ"))?;
+
+    let mut parts = vec![part("freq", freq)?];
+    for (name, value) in rule.iter().filter(|(name, _)| *name != "freq") {
+        parts.push(part(name, value)?);
+    }
+
+    Ok(parts.join(";"))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let input = include_str!("../example.ics");
+
+        let parsed = parser::Component::from_str_to_stream(input).unwrap();
+
+        for component in &parsed {
+            let jcal = to_jcal(component).unwrap();
+            assert_eq!(&from_jcal(&jcal).unwrap(), component);
+        }
+
+        let calendar = VCalendar::from_jcal(&to_jcal(&parsed[0]).unwrap()).unwrap();
+        assert_eq!(calendar.events.len(), 1);
+    }
+
+    #[test]
+    fn values() {
+        let input = "BEGIN:VEVENT\r
+DTSTART;TZID=Europe/London:20200722T140000\r
+DTEND;VALUE=DATE:20200723\r
+SUMMARY:Lunch\\, maybe\r
+CATEGORIES:WORK,FOOD\\, DRINK\r
+SEQUENCE:1\r
+GEO:37.386013;-122.082932\r
+FREEBUSY;FBTYPE=BUSY:19970308T160000Z/PT3H,19970308T200000Z/19970308T210000Z\r
+RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;UNTIL=20201231T235959Z\r
+X-OFFSET;VALUE=UTC-OFFSET:-0530\r
+REQUEST-STATUS:2.0;Success\r
+X-CUSTOM;X-PARAM=a,b:Some value\r
+END:VEVENT\r
+";
+
+        let component = parser::Component::from_str_to_stream(input)
+            .unwrap()
+            .remove(0);
+
+        let jcal = to_jcal(&component).unwrap();
+
+        assert_eq!(
+            jcal,
+            json!([
+                "vevent",
+                [
+                    ["dtstart", {"tzid": "Europe/London"}, "date-time", "2020-07-22T14:00:00"],
+                    ["dtend", {}, "date", "2020-07-23"],
+                    ["summary", {}, "text", "Lunch, maybe"],
+                    ["categories", {}, "text", "WORK", "FOOD, DRINK"],
+                    ["sequence", {}, "integer", 1],
+                    ["geo", {}, "float", [37.386013, -122.082932]],
+                    [
+                        "freebusy",
+                        {"fbtype": "BUSY"},
+                        "period",
+                        ["1997-03-08T16:00:00Z", "PT3H"],
+                        ["1997-03-08T20:00:00Z", "1997-03-08T21:00:00Z"]
+                    ],
+                    [
+                        "rrule",
+                        {},
+                        "recur",
+                        {"freq": "WEEKLY", "interval": 2, "byday": ["MO", "WE"], "until": "2020-12-31T23:59:59Z"}
+                    ],
+                    ["x-offset", {}, "utc-offset", "-05:30"],
+                    ["request-status", {}, "text", ["2.0", "Success"]],
+                    ["x-custom", {"x-param": ["a", "b"]}, "unknown", "Some value"]
+                ],
+                []
+            ])
+        );
+
+        assert_eq!(from_jcal(&jcal).unwrap(), component);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(matches!(
+            from_jcal(&json!(["vevent", []])),
+            Err(Error::Syntax { .. })
+        ));
+
+        let err = from_jcal(&json!(["vevent", [["summary", {}, "text"]], []])).unwrap_err();
+        assert!(matches!(err, Error::Syntax { .. }));
+        assert_eq!(err.context().component.as_deref(), Some("VEVENT"));
+
+        let err =
+            from_jcal(&json!(["vevent", [["rrule", {}, "recur", {"count": 3}]], []])).unwrap_err();
+        assert!(matches!(err, Error::InvalidRecurRule { .. }));
+        assert_eq!(err.context().property.as_deref(), Some("RRULE"));
+
+        let component = parser::Component::from_str_to_stream(
+            "BEGIN:VEVENT\r\nSEQUENCE:first\r\nEND:VEVENT\r\n",
+        )
+        .unwrap()
+        .remove(0);
+        let err = to_jcal(&component).unwrap_err();
+        assert_eq!(err.context().property.as_deref(), Some("SEQUENCE"));
+        assert_eq!(err.context().component.as_deref(), Some("VEVENT"));
+    }
+
+    #[test]
+    fn lenient_text() {
+        // Outlook escapes colons, which RFC 5545 doesn't allow.
+        let component = parser::Component::from_str_to_stream(
+            "BEGIN:VEVENT\r\nSUMMARY:Call mailto\\:a@example.com\r\nEND:VEVENT\r\n",
+        )
+        .unwrap()
+        .remove(0);
+
+        assert_eq!(
+            to_jcal(&component).unwrap(),
+            json!([
+                "vevent",
+                [["summary", {}, "text", "Call mailto:a@example.com"]],
+                []
+            ])
+        );
+    }
+}
@@ -0,0 +1,337 @@
+//! A hand-written lexer for content lines.
+//!
+//! Folds are skipped over as we go (rather than unfolding as a preprocessing
+//! step), so that positions in the original data remain correct and tokens
+//! can borrow from the data unless they were actually folded.
+
+use std::borrow::Cow;
+
+use crate::{
+    error::Error,
+    parser::{Locator, Parameter, Position, Property, Span},
+    unescape::decode_param_value,
+};
+
+/// Remove any folds from the string, only allocating if there are any.
+///
+/// A fold is a line ending (or run of line endings) followed by a single
+/// space or tab.
+pub(crate) fn unfold(s: &str) -> Cow<'_, str> {
+    let bytes = s.as_bytes();
+
+    let mut unfolded = String::new();
+    let mut copied = 0;
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        if !is_newline(bytes[idx]) {
+            idx += 1;
+            continue;
+        }
+
+        let fold_start = idx;
+        while idx < bytes.len() && is_newline(bytes[idx]) {
+            idx += 1;
+        }
+
+        if idx < bytes.len() && is_space(bytes[idx]) {
+            unfolded.push_str(&s[copied..fold_start]);
+            idx += 1;
+            copied = idx;
+        }
+    }
+
+    if copied == 0 {
+        return Cow::Borrowed(s);
+    }
+
+    unfolded.push_str(&s[copied..]);
+    Cow::Owned(unfolded)
+}
+
+fn is_newline(b: u8) -> bool {
+    b == b'\r' || b == b'\n'
+}
+
+fn is_space(b: u8) -> bool {
+    b == b' ' || b == b'\t'
+}
+
+fn is_name_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'-'
+}
+
+/// Whether the byte is a control character, other than tab.
+fn is_control(b: u8) -> bool {
+    (b < 0x20 && b != b'\t') || b == 0x7f
+}
+
+/// Characters allowed in an unquoted parameter value.
+fn is_safe_char(b: u8) -> bool {
+    !matches!(b, b'"' | b'`' | b',' | b':' | b';') && !is_control(b)
+}
+
+/// Characters allowed in a quoted parameter value.
+fn is_quoted_char(b: u8) -> bool {
+    b != b'"' && !is_control(b)
+}
+
+/// Remove trailing spaces and tabs.
+fn trim_end(s: Cow<'_, str>) -> Cow<'_, str> {
+    let trim = |s: &str| s.trim_end_matches(&[' ', '\t'] as &[_]).len();
+
+    match s {
+        Cow::Borrowed(s) => Cow::Borrowed(&s[..trim(s)]),
+        Cow::Owned(mut s) => {
+            s.truncate(trim(&s));
+            Cow::Owned(s)
+        }
+    }
+}
+
+/// Splits a single (possibly folded) content line into its parts.
+///
+/// As with the grammar this replaced, whitespace is allowed around the
+/// delimiters and is trimmed from the ends of values.
+pub(crate) struct Lexer<'a> {
+    text: &'a str,
+    offset: usize,
+    locator: Locator<'a>,
+}
+
+impl<'a> Lexer<'a> {
+    /// Create a lexer for the text, which starts at the given position in the
+    /// data.
+    pub(crate) fn new(text: &'a str, start: Position) -> Lexer<'a> {
+        Lexer {
+            text,
+            offset: 0,
+            locator: Locator::new(text, start),
+        }
+    }
+
+    /// Parse the text as a property, which must make up the entire line.
+    pub(crate) fn property(&mut self) -> Result<Property<'a>, Error> {
+        let mut name = self.name("property")?;
+
+        self.skip_whitespace();
+        let group = if self.eat(b'.') {
+            self.skip_whitespace();
+            Some(std::mem::replace(&mut name, self.name("property")?))
+        } else {
+            None
+        };
+
+        self.skip_whitespace();
+        let mut parameters = Vec::new();
+        while self.eat(b';') {
+            self.skip_whitespace();
+            parameters.push(self.parameter()?);
+            self.skip_whitespace();
+        }
+
+        if !self.eat(b':') {
+            return Err(self.unexpected("':' after the property name"));
+        }
+
+        self.skip_whitespace();
+        let value_start = self.offset;
+        self.take_while(|b| !is_newline(b));
+        let value = trim_end(unfold(&self.text[value_start..self.offset]));
+
+        let end = self.text.trim_end_matches(&['\r', '\n'] as &[_]).len();
+        if self.offset < end {
+            return Err(self.unexpected("the end of the line"));
+        }
+
+        Ok(Property {
+            group,
+            name,
+            value,
+            parameters,
+            span: Some(self.span(0, end)),
+        })
+    }
+
+    /// Parse a parameter, e.g. `CN=Foo`.
+    pub(crate) fn parameter(&mut self) -> Result<Parameter<'a>, Error> {
+        let start = self.offset;
+        let name = self.name("parameter")?;
+
+        self.skip_whitespace();
+        if !self.eat(b'=') {
+            return Err(self.unexpected("'=' after the parameter name"));
+        }
+
+        let mut values = Vec::new();
+        let mut end;
+        loop {
+            self.skip_whitespace();
+            values.push(decode_param_value(self.parameter_value()?));
+            end = self.offset;
+
+            self.skip_whitespace();
+            if !self.eat(b',') {
+                break;
+            }
+        }
+
+        // Don't include any trailing whitespace in the span.
+        self.offset = end;
+
+        Ok(Parameter {
+            name,
+            values,
+            span: Some(self.span(start, end)),
+        })
+    }
+
+    fn parameter_value(&mut self) -> Result<Cow<'a, str>, Error> {
+        if self.eat(b'"') {
+            let start = self.offset;
+            self.take_while(is_quoted_char);
+            let value = unfold(&self.text[start..self.offset]);
+
+            if !self.eat(b'"') {
+                return Err(self.unexpected("a closing quote"));
+            }
+
+            Ok(value)
+        } else {
+            let start = self.offset;
+            self.take_while(is_safe_char);
+
+            Ok(trim_end(unfold(&self.text[start..self.offset])))
+        }
+    }
+
+    /// Parse a (component, property or parameter) name.
+    fn name(&mut self, kind: &str) -> Result<Cow<'a, str>, Error> {
+        let start = self.offset;
+        self.take_while(is_name_char);
+
+        if self.offset == start {
+            return Err(self.unexpected(&format!("a {} name", kind)));
+        }
+
+        Ok(unfold(&self.text[start..self.offset]))
+    }
+
+    /// Move past any folds at the current offset.
+    fn skip_folds(&mut self) {
+        let bytes = self.text.as_bytes();
+
+        loop {
+            let mut idx = self.offset;
+            while idx < bytes.len() && is_newline(bytes[idx]) {
+                idx += 1;
+            }
+
+            if idx == self.offset || idx == bytes.len() || !is_space(bytes[idx]) {
+                return;
+            }
+
+            self.offset = idx + 1;
+        }
+    }
+
+    /// The next byte, skipping any folds.
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_folds();
+        self.text.as_bytes().get(self.offset).copied()
+    }
+
+    /// Move past the next byte if it's the expected one.
+    fn eat(&mut self, expected: u8) -> bool {
+        if self.peek() == Some(expected) {
+            self.offset += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Move past all bytes (and folds) that match the predicate.
+    ///
+    /// Note: predicates always accept non-ASCII bytes or reject them all, so
+    /// that we always stop on a character boundary.
+    fn take_while(&mut self, predicate: impl Fn(u8) -> bool) {
+        while let Some(b) = self.peek() {
+            if !predicate(b) {
+                break;
+            }
+            self.offset += 1;
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.take_while(is_space);
+    }
+
+    fn span(&self, start: usize, end: usize) -> Span {
+        Span {
+            start: self.locator.position(start),
+            end: self.locator.position(end),
+        }
+    }
+
+    /// An error for an unexpected character at the current offset.
+    fn unexpected(&mut self, expected: &str) -> Error {
+        self.skip_folds();
+
+        let found = match self.text[self.offset..].chars().next() {
+            Some(c) if c != '\r' && c != '\n' => format!("{:?}", c),
+            _ => "the end of the line".to_string(),
+        };
+
+        Error::syntax(format!("Expected {} but found {}", expected, found))
+            .with_span(Some(self.span(self.offset, self.offset)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unfolding() {
+        assert!(matches!(unfold("no folds"), Cow::Borrowed("no folds")));
+        assert!(matches!(unfold("line ending\r\n"), Cow::Borrowed(_)));
+
+        assert_eq!(unfold("a\r\n b"), "ab");
+        assert_eq!(unfold("a\n\tb\n  c"), "ab c");
+        assert_eq!(unfold("a\r\n\r\n b\r\n"), "ab\r\n");
+    }
+
+    fn property(text: &str) -> Result<Property<'_>, Error> {
+        Lexer::new(text, Position::start()).property()
+    }
+
+    #[test]
+    fn errors() {
+        assert!(property("SUMMARY:Test").is_ok());
+
+        for text in &[
+            "",
+            ":Test",
+            "SUMMARY",
+            "SUMMARY;:Test",
+            "SUMMARY;CN:Test",
+            "SUMMARY;CN=\"Test:Test",
+            "SUMMARY;CN=a\"b:Test",
+            "SUM MARY:Test",
+            "SUMMARY:Test\r\nDESCRIPTION:Test",
+        ] {
+            assert!(
+                matches!(property(text), Err(Error::Syntax { .. })),
+                "{:?}",
+                text
+            );
+        }
+
+        let err = property("SUMMARY;CN=\"Test:Test").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Expected a closing quote but found the end of the line"));
+    }
+}
@@ -1,8 +1,17 @@
+#[cfg(feature = "async")]
+pub mod async_reader;
+pub mod color;
 pub mod components;
+pub mod error;
+pub mod jcal;
+mod lexer;
+pub mod limits;
+pub mod lossless;
 pub mod parameters;
 pub mod parser;
 pub mod property;
+pub mod reader;
 pub mod unescape;
-
-#[macro_use]
-extern crate pest_derive;
+pub mod vcard;
+pub mod writer;
+pub mod xcal;
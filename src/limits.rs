@@ -0,0 +1,229 @@
+//! Limits on the resources used when parsing, to protect against malicious
+//! input.
+
+use crate::{error::Error, parser};
+
+/// Limits on the size and shape of the data that will be accepted.
+///
+/// The default limits are generous enough for any real world calendar, while
+/// stopping e.g. deeply nested components from overflowing the stack. Use
+/// [`ParseLimits::unlimited`] to turn them off for trusted input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// The maximum nesting depth of components, where a top-level component
+    /// has a depth of one.
+    pub max_depth: usize,
+
+    /// The maximum length in bytes of a content line, including any folds.
+    pub max_line_length: usize,
+
+    /// The maximum number of properties in a single component.
+    pub max_properties_per_component: usize,
+
+    /// The maximum total number of components, at any depth.
+    pub max_components: usize,
+
+    /// The maximum size in bytes of a decoded binary attachment (or image).
+    pub max_attachment_size: usize,
+
+    /// The maximum number of periods (e.g. seconds for a `SECONDLY` rule) a
+    /// recurrence rule is stepped through while looking for its next
+    /// occurrence, as a rule may match rarely or never.
+    pub max_recurrence_steps: usize,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        ParseLimits {
+            max_depth: 32,
+            max_line_length: 10 * 1024 * 1024,
+            max_properties_per_component: 10_000,
+            max_components: 1_000_000,
+            max_attachment_size: 10 * 1024 * 1024,
+            max_recurrence_steps: 1_000_000,
+        }
+    }
+}
+
+impl ParseLimits {
+    /// No limits at all.
+    pub fn unlimited() -> ParseLimits {
+        ParseLimits {
+            max_depth: usize::MAX,
+            max_line_length: usize::MAX,
+            max_properties_per_component: usize::MAX,
+            max_components: usize::MAX,
+            max_attachment_size: usize::MAX,
+            max_recurrence_steps: usize::MAX,
+        }
+    }
+
+    pub(crate) fn check_line_length(&self, length: usize) -> Result<(), Error> {
+        if length > self.max_line_length {
+            return Err(Error::limit_exceeded(format!(
+                "Content line is longer than {} bytes",
+                self.max_line_length
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Check that a component can be opened, given the number of components
+    /// currently open and the number opened so far.
+    pub(crate) fn check_component(&self, depth: usize, count: usize) -> Result<(), Error> {
+        if depth >= self.max_depth {
+            return Err(Error::limit_exceeded(format!(
+                "Components are nested more than {} deep",
+                self.max_depth
+            )));
+        }
+
+        if count >= self.max_components {
+            return Err(Error::limit_exceeded(format!(
+                "More than {} components",
+                self.max_components
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Check that another property can be added to a component that already
+    /// has the given number.
+    pub(crate) fn check_property_count(&self, count: usize) -> Result<(), Error> {
+        if count >= self.max_properties_per_component {
+            return Err(Error::limit_exceeded(format!(
+                "More than {} properties in a component",
+                self.max_properties_per_component
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Check a property before it's converted, i.e. that a binary attachment
+    /// won't decode to something too large.
+    pub(crate) fn check_property(&self, property: &parser::Property<'_>) -> Result<(), Error> {
+        // The value can't be longer than the line it came from.
+        self.check_line_length(property.value.len())?;
+
+        let is_binary = ["ATTACH", "IMAGE", "CONFERENCE"]
+            .iter()
+            .any(|name| property.name.eq_ignore_ascii_case(name))
+            && property.parameters.iter().any(|param| {
+                param.name.eq_ignore_ascii_case("VALUE")
+                    && param
+                        .values
+                        .iter()
+                        .any(|v| v.eq_ignore_ascii_case("BINARY"))
+            });
+
+        // Every four characters of base64 decode to three bytes.
+        let encoded = property.value.trim_end_matches('=').len();
+        let decoded_size = encoded / 4 * 3 + encoded % 4 * 3 / 4;
+        if is_binary && decoded_size > self.max_attachment_size {
+            return Err(Error::limit_exceeded(format!(
+                "Attachment is larger than {} bytes",
+                self.max_attachment_size
+            ))
+            .with_property(&property.name.to_ascii_uppercase())
+            .with_span(property.span));
+        }
+
+        Ok(())
+    }
+
+    /// Check an already parsed component (e.g. one that was built up by hand)
+    /// against the limits, before converting it.
+    pub(crate) fn check_tree(&self, component: &parser::Component<'_>) -> Result<(), Error> {
+        // This walks the tree without recursing, as the depth isn't known to
+        // be safe yet.
+        let mut stack = vec![(component, 0)];
+        let mut count = 0;
+
+        while let Some((component, depth)) = stack.pop() {
+            self.check_component(depth, count)
+                .map_err(|e| e.with_span(component.span))?;
+            count += 1;
+
+            self.check_property_count(component.properties.len().saturating_sub(1))
+                .map_err(|e| e.with_span(component.span))?;
+
+            for property in &component.properties {
+                self.check_property(property)?;
+            }
+
+            stack.extend(
+                component
+                    .sub_components
+                    .iter()
+                    .map(|sub_component| (sub_component, depth + 1)),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Component;
+
+    #[test]
+    fn attachment_size() {
+        let limits = ParseLimits {
+            max_attachment_size: 6,
+            ..Default::default()
+        };
+
+        let attach = |value: &str| {
+            let data = format!(
+                "BEGIN:VEVENT\r\nATTACH;VALUE=BINARY;ENCODING=BASE64:{}\r\nEND:VEVENT\r\n",
+                value
+            );
+            let components = Component::from_str_to_stream(&data).unwrap();
+            limits.check_tree(&components[0])
+        };
+
+        assert!(attach("AAAA").is_ok());
+        assert!(attach("AAAAAAAA").is_ok());
+        assert!(matches!(
+            attach("AAAAAAAAAA=="),
+            Err(Error::LimitExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn tree() {
+        let limits = ParseLimits {
+            max_depth: 2,
+            max_properties_per_component: 2,
+            max_components: 3,
+            ..Default::default()
+        };
+
+        let check = |data: &str| {
+            let components =
+                Component::from_str_to_stream_with_limits(data, &ParseLimits::unlimited()).unwrap();
+            limits.check_tree(&components[0])
+        };
+
+        assert!(
+            check("BEGIN:A\r\nX:1\r\nY:2\r\nBEGIN:B\r\nEND:B\r\nBEGIN:C\r\nEND:C\r\nEND:A").is_ok()
+        );
+
+        for data in &[
+            "BEGIN:A\r\nX:1\r\nY:2\r\nZ:3\r\nEND:A",
+            "BEGIN:A\r\nBEGIN:B\r\nBEGIN:C\r\nEND:C\r\nEND:B\r\nEND:A",
+            "BEGIN:A\r\nBEGIN:B\r\nEND:B\r\nBEGIN:C\r\nEND:C\r\nBEGIN:D\r\nEND:D\r\nEND:A",
+        ] {
+            assert!(
+                matches!(check(data), Err(Error::LimitExceeded { .. })),
+                "{:?}",
+                data
+            );
+        }
+    }
+}
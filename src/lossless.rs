@@ -0,0 +1,399 @@
+//! A lossless representation of components, for when data needs to be
+//! written back out exactly as it was read.
+//!
+//! Converting into [`VCalendar`] normalizes away property name casing,
+//! property order, unknown sub-components and the exact formatting of values.
+//! Instead, [`LosslessComponent`] keeps each content line's original text and
+//! writes it back out verbatim unless the line has been modified.
+
+use std::{borrow::Cow, convert::TryFrom, io::Write};
+
+use crate::{
+    components::VCalendar,
+    error::Error,
+    limits::ParseLimits,
+    parser,
+    property::Property,
+    reader::{line_span, ContentLines, Line},
+    writer::ComponentWriter,
+};
+
+/// A component that remembers the original text of its lines.
+#[derive(Debug, Clone)]
+pub struct LosslessComponent {
+    name: String,
+
+    /// The original `BEGIN` and `END` lines, if the component was parsed.
+    begin: Option<String>,
+    end: Option<String>,
+
+    /// The properties and sub-components, in their original order.
+    pub entries: Vec<Entry>,
+}
+
+/// A property or sub-component of a [`LosslessComponent`].
+#[derive(Debug, Clone)]
+pub enum Entry {
+    Property(LosslessProperty),
+    Component(LosslessComponent),
+}
+
+/// A property that remembers its original text until it is modified.
+#[derive(Debug, Clone)]
+pub struct LosslessProperty {
+    property: parser::Property<'static>,
+
+    /// The original text of the content line, including any folds and line
+    /// ending. Cleared when the property is modified.
+    raw: Option<String>,
+}
+
+impl LosslessProperty {
+    /// Create a new (i.e. modified) property.
+    pub fn new(property: parser::Property<'static>) -> LosslessProperty {
+        LosslessProperty {
+            property,
+            raw: None,
+        }
+    }
+
+    pub fn property(&self) -> &parser::Property<'static> {
+        &self.property
+    }
+
+    /// Get mutable access to the property, marking it as modified.
+    pub fn property_mut(&mut self) -> &mut parser::Property<'static> {
+        self.raw = None;
+        &mut self.property
+    }
+
+    /// Parse the property into its typed form.
+    pub fn typed(&self) -> Result<Property, Error> {
+        Property::try_from(self.property.clone())
+    }
+
+    /// Replace the property with the given typed property, marking it as
+    /// modified.
+    pub fn set_typed(&mut self, property: &Property) {
+        *self.property_mut() = property.to_parsed();
+    }
+
+    /// The original text of the content line, if it hasn't been modified.
+    pub fn raw(&self) -> Option<&str> {
+        self.raw.as_deref()
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.raw.is_none()
+    }
+}
+
+impl From<Property> for LosslessProperty {
+    fn from(property: Property) -> Self {
+        LosslessProperty::new(property.to_parsed())
+    }
+}
+
+impl LosslessComponent {
+    /// Create a new, empty component.
+    pub fn new(name: impl Into<String>) -> LosslessComponent {
+        LosslessComponent {
+            name: name.into(),
+            begin: None,
+            end: None,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Parse all the top-level components in the data.
+    ///
+    /// The data is checked against the default [`ParseLimits`].
+    pub fn parse(data: &str) -> Result<Vec<LosslessComponent>, Error> {
+        LosslessComponent::parse_with_limits(data, &ParseLimits::default())
+    }
+
+    /// Parse all the top-level components in the data, failing if it exceeds
+    /// the given limits.
+    pub fn parse_with_limits(
+        data: &str,
+        limits: &ParseLimits,
+    ) -> Result<Vec<LosslessComponent>, Error> {
+        let mut components = Vec::new();
+        let mut stack: Vec<LosslessComponent> = Vec::new();
+        let mut components_seen = 0;
+
+        // The number of properties in each open component, as counting the
+        // entries each time would be quadratic.
+        let mut property_counts: Vec<usize> = Vec::new();
+
+        // Anything between content lines (i.e. leading blank lines) gets
+        // attached to the following line, so that nothing is lost.
+        let mut previous_end = 0;
+
+        for (text, start) in ContentLines::new(data) {
+            let raw = data[previous_end..start.offset + text.len()].to_string();
+            previous_end = start.offset + text.len();
+
+            let span = Some(line_span(text, start));
+
+            limits
+                .check_line_length(text.len())
+                .map_err(|e| e.with_span(span))?;
+
+            match Line::parse(text, start).map_err(|e| e.with_span(span))? {
+                Line::Begin { name, .. } => {
+                    limits
+                        .check_component(stack.len(), components_seen)
+                        .map_err(|e| e.with_span(span))?;
+                    components_seen += 1;
+
+                    let mut component = LosslessComponent::new(name);
+                    component.begin = Some(raw);
+                    stack.push(component);
+                    property_counts.push(0);
+                }
+                Line::End { name, .. } => {
+                    let mut component = match stack.pop() {
+                        Some(component) if component.name.eq_ignore_ascii_case(&name) => component,
+                        Some(component) => {
+                            return Err(Error::syntax(format!(
+                                "Expected END:{} but found END:{}",
+                                component.name, name
+                            ))
+                            .with_span(span))
+                        }
+                        None => {
+                            return Err(Error::syntax(format!(
+                                "Unexpected END:{} outside of a component",
+                                name
+                            ))
+                            .with_span(span))
+                        }
+                    };
+                    component.end = Some(raw);
+                    property_counts.pop();
+
+                    match stack.last_mut() {
+                        Some(parent) => parent.entries.push(Entry::Component(component)),
+                        None => components.push(component),
+                    }
+                }
+                Line::Property(property) => match (stack.last_mut(), property_counts.last_mut()) {
+                    (Some(parent), Some(count)) => {
+                        limits
+                            .check_property_count(*count)
+                            .map_err(|e| e.with_span(span))?;
+                        *count += 1;
+                        parent.entries.push(Entry::Property(LosslessProperty {
+                            property: property.into_owned(),
+                            raw: Some(raw),
+                        }))
+                    }
+                    _ => {
+                        return Err(Error::syntax(format!(
+                            "Property {} found outside of a component",
+                            property.name
+                        ))
+                        .with_span(span))
+                    }
+                },
+            }
+        }
+
+        if let Some(component) = stack.pop() {
+            return Err(Error::syntax(format!("Missing END:{}", component.name)));
+        }
+
+        Ok(components)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn properties(&self) -> impl Iterator<Item = &LosslessProperty> {
+        self.entries.iter().filter_map(|entry| match entry {
+            Entry::Property(property) => Some(property),
+            Entry::Component(_) => None,
+        })
+    }
+
+    pub fn properties_mut(&mut self) -> impl Iterator<Item = &mut LosslessProperty> {
+        self.entries.iter_mut().filter_map(|entry| match entry {
+            Entry::Property(property) => Some(property),
+            Entry::Component(_) => None,
+        })
+    }
+
+    pub fn sub_components(&self) -> impl Iterator<Item = &LosslessComponent> {
+        self.entries.iter().filter_map(|entry| match entry {
+            Entry::Component(component) => Some(component),
+            Entry::Property(_) => None,
+        })
+    }
+
+    pub fn sub_components_mut(&mut self) -> impl Iterator<Item = &mut LosslessComponent> {
+        self.entries.iter_mut().filter_map(|entry| match entry {
+            Entry::Component(component) => Some(component),
+            Entry::Property(_) => None,
+        })
+    }
+
+    /// Add a new property to the end of the component.
+    pub fn push_property(&mut self, property: impl Into<LosslessProperty>) {
+        self.entries.push(Entry::Property(property.into()));
+    }
+
+    /// Convert into a (non-lossless) component, e.g. for converting into a
+    /// [`VCalendar`].
+    pub fn to_component(&self) -> parser::Component<'static> {
+        parser::Component {
+            name: Cow::Owned(self.name.clone()),
+            sub_components: self
+                .sub_components()
+                .map(LosslessComponent::to_component)
+                .collect(),
+            properties: self.properties().map(|p| p.property.clone()).collect(),
+            span: None,
+        }
+    }
+
+    /// Write the component out, using the original text for all lines that
+    /// haven't been modified.
+    pub fn write<W: Write>(&self, writer: &mut ComponentWriter<W>) -> Result<(), Error> {
+        match &self.begin {
+            Some(raw) => writer.write_raw(raw)?,
+            None => writer.write_begin(&self.name)?,
+        }
+
+        for entry in &self.entries {
+            match entry {
+                Entry::Property(property) => match &property.raw {
+                    Some(raw) => writer.write_raw(raw)?,
+                    None => writer.write_property(&property.property)?,
+                },
+                Entry::Component(component) => component.write(writer)?,
+            }
+        }
+
+        match &self.end {
+            Some(raw) => writer.write_raw(raw),
+            None => writer.write_end(&self.name),
+        }
+    }
+}
+
+impl TryFrom<&LosslessComponent> for VCalendar {
+    type Error = Error;
+
+    fn try_from(component: &LosslessComponent) -> Result<Self, Self::Error> {
+        VCalendar::try_from(component.to_component())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parameters::ParameterSet, property::PropertyValue};
+
+    fn write(components: &[LosslessComponent]) -> String {
+        let mut writer = ComponentWriter::new(Vec::new());
+        for component in components {
+            component.write(&mut writer).unwrap();
+        }
+        String::from_utf8(writer.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn unmodified() {
+        let input = include_str!("../example.ics");
+        assert_eq!(write(&LosslessComponent::parse(input).unwrap()), input);
+
+        // Odd casing, line endings, folding and unknown components are all
+        // preserved.
+        let input = "\r\nbegin:vcalendar\nVersion:2.0\r\nBEGIN:X-Custom\r\nx-prop;X-Param=\"a\":b\r\n\tc\r\nEND:x-custom\r\nDTSTART:20200101T000000\r\n\r\nend:VCALENDAR";
+        assert_eq!(write(&LosslessComponent::parse(input).unwrap()), input);
+    }
+
+    #[test]
+    fn modified() {
+        let input = include_str!("../example.ics");
+        let mut components = LosslessComponent::parse(input).unwrap();
+
+        let event = components[0]
+            .sub_components_mut()
+            .find(|c| c.name() == "VEVENT")
+            .unwrap();
+
+        for property in event.properties_mut() {
+            if let Ok(Property::Summary(mut summary)) = property.typed() {
+                summary.value = "Renamed; event".to_string();
+                property.set_typed(&Property::Summary(summary));
+            }
+        }
+
+        event.push_property(Property::Location(PropertyValue {
+            value: "Room 1".to_string(),
+            parameters: ParameterSet::from(Vec::new()),
+            group: None,
+        }));
+
+        // Only the modified lines are re-serialized, and so use CRLF.
+        let output = write(&components);
+        let expected = input
+            .replace(
+                "SUMMARY:Example fortnightly event\n",
+                "SUMMARY:Renamed\\; event\r\n",
+            )
+            .replace("END:VEVENT", "LOCATION:Room 1\r\nEND:VEVENT");
+        assert_eq!(output, expected);
+
+        let calendar = VCalendar::try_from(&components[0]).unwrap();
+        let event = &calendar.events.values().next().unwrap().base_event;
+        assert_eq!(event.summary.as_deref(), Some("Renamed; event"));
+        assert_eq!(event.location.as_deref(), Some("Room 1"));
+    }
+
+    #[test]
+    fn errors() {
+        let err = LosslessComponent::parse("BEGIN:VEVENT\r\nEND:VTODO\r\n").unwrap_err();
+        assert!(matches!(err, Error::Syntax { .. }));
+        assert_eq!(err.context().span.unwrap().start.line, 2);
+
+        assert!(LosslessComponent::parse("BEGIN:VEVENT\r\n").is_err());
+        assert!(LosslessComponent::parse("SUMMARY:Test\r\n").is_err());
+    }
+
+    #[test]
+    fn limits() {
+        let limits = ParseLimits {
+            max_depth: 2,
+            max_line_length: 20,
+            max_properties_per_component: 2,
+            max_components: 10,
+            ..Default::default()
+        };
+        let parse = |data| LosslessComponent::parse_with_limits(data, &limits);
+
+        assert!(parse("BEGIN:A\r\nX:1\r\nY:2\r\nBEGIN:B\r\nEND:B\r\nEND:A\r\n").is_ok());
+
+        for (data, line) in [
+            ("BEGIN:A\r\nX:1\r\nY:2\r\nZ:3\r\nEND:A\r\n", 4),
+            (
+                "BEGIN:A\r\nBEGIN:B\r\nBEGIN:C\r\nEND:C\r\nEND:B\r\nEND:A\r\n",
+                3,
+            ),
+            ("BEGIN:A\r\nX:0123456789\r\n 0123456789\r\nEND:A\r\n", 2),
+            (&"BEGIN:A\r\nEND:A\r\n".repeat(11), 21),
+        ] {
+            let err = parse(data).unwrap_err();
+            assert!(matches!(err, Error::LimitExceeded { .. }), "{:?}", data);
+            assert_eq!(err.context().span.unwrap().start.line, line, "{:?}", data);
+        }
+
+        // Deep nesting is rejected by default, rather than overflowing the
+        // stack.
+        let err = LosslessComponent::parse(&"BEGIN:A\r\n".repeat(200_000)).unwrap_err();
+        assert!(matches!(err, Error::LimitExceeded { .. }));
+    }
+}
@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+
 use crate::parser;
 
 /// The valid parameters on properties
@@ -36,7 +38,7 @@ pub enum Parameter {
     /// allows this parameter, the default is INDIVIDUAL. Applications MUST treat
     /// x-name and iana-token values they don't recognize the same way as they
     /// would the UNKNOWN value.
-    UserType(String),
+    UserType(CuType),
 
     /// Purpose: To specify the calendar users that have delegated their
     /// participation to the calendar user specified by the property.
@@ -86,7 +88,7 @@ pub enum Parameter {
     ///
     /// If the value type parameter is ";VALUE=BINARY", then the inline encoding
     /// parameter MUST be specified with the value ";ENCODING=BASE64".
-    Encoding(String),
+    Encoding(Encoding),
 
     /// Purpose:  To specify the content type of a referenced object.
     ///
@@ -111,7 +113,7 @@ pub enum Parameter {
     /// interval.  If not specified on a property that allows this parameter, the
     /// default is BUSY.  Applications MUST treat x-name and iana-token values
     /// they don't recognize the same way as they would the BUSY value.
-    FreeBusy(String),
+    FreeBusy(FbType),
 
     /// Purpose: To specify the language for text values in a property or
     /// property parameter.
@@ -149,7 +151,7 @@ pub enum Parameter {
     /// specified on a property that allows this parameter, the default value is
     /// NEEDS-ACTION. Applications MUST treat x-name and iana-token values they
     /// don't recognize the same way as they would the NEEDS-ACTION value.
-    ParticipationStatus(String),
+    ParticipationStatus(PartStat),
 
     /// Purpose: To specify the effective range of recurrence instances from the
     /// instance specified by the recurrence identifier specified by the
@@ -166,7 +168,7 @@ pub enum Parameter {
     /// by the recurrence identifier and all subsequent instances. The value
     /// "THISANDPRIOR" is deprecated by this revision of iCalendar and MUST NOT
     /// be generated by applications.
-    Range(String),
+    Range(Range),
 
     /// Purpose: To specify the relationship of the alarm trigger with respect
     /// to the start or end of the calendar component.
@@ -179,7 +181,7 @@ pub enum Parameter {
     /// will set the alarm to trigger off the end of the calendar component.  If
     /// the parameter is not specified on an allowable property, then the
     /// default is START.
-    Related(String),
+    Related(Related),
 
     /// Purpose: To specify the type of hierarchical relationship associated
     /// with the calendar component specified by the property.
@@ -196,7 +198,7 @@ pub enum Parameter {
     /// relationship type is PARENT. Applications MUST treat x-name and
     /// iana-token values they don't recognize the same way as they would the
     /// PARENT value.
-    RelationshipType(String),
+    RelationshipType(RelType),
 
     /// Purpose: To specify the participation role for the calendar user
     /// specified by the property.
@@ -208,7 +210,7 @@ pub enum Parameter {
     /// parameter, the default value is REQ-PARTICIPANT. Applications MUST treat
     /// x-name and iana-token values they don't recognize the same way as they
     /// would the REQ-PARTICIPANT value.
-    ParticipationRole(String),
+    ParticipationRole(Role),
 
     /// Purpose:  To specify whether there is an expectation of a favor of a
     /// reply from the calendar user specified by the property value.
@@ -284,65 +286,176 @@ pub enum Parameter {
     /// parse the value data.
     ValueDataType(String),
 
+    /// Purpose: To specify different ways in which an image for a calendar or
+    /// component can be displayed. (RFC 7986)
+    ///
+    /// Description: This property parameter MAY be specified on "IMAGE"
+    /// properties.  In the absence of this parameter, the default value
+    /// "BADGE" MUST be used.
+    Display(Vec<DisplayType>),
+
+    /// Purpose: To specify a feature or features of a conference or broadcast
+    /// system. (RFC 7986)
+    ///
+    /// Description: This property parameter MAY be specified on the
+    /// "CONFERENCE" property.  Multiple values can be specified.
+    Feature(Vec<FeatureType>),
+
+    /// Purpose: To provide a human-readable label. (RFC 7986)
+    ///
+    /// Description: This property parameter MAY be specified on the
+    /// "CONFERENCE" property.  It is anticipated that other extensions to
+    /// iCalendar will re-use this property parameter on new properties.
+    Label(String),
+
     /// Any parameter that wasn't recognized.
     Other { name: String, values: Vec<String> },
 }
 
-impl From<parser::Parameter> for Parameter {
+impl From<parser::Parameter<'_>> for Parameter {
     fn from(p: parser::Parameter) -> Self {
-        // Note: we have already asserted that the values have at least one
-        // entry, hence the `.except(..)`.
+        let values: Vec<String> = p.values.into_iter().map(Cow::into_owned).collect();
+
+        // Note: the parser ensures there is at least one value, but
+        // parameters constructed by hand (or from jCal/xCal) may have none, in
+        // which case we use an empty value.
         match &p.name.to_ascii_uppercase() as &str {
             "ALTREP" => Parameter::AltRep {
-                uri: p.values.into_iter().last().expect("values"),
+                uri: values.into_iter().last().unwrap_or_default(),
             },
-            "CN" => Parameter::CN(p.values.into_iter().last().expect("values")),
-            "CUTYPE" => Parameter::UserType(p.values.into_iter().last().expect("values")),
-            "DELEGATED-FROM" => Parameter::DelegatedFrom(p.values),
-            "DELEGATED-TO" => Parameter::DelegatedTo(p.values),
+            "CN" => Parameter::CN(values.into_iter().last().unwrap_or_default()),
+            "CUTYPE" => Parameter::UserType(CuType::from(last_value(&values))),
+            "DELEGATED-FROM" => Parameter::DelegatedFrom(values),
+            "DELEGATED-TO" => Parameter::DelegatedTo(values),
             "DIR" => Parameter::Dir {
-                uri: p.values.into_iter().last().expect("values"),
+                uri: values.into_iter().last().unwrap_or_default(),
             },
-            "ENCODING" => Parameter::Encoding(p.values.into_iter().last().expect("values")),
-            "FMTTYPE" => Parameter::FormatType(p.values.into_iter().last().expect("values")),
-            "FBTYPE" => Parameter::FreeBusy(p.values.into_iter().last().expect("values")),
-            "LANGUAGE" => Parameter::Language(p.values.into_iter().last().expect("values")),
-            "MEMBER" => Parameter::Member(p.values),
-            "PARTSTAT" => {
-                Parameter::ParticipationStatus(p.values.into_iter().last().expect("values"))
-            }
-            "RANGE" => Parameter::Range(p.values.into_iter().last().expect("values")),
-            "RELATED" => Parameter::Related(p.values.into_iter().last().expect("values")),
-            "RELTYPE" => Parameter::RelationshipType(p.values.into_iter().last().expect("values")),
-            "ROLE" => Parameter::ParticipationRole(p.values.into_iter().last().expect("values")),
+            "ENCODING" => Parameter::Encoding(Encoding::from(last_value(&values))),
+            "FMTTYPE" => Parameter::FormatType(values.into_iter().last().unwrap_or_default()),
+            "FBTYPE" => Parameter::FreeBusy(FbType::from(last_value(&values))),
+            "LANGUAGE" => Parameter::Language(values.into_iter().last().unwrap_or_default()),
+            "MEMBER" => Parameter::Member(values),
+            "PARTSTAT" => Parameter::ParticipationStatus(PartStat::from(last_value(&values))),
+            "RANGE" => Parameter::Range(Range::from(last_value(&values))),
+            "RELATED" => Parameter::Related(Related::from(last_value(&values))),
+            "RELTYPE" => Parameter::RelationshipType(RelType::from(last_value(&values))),
+            "ROLE" => Parameter::ParticipationRole(Role::from(last_value(&values))),
             "RSVP" => Parameter::RSVPExpectation(
-                p.values
+                values
                     .into_iter()
                     .last()
-                    .expect("values")
-                    .to_ascii_uppercase()
-                    == "TRUE",
+                    .unwrap_or_default()
+                    .eq_ignore_ascii_case("TRUE"),
             ),
-            "SENT-BY" => Parameter::SentBy(p.values.into_iter().last().expect("values")),
-            "TZID" => Parameter::TimeZoneID(p.values.into_iter().last().expect("values")),
-            "VALUE" => Parameter::ValueDataType(p.values.into_iter().last().expect("values")),
+            "SENT-BY" => Parameter::SentBy(values.into_iter().last().unwrap_or_default()),
+            "TZID" => Parameter::TimeZoneID(values.into_iter().last().unwrap_or_default()),
+            "VALUE" => Parameter::ValueDataType(values.into_iter().last().unwrap_or_default()),
+            "DISPLAY" => {
+                Parameter::Display(values.iter().map(|v| DisplayType::from(&**v)).collect())
+            }
+            "FEATURE" => {
+                Parameter::Feature(values.iter().map(|v| FeatureType::from(&**v)).collect())
+            }
+            "LABEL" => Parameter::Label(values.into_iter().last().unwrap_or_default()),
 
             _ => Parameter::Other {
                 name: p.name.to_ascii_uppercase(),
-                values: p.values,
+                values,
             },
         }
     }
 }
 
+/// The last of a parameter's values, or an empty value if there are none.
+fn last_value(values: &[String]) -> &str {
+    values.last().map_or("", String::as_str)
+}
+
+impl Parameter {
+    /// The name of the parameter, e.g. `CN`.
+    pub fn name(&self) -> &str {
+        match self {
+            Parameter::AltRep { .. } => "ALTREP",
+            Parameter::CN(_) => "CN",
+            Parameter::UserType(_) => "CUTYPE",
+            Parameter::DelegatedFrom(_) => "DELEGATED-FROM",
+            Parameter::DelegatedTo(_) => "DELEGATED-TO",
+            Parameter::Dir { .. } => "DIR",
+            Parameter::Encoding(_) => "ENCODING",
+            Parameter::FormatType(_) => "FMTTYPE",
+            Parameter::FreeBusy(_) => "FBTYPE",
+            Parameter::Language(_) => "LANGUAGE",
+            Parameter::Member(_) => "MEMBER",
+            Parameter::ParticipationStatus(_) => "PARTSTAT",
+            Parameter::Range(_) => "RANGE",
+            Parameter::Related(_) => "RELATED",
+            Parameter::RelationshipType(_) => "RELTYPE",
+            Parameter::ParticipationRole(_) => "ROLE",
+            Parameter::RSVPExpectation(_) => "RSVP",
+            Parameter::SentBy(_) => "SENT-BY",
+            Parameter::TimeZoneID(_) => "TZID",
+            Parameter::ValueDataType(_) => "VALUE",
+            Parameter::Display(_) => "DISPLAY",
+            Parameter::Feature(_) => "FEATURE",
+            Parameter::Label(_) => "LABEL",
+            Parameter::Other { name, .. } => name,
+        }
+    }
+
+    /// The values of the parameter, as they would appear in a content line
+    /// (before any quoting or encoding).
+    pub fn values(&self) -> Vec<&str> {
+        match self {
+            Parameter::AltRep { uri } | Parameter::Dir { uri } => vec![uri],
+            Parameter::UserType(value) => vec![value.as_str()],
+            Parameter::Encoding(value) => vec![value.as_str()],
+            Parameter::FreeBusy(value) => vec![value.as_str()],
+            Parameter::ParticipationStatus(value) => vec![value.as_str()],
+            Parameter::Range(value) => vec![value.as_str()],
+            Parameter::Related(value) => vec![value.as_str()],
+            Parameter::RelationshipType(value) => vec![value.as_str()],
+            Parameter::ParticipationRole(value) => vec![value.as_str()],
+            Parameter::CN(value)
+            | Parameter::FormatType(value)
+            | Parameter::Language(value)
+            | Parameter::SentBy(value)
+            | Parameter::Label(value)
+            | Parameter::TimeZoneID(value)
+            | Parameter::ValueDataType(value) => vec![value],
+            Parameter::Display(values) => values.iter().map(DisplayType::as_str).collect(),
+            Parameter::Feature(values) => values.iter().map(FeatureType::as_str).collect(),
+            Parameter::DelegatedFrom(values)
+            | Parameter::DelegatedTo(values)
+            | Parameter::Member(values)
+            | Parameter::Other { values, .. } => values.iter().map(String::as_str).collect(),
+            Parameter::RSVPExpectation(true) => vec!["TRUE"],
+            Parameter::RSVPExpectation(false) => vec!["FALSE"],
+        }
+    }
+}
+
+impl From<&Parameter> for parser::Parameter<'static> {
+    fn from(p: &Parameter) -> Self {
+        parser::Parameter {
+            name: Cow::Owned(p.name().to_string()),
+            values: p
+                .values()
+                .into_iter()
+                .map(|v| Cow::Owned(v.to_string()))
+                .collect(),
+            span: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct ParameterSet {
     parameters: Vec<Parameter>,
 }
 
-impl<I> From<I> for ParameterSet
+impl<'a, I> From<I> for ParameterSet
 where
-    I: IntoIterator<Item = parser::Parameter>,
+    I: IntoIterator<Item = parser::Parameter<'a>>,
 {
     fn from(iter: I) -> Self {
         ParameterSet {
@@ -352,36 +465,777 @@ where
 }
 
 impl ParameterSet {
+    pub fn new() -> ParameterSet {
+        ParameterSet::default()
+    }
+
     pub fn parameters(&self) -> &[Parameter] {
         &self.parameters
     }
 
+    /// Convert back into parameters for a content line.
+    pub fn to_parsed(&self) -> Vec<parser::Parameter<'static>> {
+        self.parameters
+            .iter()
+            .map(parser::Parameter::from)
+            .collect()
+    }
+
+    /// Set a parameter, replacing any others with the same name (in the
+    /// position of the first).
+    pub fn set(&mut self, parameter: Parameter) -> &mut Self {
+        let name = parameter.name().to_string();
+        let same_name = |param: &Parameter| param.name().eq_ignore_ascii_case(&name);
+
+        let idx = self.parameters.iter().position(same_name);
+        self.parameters.retain(|param| !same_name(param));
+        self.parameters
+            .insert(idx.unwrap_or(self.parameters.len()), parameter);
+
+        self
+    }
+
+    /// Add a parameter, keeping any others with the same name.
+    pub fn insert(&mut self, parameter: Parameter) -> &mut Self {
+        self.parameters.push(parameter);
+        self
+    }
+
+    /// Remove all parameters with the given name, returning them.
+    pub fn remove(&mut self, name: &str) -> Vec<Parameter> {
+        let (removed, kept) = std::mem::take(&mut self.parameters)
+            .into_iter()
+            .partition(|param| param.name().eq_ignore_ascii_case(name));
+        self.parameters = kept;
+
+        removed
+    }
+
+    /// The first parameter with the given name.
+    pub fn get(&self, name: &str) -> Option<&Parameter> {
+        self.parameters
+            .iter()
+            .find(|param| param.name().eq_ignore_ascii_case(name))
+    }
+
+    fn find<'a, T: ?Sized>(&'a self, f: impl Fn(&'a Parameter) -> Option<&'a T>) -> Option<&'a T> {
+        self.parameters.iter().find_map(f)
+    }
+
+    pub fn get_alt_rep(&self) -> Option<&str> {
+        self.find(|param| match param {
+            Parameter::AltRep { uri } => Some(uri.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn get_common_name(&self) -> Option<&str> {
+        self.find(|param| match param {
+            Parameter::CN(name) => Some(name.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn get_user_type(&self) -> Option<&CuType> {
+        self.find(|param| match param {
+            Parameter::UserType(user_type) => Some(user_type),
+            _ => None,
+        })
+    }
+
+    pub fn get_delegated_from(&self) -> &[String] {
+        self.find(|param| match param {
+            Parameter::DelegatedFrom(users) => Some(users.as_slice()),
+            _ => None,
+        })
+        .unwrap_or_default()
+    }
+
+    pub fn get_delegated_to(&self) -> &[String] {
+        self.find(|param| match param {
+            Parameter::DelegatedTo(users) => Some(users.as_slice()),
+            _ => None,
+        })
+        .unwrap_or_default()
+    }
+
+    pub fn get_dir(&self) -> Option<&str> {
+        self.find(|param| match param {
+            Parameter::Dir { uri } => Some(uri.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn get_encoding(&self) -> Option<&Encoding> {
+        self.find(|param| match param {
+            Parameter::Encoding(encoding) => Some(encoding),
+            _ => None,
+        })
+    }
+
+    pub fn get_format_type(&self) -> Option<&str> {
+        self.find(|param| match param {
+            Parameter::FormatType(format_type) => Some(format_type.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn get_free_busy_type(&self) -> Option<&FbType> {
+        self.find(|param| match param {
+            Parameter::FreeBusy(free_busy_type) => Some(free_busy_type),
+            _ => None,
+        })
+    }
+
+    pub fn get_language(&self) -> Option<&str> {
+        self.find(|param| match param {
+            Parameter::Language(language) => Some(language.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn get_member(&self) -> &[String] {
+        self.find(|param| match param {
+            Parameter::Member(groups) => Some(groups.as_slice()),
+            _ => None,
+        })
+        .unwrap_or_default()
+    }
+
+    pub fn get_participation_status(&self) -> Option<&PartStat> {
+        self.find(|param| match param {
+            Parameter::ParticipationStatus(status) => Some(status),
+            _ => None,
+        })
+    }
+
+    pub fn get_range(&self) -> Option<&Range> {
+        self.find(|param| match param {
+            Parameter::Range(range) => Some(range),
+            _ => None,
+        })
+    }
+
+    pub fn get_related(&self) -> Option<&Related> {
+        self.find(|param| match param {
+            Parameter::Related(related) => Some(related),
+            _ => None,
+        })
+    }
+
+    pub fn get_relationship_type(&self) -> Option<&RelType> {
+        self.find(|param| match param {
+            Parameter::RelationshipType(relationship_type) => Some(relationship_type),
+            _ => None,
+        })
+    }
+
+    pub fn get_role(&self) -> Option<&Role> {
+        self.find(|param| match param {
+            Parameter::ParticipationRole(role) => Some(role),
+            _ => None,
+        })
+    }
+
+    pub fn get_rsvp(&self) -> Option<bool> {
+        self.find(|param| match param {
+            Parameter::RSVPExpectation(rsvp) => Some(rsvp),
+            _ => None,
+        })
+        .copied()
+    }
+
+    pub fn get_sent_by(&self) -> Option<&str> {
+        self.find(|param| match param {
+            Parameter::SentBy(user) => Some(user.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn get_tzid(&self) -> Option<&str> {
+        self.find(|param| match param {
+            Parameter::TimeZoneID(tzid) => Some(tzid.as_str()),
+            _ => None,
+        })
+    }
+
     pub fn get_value_data_type(&self) -> Option<&str> {
-        for param in &self.parameters {
-            if let Parameter::ValueDataType(data_type) = param {
-                return Some(data_type);
-            }
+        self.find(|param| match param {
+            Parameter::ValueDataType(data_type) => Some(data_type.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn get_display(&self) -> &[DisplayType] {
+        self.find(|param| match param {
+            Parameter::Display(display) => Some(display.as_slice()),
+            _ => None,
+        })
+        .unwrap_or_default()
+    }
+
+    pub fn get_feature(&self) -> &[FeatureType] {
+        self.find(|param| match param {
+            Parameter::Feature(features) => Some(features.as_slice()),
+            _ => None,
+        })
+        .unwrap_or_default()
+    }
+
+    pub fn get_label(&self) -> Option<&str> {
+        self.find(|param| match param {
+            Parameter::Label(label) => Some(label.as_str()),
+            _ => None,
+        })
+    }
+
+    /// The values of an unrecognized (e.g. `X-`) parameter.
+    pub fn get_other(&self, name: &str) -> Option<&[String]> {
+        self.find(|param| match param {
+            Parameter::Other {
+                name: param_name,
+                values,
+            } if param_name.eq_ignore_ascii_case(name) => Some(values.as_slice()),
+            _ => None,
+        })
+    }
+}
+
+/// The participation role of a calendar user, c.f. [`Parameter::ParticipationRole`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Role {
+    Chair,
+    ReqParticipant,
+    OptParticipant,
+    NonParticipant,
+    Other(String),
+}
+
+impl Default for Role {
+    /// The default role, when there's no `ROLE` parameter.
+    fn default() -> Self {
+        Role::ReqParticipant
+    }
+}
+
+impl From<&str> for Role {
+    fn from(value: &str) -> Self {
+        match &value.to_ascii_uppercase() as &str {
+            "CHAIR" => Role::Chair,
+            "REQ-PARTICIPANT" => Role::ReqParticipant,
+            "OPT-PARTICIPANT" => Role::OptParticipant,
+            "NON-PARTICIPANT" => Role::NonParticipant,
+            _ => Role::Other(value.to_string()),
         }
+    }
+}
 
-        None
+impl Role {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Role::Chair => "CHAIR",
+            Role::ReqParticipant => "REQ-PARTICIPANT",
+            Role::OptParticipant => "OPT-PARTICIPANT",
+            Role::NonParticipant => "NON-PARTICIPANT",
+            Role::Other(value) => value,
+        }
     }
 
-    pub fn get_encoding(&self) -> Option<&str> {
-        for param in &self.parameters {
-            if let Parameter::Encoding(encoding) = param {
-                return Some(encoding);
-            }
+    /// The value to act on, as unrecognized values must be treated as
+    /// `REQ-PARTICIPANT`.
+    pub fn effective(&self) -> Self {
+        match self {
+            Role::Other(_) => Role::ReqParticipant,
+            value => value.clone(),
         }
+    }
+}
+
+/// The participation status of a calendar user, c.f.
+/// [`Parameter::ParticipationStatus`].
+///
+/// Not all of the statuses are valid for every component, e.g. `COMPLETED`
+/// is only valid for to-dos.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PartStat {
+    NeedsAction,
+    Accepted,
+    Declined,
+    Tentative,
+    Delegated,
+    Completed,
+    InProcess,
+    Other(String),
+}
 
-        None
+impl Default for PartStat {
+    /// The default status, when there's no `PARTSTAT` parameter.
+    fn default() -> Self {
+        PartStat::NeedsAction
     }
-    pub fn get_tzid(&self) -> Option<&str> {
-        for param in &self.parameters {
-            if let Parameter::TimeZoneID(tzid) = param {
-                return Some(tzid);
-            }
+}
+
+impl From<&str> for PartStat {
+    fn from(value: &str) -> Self {
+        match &value.to_ascii_uppercase() as &str {
+            "NEEDS-ACTION" => PartStat::NeedsAction,
+            "ACCEPTED" => PartStat::Accepted,
+            "DECLINED" => PartStat::Declined,
+            "TENTATIVE" => PartStat::Tentative,
+            "DELEGATED" => PartStat::Delegated,
+            "COMPLETED" => PartStat::Completed,
+            "IN-PROCESS" => PartStat::InProcess,
+            _ => PartStat::Other(value.to_string()),
+        }
+    }
+}
+
+impl PartStat {
+    pub fn as_str(&self) -> &str {
+        match self {
+            PartStat::NeedsAction => "NEEDS-ACTION",
+            PartStat::Accepted => "ACCEPTED",
+            PartStat::Declined => "DECLINED",
+            PartStat::Tentative => "TENTATIVE",
+            PartStat::Delegated => "DELEGATED",
+            PartStat::Completed => "COMPLETED",
+            PartStat::InProcess => "IN-PROCESS",
+            PartStat::Other(value) => value,
+        }
+    }
+
+    /// The value to act on, as unrecognized values must be treated as
+    /// `NEEDS-ACTION`.
+    pub fn effective(&self) -> Self {
+        match self {
+            PartStat::Other(_) => PartStat::NeedsAction,
+            value => value.clone(),
+        }
+    }
+
+    /// Whether the calendar user has yet to reply, which includes any status
+    /// that isn't recognized.
+    pub fn is_pending(&self) -> bool {
+        matches!(self, PartStat::NeedsAction | PartStat::Other(_))
+    }
+}
+
+/// The type of a calendar user, c.f. [`Parameter::UserType`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CuType {
+    Individual,
+    Group,
+    Resource,
+    Room,
+    Unknown,
+    Other(String),
+}
+
+impl Default for CuType {
+    /// The default type, when there's no `CUTYPE` parameter.
+    fn default() -> Self {
+        CuType::Individual
+    }
+}
+
+impl From<&str> for CuType {
+    fn from(value: &str) -> Self {
+        match &value.to_ascii_uppercase() as &str {
+            "INDIVIDUAL" => CuType::Individual,
+            "GROUP" => CuType::Group,
+            "RESOURCE" => CuType::Resource,
+            "ROOM" => CuType::Room,
+            "UNKNOWN" => CuType::Unknown,
+            _ => CuType::Other(value.to_string()),
+        }
+    }
+}
+
+impl CuType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            CuType::Individual => "INDIVIDUAL",
+            CuType::Group => "GROUP",
+            CuType::Resource => "RESOURCE",
+            CuType::Room => "ROOM",
+            CuType::Unknown => "UNKNOWN",
+            CuType::Other(value) => value,
+        }
+    }
+
+    /// The value to act on, as unrecognized values must be treated as
+    /// `UNKNOWN`.
+    pub fn effective(&self) -> Self {
+        match self {
+            CuType::Other(_) => CuType::Unknown,
+            value => value.clone(),
+        }
+    }
+}
+
+/// The inline encoding of a property value, c.f. [`Parameter::Encoding`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Encoding {
+    EightBit,
+    Base64,
+    Other(String),
+}
+
+impl Default for Encoding {
+    /// Values are text unless an encoding is given.
+    fn default() -> Self {
+        Encoding::EightBit
+    }
+}
+
+impl From<&str> for Encoding {
+    fn from(value: &str) -> Self {
+        match &value.to_ascii_uppercase() as &str {
+            "8BIT" => Encoding::EightBit,
+            "BASE64" => Encoding::Base64,
+            _ => Encoding::Other(value.to_string()),
+        }
+    }
+}
+
+impl Encoding {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Encoding::EightBit => "8BIT",
+            Encoding::Base64 => "BASE64",
+            Encoding::Other(value) => value,
+        }
+    }
+}
+
+/// The type of a free/busy period, c.f. [`Parameter::FreeBusy`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FbType {
+    Free,
+    Busy,
+    BusyUnavailable,
+    BusyTentative,
+    Other(String),
+}
+
+impl Default for FbType {
+    /// The default type, when there's no `FBTYPE` parameter.
+    fn default() -> Self {
+        FbType::Busy
+    }
+}
+
+impl From<&str> for FbType {
+    fn from(value: &str) -> Self {
+        match &value.to_ascii_uppercase() as &str {
+            "FREE" => FbType::Free,
+            "BUSY" => FbType::Busy,
+            "BUSY-UNAVAILABLE" => FbType::BusyUnavailable,
+            "BUSY-TENTATIVE" => FbType::BusyTentative,
+            _ => FbType::Other(value.to_string()),
+        }
+    }
+}
+
+impl FbType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            FbType::Free => "FREE",
+            FbType::Busy => "BUSY",
+            FbType::BusyUnavailable => "BUSY-UNAVAILABLE",
+            FbType::BusyTentative => "BUSY-TENTATIVE",
+            FbType::Other(value) => value,
+        }
+    }
+
+    /// The value to act on, as unrecognized values must be treated as
+    /// `BUSY`.
+    pub fn effective(&self) -> Self {
+        match self {
+            FbType::Other(_) => FbType::Busy,
+            value => value.clone(),
+        }
+    }
+}
+
+/// The range of recurrence instances affected by a `RECURRENCE-ID`, c.f.
+/// [`Parameter::Range`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Range {
+    ThisAndFuture,
+    ThisAndPrior,
+    Other(String),
+}
+
+impl From<&str> for Range {
+    fn from(value: &str) -> Self {
+        match &value.to_ascii_uppercase() as &str {
+            "THISANDFUTURE" => Range::ThisAndFuture,
+            "THISANDPRIOR" => Range::ThisAndPrior,
+            _ => Range::Other(value.to_string()),
         }
+    }
+}
+
+impl Range {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Range::ThisAndFuture => "THISANDFUTURE",
+            Range::ThisAndPrior => "THISANDPRIOR",
+            Range::Other(value) => value,
+        }
+    }
+}
+
+/// What an alarm trigger is relative to, c.f. [`Parameter::Related`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Related {
+    Start,
+    End,
+    Other(String),
+}
+
+impl Default for Related {
+    /// Triggers are relative to the start unless otherwise given.
+    fn default() -> Self {
+        Related::Start
+    }
+}
+
+impl From<&str> for Related {
+    fn from(value: &str) -> Self {
+        match &value.to_ascii_uppercase() as &str {
+            "START" => Related::Start,
+            "END" => Related::End,
+            _ => Related::Other(value.to_string()),
+        }
+    }
+}
+
+impl Related {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Related::Start => "START",
+            Related::End => "END",
+            Related::Other(value) => value,
+        }
+    }
+}
+
+/// The relationship to another component, c.f.
+/// [`Parameter::RelationshipType`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelType {
+    Parent,
+    Child,
+    Sibling,
+    Other(String),
+}
+
+impl Default for RelType {
+    /// The default type, when there's no `RELTYPE` parameter.
+    fn default() -> Self {
+        RelType::Parent
+    }
+}
+
+impl From<&str> for RelType {
+    fn from(value: &str) -> Self {
+        match &value.to_ascii_uppercase() as &str {
+            "PARENT" => RelType::Parent,
+            "CHILD" => RelType::Child,
+            "SIBLING" => RelType::Sibling,
+            _ => RelType::Other(value.to_string()),
+        }
+    }
+}
+
+impl RelType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            RelType::Parent => "PARENT",
+            RelType::Child => "CHILD",
+            RelType::Sibling => "SIBLING",
+            RelType::Other(value) => value,
+        }
+    }
+
+    /// The value to act on, as unrecognized values must be treated as
+    /// `PARENT`.
+    pub fn effective(&self) -> Self {
+        match self {
+            RelType::Other(_) => RelType::Parent,
+            value => value.clone(),
+        }
+    }
+}
+
+/// How an image can be displayed, c.f. [`Parameter::Display`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DisplayType {
+    Badge,
+    Graphic,
+    Fullsize,
+    Thumbnail,
+    Other(String),
+}
+
+impl Default for DisplayType {
+    /// The default type, when there's no `DISPLAY` parameter.
+    fn default() -> Self {
+        DisplayType::Badge
+    }
+}
+
+impl From<&str> for DisplayType {
+    fn from(value: &str) -> Self {
+        match &value.to_ascii_uppercase() as &str {
+            "BADGE" => DisplayType::Badge,
+            "GRAPHIC" => DisplayType::Graphic,
+            "FULLSIZE" => DisplayType::Fullsize,
+            "THUMBNAIL" => DisplayType::Thumbnail,
+            _ => DisplayType::Other(value.to_string()),
+        }
+    }
+}
+
+impl DisplayType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            DisplayType::Badge => "BADGE",
+            DisplayType::Graphic => "GRAPHIC",
+            DisplayType::Fullsize => "FULLSIZE",
+            DisplayType::Thumbnail => "THUMBNAIL",
+            DisplayType::Other(value) => value,
+        }
+    }
+}
+
+/// A feature of a conference system, c.f. [`Parameter::Feature`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeatureType {
+    Audio,
+    Chat,
+    Feed,
+    Moderator,
+    Phone,
+    Screen,
+    Video,
+    Other(String),
+}
+
+impl From<&str> for FeatureType {
+    fn from(value: &str) -> Self {
+        match &value.to_ascii_uppercase() as &str {
+            "AUDIO" => FeatureType::Audio,
+            "CHAT" => FeatureType::Chat,
+            "FEED" => FeatureType::Feed,
+            "MODERATOR" => FeatureType::Moderator,
+            "PHONE" => FeatureType::Phone,
+            "SCREEN" => FeatureType::Screen,
+            "VIDEO" => FeatureType::Video,
+            _ => FeatureType::Other(value.to_string()),
+        }
+    }
+}
+
+impl FeatureType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            FeatureType::Audio => "AUDIO",
+            FeatureType::Chat => "CHAT",
+            FeatureType::Feed => "FEED",
+            FeatureType::Moderator => "MODERATOR",
+            FeatureType::Phone => "PHONE",
+            FeatureType::Screen => "SCREEN",
+            FeatureType::Video => "VIDEO",
+            FeatureType::Other(value) => value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameters(line: &str) -> ParameterSet {
+        let input = format!("BEGIN:VEVENT\r\n{}\r\nEND:VEVENT\r\n", line);
+        let mut component = parser::Component::from_str_to_stream(&input)
+            .unwrap()
+            .remove(0);
+        component.properties.remove(0).parameters.into()
+    }
+
+    #[test]
+    fn typed_parameters() {
+        let params = parameters(
+            "ATTENDEE;PARTSTAT=x-maybe;ROLE=opt-participant;CUTYPE=X-ROBOT;\
+            X-FOO=a,b:mailto:a@example.com",
+        );
+
+        let status = params.get_participation_status().unwrap();
+        assert_eq!(status, &PartStat::Other("x-maybe".to_string()));
+        assert_eq!(status.effective(), PartStat::NeedsAction);
+        assert_eq!(params.get_role(), Some(&Role::OptParticipant));
+        assert_eq!(params.get_user_type().unwrap().effective(), CuType::Unknown);
+        assert_eq!(params.get_rsvp(), None);
+        assert_eq!(params.get_other("x-foo").unwrap(), ["a", "b"]);
+
+        // Unrecognized values are kept as is.
+        assert_eq!(params.parameters()[0].values(), ["x-maybe"]);
+        assert_eq!(params.parameters()[1].values(), ["OPT-PARTICIPANT"]);
+
+        let params = parameters(
+            "FREEBUSY;FBTYPE=X-OOO;ENCODING=BASE64;RANGE=THISANDFUTURE;RELATED=END;\
+            RELTYPE=SIBLING:20200101T100000Z/PT1H",
+        );
+        assert_eq!(
+            params.get_free_busy_type().unwrap().effective(),
+            FbType::Busy
+        );
+        assert_eq!(params.get_encoding(), Some(&Encoding::Base64));
+        assert_eq!(params.get_range(), Some(&Range::ThisAndFuture));
+        assert_eq!(params.get_related(), Some(&Related::End));
+        assert_eq!(params.get_relationship_type(), Some(&RelType::Sibling));
+    }
+
+    #[test]
+    fn builder() {
+        let mut params = ParameterSet::new();
+        params
+            .set(Parameter::CN("Alice".to_string()))
+            .insert(Parameter::ParticipationRole(Role::Chair))
+            .insert(Parameter::Other {
+                name: "X-A".to_string(),
+                values: vec!["1".to_string()],
+            })
+            .insert(Parameter::Other {
+                name: "X-A".to_string(),
+                values: vec!["2".to_string()],
+            })
+            .set(Parameter::RSVPExpectation(true));
+
+        assert_eq!(params.get_common_name(), Some("Alice"));
+        assert_eq!(params.get_rsvp(), Some(true));
+        assert_eq!(params.parameters().len(), 5);
+
+        // Setting replaces all of the parameters with that name, in place.
+        params.set(Parameter::Other {
+            name: "x-a".to_string(),
+            values: vec!["3".to_string()],
+        });
+        let names = params
+            .parameters()
+            .iter()
+            .map(Parameter::name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["CN", "ROLE", "x-a", "RSVP"]);
+        assert_eq!(params.get("X-A").unwrap().values(), ["3"]);
 
-        None
+        let removed = params.remove("role");
+        assert_eq!(removed.len(), 1);
+        assert_eq!(params.get_role(), None);
+        assert!(params.remove("ROLE").is_empty());
+        assert_eq!(params.to_parsed()[2].values, ["TRUE"]);
     }
 }
@@ -1,54 +1,244 @@
-use anyhow::{bail, Error};
-use pest::{iterators::Pair, Parser};
+use std::{borrow::Cow, fmt::Display};
 
-fn strip_folds(s: &str) -> String {
-    let re = regex::Regex::new(r"\r?\n[\t ]").unwrap();
+use crate::error::Error;
+use crate::limits::ParseLimits;
+use crate::reader::{line_span, ComponentBuilder, ContentLines, Line};
+use crate::unescape::encode_param_value;
 
-    re.replace_all(s, "").into_owned()
+/// A position in the parsed data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Position {
+    /// The byte offset from the start of the data.
+    pub offset: usize,
+
+    /// The line number, starting from 1.
+    pub line: usize,
+
+    /// The column (in characters) within the line, starting from 1.
+    pub column: usize,
+}
+
+impl Position {
+    /// The position of the start of the data.
+    pub fn start() -> Position {
+        Position {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+}
+
+/// The region of the parsed data that an item came from. Line endings after
+/// the item are not included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.start.line, self.start.column)
+    }
+}
+
+/// Maps offsets in some text to positions in the parsed data.
+pub(crate) struct Locator<'a> {
+    text: &'a str,
+
+    /// The offsets of the start of each line in the text.
+    line_starts: Vec<usize>,
+
+    /// The position of the start of the text in the parsed data.
+    origin: Position,
+}
+
+impl<'a> Locator<'a> {
+    pub(crate) fn new(text: &'a str, origin: Position) -> Locator<'a> {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
+
+        Locator {
+            text,
+            line_starts,
+            origin,
+        }
+    }
+
+    pub(crate) fn position(&self, offset: usize) -> Position {
+        let line_idx = match self.line_starts.binary_search(&offset) {
+            Ok(idx) => idx,
+            Err(idx) => idx - 1,
+        };
+        let line_start = self.line_starts[line_idx];
+        let column = self.text[line_start..offset].chars().count() + 1;
+
+        if line_idx == 0 {
+            Position {
+                offset: self.origin.offset + offset,
+                line: self.origin.line,
+                column: self.origin.column + column - 1,
+            }
+        } else {
+            Position {
+                offset: self.origin.offset + offset,
+                line: self.origin.line + line_idx,
+                column,
+            }
+        }
+    }
 }
 
-#[derive(Parser)]
-#[grammar = "grammar.pest"]
-struct CalParser;
+/// Whether a parameter value needs to be quoted, i.e. it contains characters
+/// that aren't allowed in an unquoted value (those that aren't a `SAFE-CHAR`
+/// in RFC 5545).
+pub(crate) fn needs_quoting(value: &str) -> bool {
+    value.is_empty() || value.contains(&['"', ',', ':', ';'] as &[_])
+}
 
+/// A parsed component, e.g. a `VCALENDAR` or `VEVENT`.
+///
+/// Note: equality ignores where the components came from, i.e. the spans.
 #[derive(Debug, Clone)]
-pub struct Component {
-    pub name: String,
-    pub sub_components: Vec<Component>,
-    pub properties: Vec<Property>,
+pub struct Component<'a> {
+    pub name: Cow<'a, str>,
+    pub sub_components: Vec<Component<'a>>,
+    pub properties: Vec<Property<'a>>,
+
+    /// Where the component was parsed from, if it was parsed.
+    pub span: Option<Span>,
 }
 
-impl Component {
-    pub fn from_str_to_stream(data: &str) -> Result<Vec<Component>, Error> {
-        let pairs = CalParser::parse(Rule::component, &data)?;
+impl PartialEq for Component<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.sub_components == other.sub_components
+            && self.properties == other.properties
+    }
+}
 
-        pairs.map(Component::from_pair).collect()
+impl Eq for Component<'_> {}
+
+impl<'a> Component<'a> {
+    /// Parse all the top-level components in the data, borrowing from it
+    /// where possible.
+    ///
+    /// The data is checked against the default [`ParseLimits`].
+    pub fn from_str_to_stream(data: &'a str) -> Result<Vec<Component<'a>>, Error> {
+        Component::from_str_to_stream_with_limits(data, &ParseLimits::default())
     }
 
-    fn from_pair(pair: Pair<Rule>) -> Result<Component, Error> {
-        let span = pair.as_span();
-        let mut name = None;
-        let mut sub_components = Vec::new();
-        let mut properties = Vec::new();
+    /// Parse all the top-level components in the data, failing if it exceeds
+    /// the given limits.
+    pub fn from_str_to_stream_with_limits(
+        data: &'a str,
+        limits: &ParseLimits,
+    ) -> Result<Vec<Component<'a>>, Error> {
+        let mut builder = ComponentBuilder::new(*limits);
+        let mut components = Vec::new();
+
+        for (text, start) in ContentLines::new(data) {
+            let span = Some(line_span(text, start));
+
+            limits
+                .check_line_length(text.len())
+                .map_err(|e| e.with_span(span))?;
+
+            let line = Line::parse(text, start).map_err(|e| e.with_span(span))?;
+            if let Some(component) = builder.push(line).map_err(|e| e.with_span(span))? {
+                components.push(component);
+            }
+        }
+
+        builder.finish()?;
+
+        Ok(components)
+    }
+
+    /// Parse the data, skipping over any content lines that are malformed or
+    /// out of place rather than failing.
+    ///
+    /// Parsing resynchronises at the next content line, and components that
+    /// are missing their `END` line are closed when their parent is (or at
+    /// the end of the data). The returned [`LenientParse`] lists everything
+    /// that was skipped, and why.
+    ///
+    /// Lines that would exceed the default [`ParseLimits`] are skipped.
+    pub fn from_str_lenient(data: &'a str) -> LenientParse<'a> {
+        Component::from_str_lenient_with_limits(data, &ParseLimits::default())
+    }
+
+    /// Parse the data leniently, as [`Component::from_str_lenient`], skipping
+    /// any lines that would exceed the given limits.
+    pub fn from_str_lenient_with_limits(data: &'a str, limits: &ParseLimits) -> LenientParse<'a> {
+        let mut builder = ComponentBuilder::new(*limits);
+        let mut parsed = LenientParse {
+            components: Vec::new(),
+            skipped: Vec::new(),
+        };
+
+        // The end of the last content line that we used.
+        let mut last_end = Position::start();
+
+        for (text, start) in ContentLines::new(data) {
+            let span = line_span(text, start);
+
+            if limits.check_line_length(text.len()).is_err() {
+                parsed.skip(span, SkipReason::LimitExceeded);
+                continue;
+            }
+
+            let line = match Line::parse(text, start) {
+                Ok(line) => line,
+                Err(_) => {
+                    parsed.skip(span, SkipReason::InvalidLine);
+                    continue;
+                }
+            };
 
-        for inner_pair in pair.into_inner() {
-            match inner_pair.as_rule() {
-                Rule::name => name = Some(strip_folds(inner_pair.as_str())),
-                Rule::component => sub_components.push(Component::from_pair(inner_pair)?),
-                Rule::property => properties.push(Property::from_pair(inner_pair)?),
-                _ => bail!("Unexpected type {:?}", inner_pair.as_rule()),
+            match &line {
+                Line::End { name, .. } if !builder.is_open(name) => {
+                    parsed.skip(span, SkipReason::UnexpectedEnd(name.to_string()));
+                    continue;
+                }
+                Line::End { name, .. } => {
+                    while builder
+                        .current()
+                        .is_some_and(|c| !c.name.eq_ignore_ascii_case(name))
+                    {
+                        parsed.close_unterminated(&mut builder, last_end);
+                    }
+                }
+                Line::Property(_) if builder.depth() == 0 => {
+                    parsed.skip(span, SkipReason::PropertyOutsideComponent);
+                    continue;
+                }
+                _ => {}
             }
+
+            match builder.push(line) {
+                Ok(Some(component)) => parsed.components.push(component),
+                Ok(None) => {}
+                Err(Error::LimitExceeded { .. }) => {
+                    parsed.skip(span, SkipReason::LimitExceeded);
+                    continue;
+                }
+                Err(_) => {
+                    parsed.skip(span, SkipReason::InvalidLine);
+                    continue;
+                }
+            }
+
+            last_end = span.end;
         }
 
-        if let Some(name) = name {
-            Ok(Component {
-                name,
-                sub_components,
-                properties,
-            })
-        } else {
-            bail!("No name for component: {:?}", span.as_str());
+        while builder.depth() > 0 {
+            parsed.close_unterminated(&mut builder, last_end);
         }
+
+        parsed
     }
 
     pub fn as_string(&self) -> String {
@@ -62,45 +252,61 @@ impl Component {
 
         format!("BEGIN:{}\n{}\nEND:{}", self.name, lines, self.name)
     }
+
+    /// Convert into a component that doesn't borrow from the parsed data.
+    pub fn into_owned(self) -> Component<'static> {
+        Component {
+            name: Cow::Owned(self.name.into_owned()),
+            sub_components: self
+                .sub_components
+                .into_iter()
+                .map(Component::into_owned)
+                .collect(),
+            properties: self
+                .properties
+                .into_iter()
+                .map(Property::into_owned)
+                .collect(),
+            span: self.span,
+        }
+    }
 }
 
+/// A parsed property.
+///
+/// Note: equality ignores where the properties came from, i.e. the spans.
 #[derive(Debug, Clone)]
-pub struct Property {
-    pub name: String,
-    pub value: String,
-    pub parameters: Vec<Parameter>,
-}
-
-impl Property {
-    fn from_pair(pair: Pair<Rule>) -> Result<Property, Error> {
-        let span = pair.as_span();
-        let mut name = None;
-        let mut value = None;
-        let mut parameters = Vec::new();
-
-        for inner_pair in pair.into_inner() {
-            match inner_pair.as_rule() {
-                Rule::name => name = Some(strip_folds(inner_pair.as_str())),
-                Rule::property_value => value = Some(strip_folds(inner_pair.as_str())),
-                Rule::param => parameters.push(Parameter::from_pair(inner_pair)?),
-                _ => bail!("Unexpected type {:?}", inner_pair.as_rule()),
-            }
-        }
+pub struct Property<'a> {
+    /// The (vCard style) group of the property, e.g. `item1` for `item1.URL`.
+    pub group: Option<Cow<'a, str>>,
+    pub name: Cow<'a, str>,
+    pub value: Cow<'a, str>,
+    pub parameters: Vec<Parameter<'a>>,
+
+    /// Where the property was parsed from, if it was parsed.
+    pub span: Option<Span>,
+}
 
-        if let (Some(name), Some(value)) = (name, value) {
-            Ok(Property {
-                name,
-                value,
-                parameters,
-            })
-        } else {
-            bail!("No name for property: {:?}", span.as_str());
-        }
+impl PartialEq for Property<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.group == other.group
+            && self.name == other.name
+            && self.value == other.value
+            && self.parameters == other.parameters
     }
+}
 
+impl Eq for Property<'_> {}
+
+impl<'a> Property<'a> {
     pub fn as_string(&self) -> String {
+        let name = match &self.group {
+            Some(group) => format!("{}.{}", group, self.name),
+            None => self.name.to_string(),
+        };
+
         if self.parameters.is_empty() {
-            format!("{}:{}", self.name, self.value)
+            format!("{}:{}", name, self.value)
         } else {
             let params = self
                 .parameters
@@ -109,43 +315,47 @@ impl Property {
                 .collect::<Vec<_>>()
                 .join(";");
 
-            format!("{};{}:{}", self.name, params, self.value)
+            format!("{};{}:{}", name, params, self.value)
+        }
+    }
+
+    /// Convert into a property that doesn't borrow from the parsed data.
+    pub fn into_owned(self) -> Property<'static> {
+        Property {
+            group: self.group.map(|group| Cow::Owned(group.into_owned())),
+            name: Cow::Owned(self.name.into_owned()),
+            value: Cow::Owned(self.value.into_owned()),
+            parameters: self
+                .parameters
+                .into_iter()
+                .map(Parameter::into_owned)
+                .collect(),
+            span: self.span,
         }
     }
 }
 
+/// A parsed property parameter.
+///
+/// Note: equality ignores where the parameters came from, i.e. the spans.
 #[derive(Debug, Clone)]
-pub struct Parameter {
-    pub name: String,
-    pub values: Vec<String>,
-}
-
-impl Parameter {
-    fn from_pair(pair: Pair<Rule>) -> Result<Parameter, Error> {
-        let span = pair.as_span();
-        let mut name = None;
-        let mut values = Vec::new();
-        for inner_pair in pair.into_inner() {
-            match inner_pair.as_rule() {
-                Rule::name => name = Some(strip_folds(inner_pair.as_str())),
-                Rule::param_value => {
-                    values.push(strip_folds(inner_pair.as_str().trim_matches('"')))
-                }
-                _ => bail!("Unexpected type {:?}", inner_pair.as_rule()),
-            }
-        }
+pub struct Parameter<'a> {
+    pub name: Cow<'a, str>,
+    pub values: Vec<Cow<'a, str>>,
 
-        if values.is_empty() {
-            bail!("No values for param {:?}", span.as_str());
-        }
+    /// Where the parameter was parsed from, if it was parsed.
+    pub span: Option<Span>,
+}
 
-        if let Some(name) = name {
-            Ok(Parameter { name, values })
-        } else {
-            bail!("No name for parameter: {:?}", span.as_str());
-        }
+impl PartialEq for Parameter<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.values == other.values
     }
+}
+
+impl Eq for Parameter<'_> {}
 
+impl<'a> Parameter<'a> {
     pub fn as_string(&self) -> String {
         // We need to convert the values into a comma seperated string, quoting
         // values that need quoting.
@@ -153,10 +363,11 @@ impl Parameter {
             .values
             .iter()
             .map(|v| {
-                if v.is_empty() || v.contains(&['`', ':', ';'] as &[_]) {
+                let v = encode_param_value(v);
+                if needs_quoting(&v) {
                     format!(r#""{}""#, v)
                 } else {
-                    v.to_string()
+                    v.into_owned()
                 }
             })
             .collect::<Vec<_>>()
@@ -164,6 +375,93 @@ impl Parameter {
 
         format!("{}={}", self.name, values)
     }
+
+    /// Convert into a parameter that doesn't borrow from the parsed data.
+    pub fn into_owned(self) -> Parameter<'static> {
+        Parameter {
+            name: Cow::Owned(self.name.into_owned()),
+            values: self
+                .values
+                .into_iter()
+                .map(|v| Cow::Owned(v.into_owned()))
+                .collect(),
+            span: self.span,
+        }
+    }
+}
+
+/// The result of parsing data with [`Component::from_str_lenient`].
+#[derive(Debug, Clone)]
+pub struct LenientParse<'a> {
+    /// The top-level components that were recovered.
+    pub components: Vec<Component<'a>>,
+
+    /// The regions of the data that were skipped, in order.
+    pub skipped: Vec<SkippedRegion>,
+}
+
+impl<'a> LenientParse<'a> {
+    fn skip(&mut self, span: Span, reason: SkipReason) {
+        self.skipped.push(SkippedRegion { span, reason });
+    }
+
+    /// Close the innermost open component, recording that it was missing its
+    /// `END` line.
+    fn close_unterminated(&mut self, builder: &mut ComponentBuilder<'a>, end: Position) {
+        if let Some(current) = builder.current() {
+            let reason = SkipReason::MissingEnd(current.name.to_string());
+            let start = current.span.map_or(end, |span| span.start);
+            self.skip(Span { start, end }, reason);
+        }
+
+        if let Some(component) = builder.close(end) {
+            self.components.push(component);
+        }
+    }
+}
+
+/// A region of the data that was skipped by [`Component::from_str_lenient`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedRegion {
+    /// The region of the data. For [`SkipReason::MissingEnd`] this is the
+    /// component that wasn't terminated.
+    pub span: Span,
+
+    pub reason: SkipReason,
+}
+
+/// Why a region of the data was skipped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The content line couldn't be parsed, e.g. a property without a colon.
+    InvalidLine,
+
+    /// A property appeared outside of any component.
+    PropertyOutsideComponent,
+
+    /// An `END` line didn't match any open component.
+    UnexpectedEnd(String),
+
+    /// A component was missing its `END` line, and so was closed implicitly.
+    MissingEnd(String),
+
+    /// The line would have exceeded the [`ParseLimits`], e.g. it was too long
+    /// or there were too many components.
+    LimitExceeded,
+}
+
+impl Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipReason::InvalidLine => write!(f, "invalid content line"),
+            SkipReason::PropertyOutsideComponent => {
+                write!(f, "property found outside of a component")
+            }
+            SkipReason::UnexpectedEnd(name) => write!(f, "unexpected END:{}", name),
+            SkipReason::MissingEnd(name) => write!(f, "missing END:{}", name),
+            SkipReason::LimitExceeded => write!(f, "limit exceeded"),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -171,6 +469,11 @@ mod tests {
     use anyhow::Result;
 
     use super::*;
+    use crate::lexer::Lexer;
+
+    fn parse_parameter(text: &str) -> Result<Parameter<'_>> {
+        Ok(Lexer::new(text, Position::start()).parameter()?)
+    }
 
     #[test]
     fn parameter_fold() -> Result<()> {
@@ -182,9 +485,7 @@ mod tests {
             "CN\n =Test Foo",
         ];
         for test_case in test_cases {
-            let mut pairs = CalParser::parse(Rule::param, test_case)?;
-
-            let param = Parameter::from_pair(pairs.next().unwrap())?;
+            let param = parse_parameter(test_case)?;
 
             assert_eq!(param.name, "CN");
             assert_eq!(param.values, &["Test Foo"]);
@@ -192,6 +493,39 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn parameter_multiple_values() -> Result<()> {
+        let test_case = r#"MEMBER="mailto:a@example.com",b,"c,d""#;
+
+        let param = parse_parameter(test_case)?;
+
+        assert_eq!(param.values, &["mailto:a@example.com", "b", "c,d"]);
+        assert_eq!(param.as_string(), test_case);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parameter_caret_encoding() -> Result<()> {
+        let test_cases = [
+            (r#"CN="O'Brien ^'Bob^'""#, r#"O'Brien "Bob""#),
+            ("CN=Line^nBreak", "Line\nBreak"),
+            ("CN=^^^x^", "^^x^"),
+        ];
+
+        for (test_case, expected) in test_cases {
+            let param = parse_parameter(test_case)?;
+
+            assert_eq!(param.values, &[expected]);
+
+            // Re-encoding and parsing should give back the same value.
+            let encoded = param.as_string();
+            assert_eq!(parse_parameter(&encoded)?, param);
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn property_fold() -> Result<()> {
         let test_cases = [
@@ -202,9 +536,7 @@ mod tests {
             "ORGANIZER\n ;CN=Test Foo:mailto:test@example.com\n",
         ];
         for test_case in test_cases {
-            let mut pairs = CalParser::parse(Rule::property, test_case)?;
-
-            let property = Property::from_pair(pairs.next().unwrap())?;
+            let property = Lexer::new(test_case, Position::start()).property()?;
 
             assert_eq!(property.name, "ORGANIZER");
             assert_eq!(property.value, "mailto:test@example.com");
@@ -215,4 +547,168 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn borrows_unless_folded() -> Result<()> {
+        let input = "BEGIN:VEVENT\nSUMMARY;CN=Foo:Test\nDESCRIPTION:Long\n  text\nEND:VEVENT\n";
+
+        let component = Component::from_str_to_stream(input)?.remove(0);
+
+        assert!(matches!(component.name, Cow::Borrowed("VEVENT")));
+
+        let summary = &component.properties[0];
+        assert!(matches!(summary.name, Cow::Borrowed("SUMMARY")));
+        assert!(matches!(summary.value, Cow::Borrowed("Test")));
+        assert!(matches!(
+            summary.parameters[0].values[0],
+            Cow::Borrowed("Foo")
+        ));
+
+        let description = &component.properties[1];
+        assert!(matches!(description.value, Cow::Owned(_)));
+        assert_eq!(description.value, "Long text");
+
+        // Converting to an owned component shouldn't change anything.
+        let owned: Component<'static> = component.clone().into_owned();
+        assert_eq!(owned, component);
+
+        Ok(())
+    }
+
+    #[test]
+    fn spans() -> Result<()> {
+        let input =
+            "BEGIN:VEVENT\r\nSUMMARY;CN=Foo:Test\r\nDESCRIPTION:Long\r\n  text\r\nEND:VEVENT\r\n";
+
+        let component = Component::from_str_to_stream(input)?.remove(0);
+
+        let span = component.span.unwrap();
+        assert_eq!(span.start, Position::start());
+        assert_eq!((span.end.line, span.end.column), (5, 11));
+        assert_eq!(span.end.offset, input.len() - 2);
+
+        let summary = component.properties[0].span.unwrap();
+        assert_eq!(summary.start.offset, 14);
+        assert_eq!((summary.start.line, summary.start.column), (2, 1));
+        assert_eq!((summary.end.line, summary.end.column), (2, 20));
+
+        let parameter = component.properties[0].parameters[0].span.unwrap();
+        assert_eq!((parameter.start.line, parameter.start.column), (2, 9));
+        assert_eq!(
+            &input[parameter.start.offset..parameter.end.offset],
+            "CN=Foo"
+        );
+
+        let description = component.properties[1].span.unwrap();
+        assert_eq!((description.start.line, description.start.column), (3, 1));
+        assert_eq!((description.end.line, description.end.column), (4, 7));
+        assert_eq!(description.to_string(), "line 3, column 1");
+
+        Ok(())
+    }
+
+    #[test]
+    fn lenient() {
+        let input = "\r\n\
+            BEGIN:VCALENDAR\r\n\
+            VERSION:2.0\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:1\r\n\
+            NO COLON HERE\r\n\
+            SUMMARY:Test\r\n\
+            END:VEVENT\r\n\
+            \r\n\
+            BEGIN:VEVENT\r\n\
+            UID:2\r\n\
+            END:VTODO\r\n\
+            END:VCALENDAR\r\n\
+            X-TRAILING:1";
+
+        let parsed = Component::from_str_lenient(input);
+
+        let skipped = parsed
+            .skipped
+            .iter()
+            .map(|s| (s.span.start.line, s.span.end.line, s.reason.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            skipped,
+            &[
+                (6, 6, "invalid content line".to_string()),
+                (12, 12, "unexpected END:VTODO".to_string()),
+                (10, 11, "missing END:VEVENT".to_string()),
+                (14, 14, "property found outside of a component".to_string()),
+            ]
+        );
+
+        assert_eq!(parsed.components.len(), 1);
+        let calendar = &parsed.components[0];
+        assert_eq!(calendar.properties.len(), 1);
+        assert_eq!(calendar.sub_components.len(), 2);
+        assert_eq!(calendar.sub_components[0].properties.len(), 2);
+        assert_eq!(calendar.sub_components[1].properties[0].value, "2");
+        assert_eq!(calendar.span.unwrap().end.line, 13);
+
+        // Borrowing is preserved.
+        assert!(matches!(calendar.properties[0].value, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn lenient_matches_strict() -> Result<()> {
+        let input = include_str!("../example.ics");
+
+        let parsed = Component::from_str_lenient(input);
+
+        assert!(parsed.skipped.is_empty());
+        assert_eq!(parsed.components, Component::from_str_to_stream(input)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn quoting() {
+        for value in ["", "a,b", "mailto:a@example.com", "a;b", "\"a\""] {
+            assert!(needs_quoting(value), "{:?}", value);
+        }
+        for value in ["plain", "Doe Jane", "a`b", "^'"] {
+            assert!(!needs_quoting(value), "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn limits() {
+        let limits = ParseLimits {
+            max_depth: 2,
+            max_line_length: 20,
+            max_properties_per_component: 2,
+            max_components: 10,
+            ..Default::default()
+        };
+        let parse = |data| Component::from_str_to_stream_with_limits(data, &limits);
+
+        assert!(parse("BEGIN:A\r\nX:1\r\nY:2\r\nBEGIN:B\r\nEND:B\r\nEND:A\r\n").is_ok());
+
+        for (data, line) in [
+            ("BEGIN:A\r\nX:1\r\nY:2\r\nZ:3\r\nEND:A\r\n", 4),
+            (
+                "BEGIN:A\r\nBEGIN:B\r\nBEGIN:C\r\nEND:C\r\nEND:B\r\nEND:A\r\n",
+                3,
+            ),
+            ("BEGIN:A\r\nX:0123456789\r\n 0123456789\r\nEND:A\r\n", 2),
+            (&"BEGIN:A\r\nEND:A\r\n".repeat(11), 21),
+        ] {
+            let err = parse(data).unwrap_err();
+            assert!(matches!(err, Error::LimitExceeded { .. }), "{:?}", data);
+            assert_eq!(err.context().span.unwrap().start.line, line, "{:?}", data);
+        }
+
+        // Lenient parsing skips the lines instead.
+        let parsed = Component::from_str_lenient_with_limits(
+            "BEGIN:A\r\nBEGIN:B\r\nBEGIN:C\r\nX:1\r\nEND:C\r\nEND:B\r\nEND:A\r\n",
+            &limits,
+        );
+        assert_eq!(parsed.components.len(), 1);
+        assert_eq!(parsed.components[0].sub_components[0].properties.len(), 1);
+        assert_eq!(parsed.skipped[0].reason, SkipReason::LimitExceeded);
+    }
 }
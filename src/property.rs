@@ -1,15 +1,39 @@
-use std::{collections::VecDeque, convert::TryFrom, fmt::Debug, ops::Add, str::FromStr};
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    convert::TryFrom,
+    fmt::{self, Debug},
+    ops::Add,
+    str::FromStr,
+};
+
+use crate::{
+    color::Color,
+    components::VCalendar,
+    error::Error,
+    limits::ParseLimits,
+    unescape::{escape, escape_list, split_escaped, unescape_lenient, unescape_list_lenient},
+};
 
-use crate::{components::VCalendar, unescape::unescape};
-use anyhow::{bail, format_err, Context, Error};
 use chrono::{
-    Date, DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Timelike,
-    Utc, Weekday,
+    Date, DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone,
+    Timelike, Utc, Weekday,
 };
 use itertools::Itertools;
 use url::Url;
 
-use crate::{parameters::ParameterSet, parser};
+use crate::{
+    parameters::{CuType, Encoding, ParameterSet, PartStat, Related, Role},
+    parser,
+};
+
+/// The largest number of seconds a `Duration` can hold (it's stored in
+/// milliseconds).
+const MAX_DURATION_SECONDS: i64 = i64::MAX / 1000;
+
+/// The last year that iCalendar can represent. Recurrences stop here rather
+/// than running into the limits of chrono's types.
+const MAX_YEAR: i32 = 9999;
 
 #[derive(Debug, Clone)]
 pub enum Property {
@@ -231,8 +255,8 @@ pub enum Property {
     URL(PropertyValue<Url>),
     UID(PropertyValue<String>),
 
-    ExceptionDateTimes(PropertyValue<DateOrDateTime>),
-    RecurrenceDateTimes(PropertyValue<DateDateTimeOrPeriod>),
+    ExceptionDateTimes(PropertyValue<Vec<DateOrDateTime>>),
+    RecurrenceDateTimes(PropertyValue<Vec<DateDateTimeOrPeriod>>),
     RecurrenceRule(PropertyValue<RecurRule>),
 
     Action(PropertyValue<String>),
@@ -249,239 +273,383 @@ pub enum Property {
     ProductIdentifier(PropertyValue<String>),
     Version(PropertyValue<String>),
 
+    /// The name of a calendar, which can be given in several languages (with
+    /// the `LANGUAGE` parameter). (RFC 7986)
+    Name(PropertyValue<String>),
+
+    /// A color to display a calendar or component with. (RFC 7986)
+    Color(PropertyValue<Color>),
+
+    /// An image for a calendar or component, e.g. a logo, with its `DISPLAY`
+    /// parameter giving how it should be displayed. (RFC 7986)
+    Image(PropertyValue<AttachEnum>),
+
+    /// How to join a conference for a component, with `FEATURE` and `LABEL`
+    /// parameters describing it. (RFC 7986)
+    Conference(PropertyValue<AttachEnum>),
+
+    /// The suggested minimum interval for polling a calendar for changes.
+    /// (RFC 7986)
+    RefreshInterval(PropertyValue<Duration>),
+
+    /// Where a calendar can be refreshed from. (RFC 7986)
+    Source(PropertyValue<Url>),
+
     // TODO: Add the others
     Other(String, PropertyValue<String>),
 }
 
-impl TryFrom<parser::Property> for Property {
+impl TryFrom<parser::Property<'_>> for Property {
     type Error = Error;
 
-    fn try_from(property: parser::Property) -> Result<Self, Self::Error> {
-        let parameters: ParameterSet = property.parameters.iter().cloned().into();
+    fn try_from(property: parser::Property<'_>) -> Result<Self, Self::Error> {
+        let name = property.name.to_string();
+        let span = property.span;
 
-        let prop = match &property.name.to_ascii_uppercase() as &str {
-            "ATTACH" => {
-                if let Some(data_type) = parameters.get_value_data_type() {
-                    match &data_type.to_ascii_uppercase() as &str {
-                        "BINARY" => {
-                            if parameters.get_encoding() != Some("BASE64") {
-                                bail!("Unknown encoding for binary attach property");
-                            }
+        Property::from_parsed(property).map_err(|e| e.with_property(&name).with_span(span))
+    }
+}
 
-                            let value = base64::decode(&property.value)?;
-                            Property::Attach(PropertyValue {
-                                value: AttachEnum::Binary(value),
-                                parameters,
-                            })
-                        }
-                        "URI" => Property::Attach(PropertyValue {
-                            value: AttachEnum::Url(property.value.parse()?),
-                            parameters,
-                        }),
-                        data_type => Property::Attach(PropertyValue {
-                            value: AttachEnum::Other {
-                                data_type: data_type.to_string(),
-                                value: property.value.clone(),
-                            },
-                            parameters,
-                        }),
-                    }
-                } else {
-                    Property::Attach(PropertyValue {
-                        value: AttachEnum::Url(property.value.parse()?),
-                        parameters,
-                    })
-                }
-            }
+impl Property {
+    fn from_parsed(property: parser::Property<'_>) -> Result<Self, Error> {
+        let parameters: ParameterSet = property.parameters.into();
+        let group = property.group.map(Cow::into_owned);
+
+        let prop = match &property.name.to_ascii_uppercase() as &str {
+            "ATTACH" => Property::Attach(PropertyValue {
+                value: AttachEnum::parse_from(&property.value, &parameters)?,
+                parameters,
+                group,
+            }),
             "CATEGORIES" => Property::Categories(PropertyValue {
-                value: property
-                    .value
-                    .split(',')
-                    .map(|s| unescape(&s.trim().to_string()))
-                    .collect::<Result<_, _>>()?,
+                value: unescape_list_lenient(&property.value),
                 parameters,
+                group,
             }),
-            // "CLASS" => todo!(),
+            "CLASS" => {
+                let value = match &property.value.to_ascii_uppercase() as &str {
+                    "PUBLIC" => ClassEnum::Public,
+                    "PRIVATE" => ClassEnum::Private,
+                    "CONFIDENTIAL" => ClassEnum::Confidential,
+                    _ => ClassEnum::Other(property.value.to_string()),
+                };
+                Property::Class(PropertyValue {
+                    value,
+                    parameters,
+                    group,
+                })
+            }
             "COMMENT" => Property::Comment(PropertyValue {
-                value: unescape(&property.value)?,
+                value: unescape_lenient(&property.value).into_owned(),
                 parameters,
+                group,
             }),
             "DESCRIPTION" => Property::Description(PropertyValue {
-                value: unescape(&property.value)?,
+                value: unescape_lenient(&property.value).into_owned(),
                 parameters,
+                group,
+            }),
+            "GEO" => Property::Geo(PropertyValue {
+                value: parse_geo(&property.value)?,
+                parameters,
+                group,
             }),
-            // "GEO" => todo!(),
             "LOCATION" => Property::Location(PropertyValue {
-                value: unescape(&property.value)?,
+                value: unescape_lenient(&property.value).into_owned(),
                 parameters,
+                group,
             }),
             "PERCENT-COMPLETE" => Property::PercentComplete(PropertyValue {
                 value: property.value.parse()?,
                 parameters,
+                group,
             }),
             "PRIORITY" => Property::Priority(PropertyValue {
                 value: property.value.parse()?,
                 parameters,
+                group,
             }),
             "RESOURCES" => Property::Resources(PropertyValue {
-                value: property
-                    .value
-                    .split(',')
-                    .map(|s| unescape(&s.trim().to_string()))
-                    .collect::<Result<_, _>>()?,
+                value: unescape_list_lenient(&property.value),
                 parameters,
+                group,
             }),
-            // "STATUS" => todo!(),
+            "STATUS" => {
+                let value = match &property.value.to_ascii_uppercase() as &str {
+                    "CANCELLED" => StatusEnum::Cancelled,
+                    "TENTATIVE" => StatusEnum::Tentative,
+                    "CONFIRMED" => StatusEnum::Confirmed,
+                    "NEEDS-ACTION" => StatusEnum::NeesAction,
+                    "COMPLETED" => StatusEnum::Completed,
+                    "IN-PROCESS" => StatusEnum::InProgress,
+                    "DRAFT" => StatusEnum::Draft,
+                    "FINAL" => StatusEnum::Final,
+                    _ => StatusEnum::Other(property.value.to_string()),
+                };
+                Property::Status(PropertyValue {
+                    value,
+                    parameters,
+                    group,
+                })
+            }
             "SUMMARY" => Property::Summary(PropertyValue {
-                value: unescape(&property.value)?,
+                value: unescape_lenient(&property.value).into_owned(),
+                parameters,
+                group,
+            }),
+            "COMPLETED" => Property::Completed(PropertyValue {
+                value: parse_utc_date_time(&property.value, &parameters)?,
                 parameters,
+                group,
             }),
-            // "COMPLETED" => todo!(),
             "DTEND" => Property::End(PropertyValue {
                 value: DateOrDateTime::parse_from(&property.value, &parameters)?,
                 parameters,
+                group,
             }),
             "DUE" => Property::Due(PropertyValue {
                 value: DateOrDateTime::parse_from(&property.value, &parameters)?,
                 parameters,
+                group,
             }),
             "DTSTART" => Property::Start(PropertyValue {
                 value: DateOrDateTime::parse_from(&property.value, &parameters)?,
                 parameters,
+                group,
             }),
-            // "DURATION" => todo!(),
-            // "FREEBUSY" => todo!(),
+            "DURATION" => Property::Duration(PropertyValue {
+                value: parse_duration(&property.value)?,
+                parameters,
+                group,
+            }),
+            "FREEBUSY" => {
+                let value = property
+                    .value
+                    .split(',')
+                    .map(|period| {
+                        let period = Period::parse_from(period, &parameters)?;
+                        if !matches!(period.start, IcalDateTime::Utc(_)) {
+                            return Err(Error::invalid_value("FREEBUSY periods must be UTC"));
+                        }
+                        if period.duration <= Duration::zero() {
+                            return Err(Error::invalid_value(
+                                "FREEBUSY periods must have a positive duration",
+                            ));
+                        }
+                        Ok(period)
+                    })
+                    .collect::<Result<_, Error>>()?;
+
+                Property::FreeBusyTime(PropertyValue {
+                    value,
+                    parameters,
+                    group,
+                })
+            }
             "TRANSP" => {
                 let value = match &property.value.to_ascii_uppercase() as &str {
                     "OPAQUE" => TransparencyEnum::Opaque,
                     "TRANSPARENT" => TransparencyEnum::Tranparent,
-                    _ => TransparencyEnum::Other(property.value.clone()),
+                    _ => TransparencyEnum::Other(property.value.to_string()),
                 };
-                Property::Transparency(PropertyValue { value, parameters })
+                Property::Transparency(PropertyValue {
+                    value,
+                    parameters,
+                    group,
+                })
             }
             "TZID" => Property::TimeZoneID(PropertyValue {
-                value: unescape(&property.value)?,
+                value: unescape_lenient(&property.value).into_owned(),
                 parameters,
+                group,
             }),
             "TZNAME" => Property::TimeZoneName(PropertyValue {
-                value: unescape(&property.value)?,
+                value: unescape_lenient(&property.value).into_owned(),
                 parameters,
+                group,
             }),
             "TZOFFSETFROM" => Property::TimeZoneOffsetFrom(PropertyValue {
                 value: parse_offset(&property.value)?,
                 parameters,
+                group,
             }),
             "TZOFFSETTO" => Property::TimeZoneOffsetTo(PropertyValue {
                 value: parse_offset(&property.value)?,
                 parameters,
+                group,
             }),
             "TZURL" => Property::TimeZoneURL(PropertyValue {
                 value: property.value.parse()?,
                 parameters,
+                group,
             }),
             "ATTENDEE" => Property::Attendee(PropertyValue {
                 value: property.value.parse()?,
                 parameters,
+                group,
             }),
             "CONTACT" => Property::Contact(PropertyValue {
-                value: unescape(&property.value)?,
+                value: unescape_lenient(&property.value).into_owned(),
                 parameters,
+                group,
             }),
             "ORGANIZER" => Property::Organizer(PropertyValue {
                 value: property.value.parse()?,
                 parameters,
+                group,
             }),
             "RECURRENCE-ID" => Property::RecurrenceID(PropertyValue {
                 value: DateOrDateTime::parse_from(&property.value, &parameters)?,
                 parameters,
+                group,
             }),
             "RELATED-TO" => Property::RelatedTo(PropertyValue {
-                value: unescape(&property.value)?,
+                value: unescape_lenient(&property.value).into_owned(),
                 parameters,
+                group,
             }),
             "URL" => Property::URL(PropertyValue {
                 value: property.value.parse()?,
                 parameters,
+                group,
             }),
             "UID" => Property::UID(PropertyValue {
-                value: unescape(&property.value)?,
+                value: unescape_lenient(&property.value).into_owned(),
                 parameters,
+                group,
             }),
             "EXDATE" => Property::ExceptionDateTimes(PropertyValue {
-                value: DateOrDateTime::parse_from(&property.value, &parameters)?,
+                value: DateOrDateTime::parse_list(&property.value, &parameters)?,
                 parameters,
+                group,
             }),
             "RDATE" => Property::RecurrenceDateTimes(PropertyValue {
-                value: DateDateTimeOrPeriod::parse_from(&property.value, &parameters)?,
+                value: DateDateTimeOrPeriod::parse_list(&property.value, &parameters)?,
                 parameters,
+                group,
             }),
             "RRULE" => Property::RecurrenceRule(PropertyValue {
                 value: property.value.parse()?,
                 parameters,
+                group,
             }),
             "ACTION" => Property::Action(PropertyValue {
-                value: unescape(&property.value)?,
+                value: unescape_lenient(&property.value).into_owned(),
                 parameters,
+                group,
             }),
             "REPEAT" => Property::Repeat(PropertyValue {
                 value: property.value.parse()?,
                 parameters,
+                group,
             }),
-            // "TRIGGER" => todo!(),
-            "CREATED" => {
-                let date = DateOrDateTime::parse_from(&property.value, &parameters)?;
-
-                if let DateOrDateTime::DateTime(IcalDateTime::Utc(date)) = date {
-                    Property::Created(PropertyValue {
-                        value: date,
-                        parameters,
-                    })
-                } else {
-                    bail!("CREATED must be UTC")
-                }
-            }
-            "DTSTAMP" => {
-                let date = DateOrDateTime::parse_from(&property.value, &parameters)?;
+            "TRIGGER" => {
+                let is_date_time = parameters
+                    .get_value_data_type()
+                    .is_some_and(|data_type| data_type.eq_ignore_ascii_case("DATE-TIME"));
+
+                let value = if is_date_time {
+                    if parameters.get_related().is_some() {
+                        return Err(Error::invalid_value(
+                            "RELATED can't be used with a DATE-TIME TRIGGER",
+                        ));
+                    }
 
-                if let DateOrDateTime::DateTime(IcalDateTime::Utc(date)) = date {
-                    Property::DateTimeStamp(PropertyValue {
-                        value: date,
-                        parameters,
-                    })
+                    match DateOrDateTime::parse_from(&property.value, &parameters)? {
+                        DateOrDateTime::DateTime(date @ IcalDateTime::Utc(_)) => {
+                            DateTimeOrDuration::DateTime(date)
+                        }
+                        _ => return Err(Error::invalid_value("TRIGGER must be UTC")),
+                    }
                 } else {
-                    bail!("DTSTAMP must be UTC")
-                }
+                    match parameters.get_related() {
+                        Some(Related::Other(related)) => {
+                            return Err(Error::invalid_value(format!(
+                                "Invalid RELATED value {:?}",
+                                related
+                            )))
+                        }
+                        _ => DateTimeOrDuration::Duration(parse_duration(&property.value)?),
+                    }
+                };
+
+                Property::Trigger(PropertyValue {
+                    value,
+                    parameters,
+                    group,
+                })
             }
-            // "LAST-MODIFIED" => {
-            //     let date = DateOrDateTime::parse_from(&property.value, &parameters)?;
-
-            //     if let DateOrDateTime::DateTime(IcalDateTime::Utc(date)) = date {
-            //         Property::LastModified(PropertyValue {
-            //             value: date,
-            //             parameters,
-            //         })
-            //     } else {
-            //         bail!("LAST-MODIFIED must be UTC")
-            //     }
-            // }
+            "CREATED" => Property::Created(PropertyValue {
+                value: parse_utc_date_time(&property.value, &parameters)?,
+                parameters,
+                group,
+            }),
+            "DTSTAMP" => Property::DateTimeStamp(PropertyValue {
+                value: parse_utc_date_time(&property.value, &parameters)?,
+                parameters,
+                group,
+            }),
+            "LAST-MODIFIED" => Property::LastModified(PropertyValue {
+                value: parse_utc_date_time(&property.value, &parameters)?,
+                parameters,
+                group,
+            }),
             "SEQUENCE" => Property::SequenceNumber(PropertyValue {
                 value: property.value.parse()?,
                 parameters,
+                group,
+            }),
+            "REQUEST-STATUS" => Property::RequestStatus(PropertyValue {
+                value: property.value.parse()?,
+                parameters,
+                group,
             }),
-            // "REQUEST-STATUS" => todo!(),
             "PRODID" => Property::ProductIdentifier(PropertyValue {
                 value: property.value.parse()?,
                 parameters,
+                group,
             }),
             "VERSION" => Property::Version(PropertyValue {
                 value: property.value.parse()?,
                 parameters,
+                group,
+            }),
+            "NAME" => Property::Name(PropertyValue {
+                value: unescape_lenient(&property.value).into_owned(),
+                parameters,
+                group,
+            }),
+            "COLOR" => Property::Color(PropertyValue {
+                value: Color::from_name(&unescape_lenient(&property.value)),
+                parameters,
+                group,
+            }),
+            "IMAGE" => Property::Image(PropertyValue {
+                value: AttachEnum::parse_from(&property.value, &parameters)?,
+                parameters,
+                group,
+            }),
+            "CONFERENCE" => Property::Conference(PropertyValue {
+                value: AttachEnum::parse_from(&property.value, &parameters)?,
+                parameters,
+                group,
+            }),
+            "REFRESH-INTERVAL" => Property::RefreshInterval(PropertyValue {
+                value: parse_duration(&property.value)?,
+                parameters,
+                group,
+            }),
+            "SOURCE" => Property::Source(PropertyValue {
+                value: property.value.parse()?,
+                parameters,
+                group,
             }),
             _ => Property::Other(
-                property.name,
+                property.name.into_owned(),
                 PropertyValue {
-                    value: property.value,
+                    value: property.value.into_owned(),
                     parameters,
+                    group,
                 },
             ),
         };
@@ -490,26 +658,573 @@ impl TryFrom<parser::Property> for Property {
     }
 }
 
+/// The value data type of the property when no `VALUE` parameter is given,
+/// e.g. `DATE-TIME` for `DTSTART`. Returns `None` for unknown properties.
+pub(crate) fn default_value_type(name: &str) -> Option<&'static str> {
+    let value_type = match &name.to_ascii_uppercase() as &str {
+        "ATTACH" | "TZURL" | "URL" | "IMAGE" | "CONFERENCE" | "SOURCE" => "URI",
+        "CALSCALE" | "METHOD" | "PRODID" | "VERSION" | "CATEGORIES" | "CLASS" | "COMMENT"
+        | "DESCRIPTION" | "LOCATION" | "RESOURCES" | "STATUS" | "SUMMARY" | "TRANSP" | "TZID"
+        | "TZNAME" | "CONTACT" | "RELATED-TO" | "UID" | "ACTION" | "REQUEST-STATUS" | "NAME"
+        | "COLOR" => "TEXT",
+        "GEO" => "FLOAT",
+        "PERCENT-COMPLETE" | "PRIORITY" | "REPEAT" | "SEQUENCE" => "INTEGER",
+        "COMPLETED" | "DTEND" | "DUE" | "DTSTART" | "RECURRENCE-ID" | "EXDATE" | "RDATE"
+        | "CREATED" | "DTSTAMP" | "LAST-MODIFIED" => "DATE-TIME",
+        "DURATION" | "TRIGGER" | "REFRESH-INTERVAL" => "DURATION",
+        "FREEBUSY" => "PERIOD",
+        "TZOFFSETFROM" | "TZOFFSETTO" => "UTC-OFFSET",
+        "ATTENDEE" | "ORGANIZER" => "CAL-ADDRESS",
+        "RRULE" => "RECUR",
+        _ => return None,
+    };
+
+    Some(value_type)
+}
+
+impl Property {
+    /// Convert back into a parsed property, e.g. so that it can be written
+    /// out.
+    ///
+    /// The value is re-serialized from its typed form, so may not match the
+    /// originally parsed text exactly (e.g. a recurrence rule's parts may be
+    /// reordered).
+    pub fn to_parsed(&self) -> parser::Property<'static> {
+        match self {
+            Property::Attach(v) => to_parsed("ATTACH", v, format_attach(&v.value)),
+            Property::Categories(v) => to_parsed("CATEGORIES", v, escape_list(&v.value)),
+            Property::Class(v) => to_parsed(
+                "CLASS",
+                v,
+                match &v.value {
+                    ClassEnum::Public => "PUBLIC".to_string(),
+                    ClassEnum::Private => "PRIVATE".to_string(),
+                    ClassEnum::Confidential => "CONFIDENTIAL".to_string(),
+                    ClassEnum::Other(value) => value.clone(),
+                },
+            ),
+            Property::Comment(v) => to_parsed("COMMENT", v, escape(&v.value).into_owned()),
+            Property::Description(v) => to_parsed("DESCRIPTION", v, escape(&v.value).into_owned()),
+            Property::Geo(v) => to_parsed("GEO", v, format!("{};{}", v.value.0, v.value.1)),
+            Property::Location(v) => to_parsed("LOCATION", v, escape(&v.value).into_owned()),
+            Property::PercentComplete(v) => to_parsed("PERCENT-COMPLETE", v, v.value.to_string()),
+            Property::Priority(v) => to_parsed("PRIORITY", v, v.value.to_string()),
+            Property::Resources(v) => to_parsed("RESOURCES", v, escape_list(&v.value)),
+            Property::Status(v) => to_parsed("STATUS", v, v.value.as_str().to_string()),
+            Property::Summary(v) => to_parsed("SUMMARY", v, escape(&v.value).into_owned()),
+            Property::Completed(v) => to_parsed("COMPLETED", v, format_utc(&v.value)),
+            Property::End(v) => to_parsed("DTEND", v, format_date_or_date_time(&v.value)),
+            Property::Due(v) => to_parsed("DUE", v, format_date_or_date_time(&v.value)),
+            Property::Start(v) => to_parsed("DTSTART", v, format_date_or_date_time(&v.value)),
+            Property::Duration(v) => to_parsed("DURATION", v, format_duration(&v.value)),
+            Property::FreeBusyTime(v) => to_parsed(
+                "FREEBUSY",
+                v,
+                v.value
+                    .iter()
+                    .map(format_period)
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            Property::Transparency(v) => to_parsed(
+                "TRANSP",
+                v,
+                match &v.value {
+                    TransparencyEnum::Opaque => "OPAQUE".to_string(),
+                    TransparencyEnum::Tranparent => "TRANSPARENT".to_string(),
+                    TransparencyEnum::Other(value) => value.clone(),
+                },
+            ),
+            Property::TimeZoneID(v) => to_parsed("TZID", v, escape(&v.value).into_owned()),
+            Property::TimeZoneName(v) => to_parsed("TZNAME", v, escape(&v.value).into_owned()),
+            Property::TimeZoneOffsetFrom(v) => {
+                to_parsed("TZOFFSETFROM", v, format_offset(&v.value))
+            }
+            Property::TimeZoneOffsetTo(v) => to_parsed("TZOFFSETTO", v, format_offset(&v.value)),
+            Property::TimeZoneURL(v) => to_parsed("TZURL", v, v.value.to_string()),
+            Property::Attendee(v) => to_parsed("ATTENDEE", v, v.value.to_string()),
+            Property::Contact(v) => to_parsed("CONTACT", v, escape(&v.value).into_owned()),
+            Property::Organizer(v) => to_parsed("ORGANIZER", v, v.value.to_string()),
+            Property::RecurrenceID(v) => {
+                to_parsed("RECURRENCE-ID", v, format_date_or_date_time(&v.value))
+            }
+            Property::RelatedTo(v) => to_parsed("RELATED-TO", v, escape(&v.value).into_owned()),
+            Property::URL(v) => to_parsed("URL", v, v.value.to_string()),
+            Property::UID(v) => to_parsed("UID", v, escape(&v.value).into_owned()),
+            Property::ExceptionDateTimes(v) => to_parsed(
+                "EXDATE",
+                v,
+                format_list(v.value.iter().map(format_date_or_date_time)),
+            ),
+            Property::RecurrenceDateTimes(v) => to_parsed(
+                "RDATE",
+                v,
+                format_list(v.value.iter().map(|value| match value {
+                    DateDateTimeOrPeriod::Date(date) => format_date(date),
+                    DateDateTimeOrPeriod::DateTime(date) => format_date_time(date),
+                    DateDateTimeOrPeriod::Period(period) => format_period(period),
+                })),
+            ),
+            Property::RecurrenceRule(v) => to_parsed("RRULE", v, v.value.to_string()),
+            Property::Action(v) => to_parsed("ACTION", v, escape(&v.value).into_owned()),
+            Property::Repeat(v) => to_parsed("REPEAT", v, v.value.to_string()),
+            Property::Trigger(v) => to_parsed(
+                "TRIGGER",
+                v,
+                match &v.value {
+                    DateTimeOrDuration::DateTime(date) => format_date_time(date),
+                    DateTimeOrDuration::Duration(duration) => format_duration(duration),
+                },
+            ),
+            Property::Created(v) => to_parsed("CREATED", v, format_utc(&v.value)),
+            Property::DateTimeStamp(v) => to_parsed("DTSTAMP", v, format_utc(&v.value)),
+            Property::LastModified(v) => to_parsed("LAST-MODIFIED", v, format_utc(&v.value)),
+            Property::SequenceNumber(v) => to_parsed("SEQUENCE", v, v.value.to_string()),
+            Property::RequestStatus(v) => to_parsed("REQUEST-STATUS", v, v.value.to_string()),
+            Property::ProductIdentifier(v) => to_parsed("PRODID", v, v.value.clone()),
+            Property::Version(v) => to_parsed("VERSION", v, v.value.clone()),
+            Property::Name(v) => to_parsed("NAME", v, escape(&v.value).into_owned()),
+            Property::Color(v) => to_parsed("COLOR", v, v.value.to_string()),
+            Property::Image(v) => to_parsed("IMAGE", v, format_attach(&v.value)),
+            Property::Conference(v) => to_parsed("CONFERENCE", v, format_attach(&v.value)),
+            Property::RefreshInterval(v) => {
+                to_parsed("REFRESH-INTERVAL", v, format_duration(&v.value))
+            }
+            Property::Source(v) => to_parsed("SOURCE", v, v.value.to_string()),
+            Property::Other(name, v) => {
+                let mut property = to_parsed("", v, v.value.clone());
+                property.name = Cow::Owned(name.clone());
+                property
+            }
+        }
+    }
+}
+
+fn to_parsed<T: Debug + Clone>(
+    name: &'static str,
+    value: &PropertyValue<T>,
+    text: String,
+) -> parser::Property<'static> {
+    parser::Property {
+        group: value.group.clone().map(Cow::Owned),
+        name: Cow::Borrowed(name),
+        value: Cow::Owned(text),
+        parameters: value.parameters.to_parsed(),
+        span: None,
+    }
+}
+
+fn format_attach(attach: &AttachEnum) -> String {
+    match attach {
+        AttachEnum::Url(url) => url.to_string(),
+        AttachEnum::Binary(data) => base64::encode(data),
+        AttachEnum::Other { value, .. } => value.clone(),
+    }
+}
+
+fn format_date(date: &NaiveDate) -> String {
+    date.format("%Y%m%d").to_string()
+}
+
+fn format_utc(date: &DateTime<Utc>) -> String {
+    date.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn format_date_time(date: &IcalDateTime) -> String {
+    match date {
+        IcalDateTime::Local(date) | IcalDateTime::TZ { date, .. } => {
+            date.format("%Y%m%dT%H%M%S").to_string()
+        }
+        IcalDateTime::Utc(date) => format_utc(date),
+    }
+}
+
+fn format_date_or_date_time(date: &DateOrDateTime) -> String {
+    match date {
+        DateOrDateTime::Date(date) => format_date(date),
+        DateOrDateTime::DateTime(date) => format_date_time(date),
+    }
+}
+
+/// Join the values of a multi-valued property.
+fn format_list(values: impl Iterator<Item = String>) -> String {
+    values.collect::<Vec<_>>().join(",")
+}
+
+fn format_period(period: &Period) -> String {
+    format!(
+        "{}/{}",
+        format_date_time(&period.start),
+        format_duration(&period.duration)
+    )
+}
+
+/// Format a duration as a `DURATION` value, e.g. `P1DT2H`.
+///
+/// Any fractional seconds are dropped.
+pub fn format_duration(duration: &Duration) -> String {
+    let mut seconds = duration.num_seconds();
+    let mut text = String::new();
+
+    if seconds < 0 {
+        text.push('-');
+        seconds = -seconds;
+    }
+    text.push('P');
+
+    if seconds != 0 && seconds % (7 * 24 * 60 * 60) == 0 {
+        text.push_str(&format!("{}W", seconds / (7 * 24 * 60 * 60)));
+        return text;
+    }
+
+    let days = seconds / (24 * 60 * 60);
+    let hours = seconds / (60 * 60) % 24;
+    let minutes = seconds / 60 % 60;
+    seconds %= 60;
+
+    if days != 0 {
+        text.push_str(&format!("{}D", days));
+    }
+
+    if hours != 0 || minutes != 0 || seconds != 0 || days == 0 {
+        text.push('T');
+        if hours != 0 {
+            text.push_str(&format!("{}H", hours));
+        }
+        // Minutes can't be skipped between hours and seconds.
+        if minutes != 0 || (hours != 0 && seconds != 0) {
+            text.push_str(&format!("{}M", minutes));
+        }
+        if seconds != 0 || (hours == 0 && minutes == 0) {
+            text.push_str(&format!("{}S", seconds));
+        }
+    }
+
+    text
+}
+
+fn format_time(time: &IcalTime) -> String {
+    match time {
+        IcalTime::Local(time) | IcalTime::TZ { time, .. } => time.format("%H%M%S").to_string(),
+        IcalTime::Utc(time) => time.format("%H%M%SZ").to_string(),
+    }
+}
+
+fn format_offset(offset: &FixedOffset) -> String {
+    let seconds = offset.local_minus_utc();
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.abs();
+
+    let mut text = format!("{}{:02}{:02}", sign, seconds / 3600, seconds / 60 % 60);
+    if seconds % 60 != 0 {
+        text.push_str(&format!("{:02}", seconds % 60));
+    }
+
+    text
+}
+
+/// Parse a UTC offset, i.e. `±HHMM` or `±HHMMSS`.
 fn parse_offset(value: &str) -> Result<FixedOffset, Error> {
-    if !value.starts_with(&['+', '-'] as &[char]) || value.len() != 5 {
-        bail!("Invalid TZOFFSETFROM prop: {}", value)
+    if !value.starts_with(&['+', '-'] as &[char])
+        || (value.len() != 5 && value.len() != 7)
+        || !value[1..].bytes().all(|b| b.is_ascii_digit())
+    {
+        return Err(Error::invalid_value(format!(
+            "Invalid TZOFFSETFROM prop: {}",
+            value
+        )));
     }
     let hours: i32 = value[1..3].parse()?;
-    let seconds: i32 = value[3..].parse()?;
+    let minutes: i32 = value[3..5].parse()?;
+    let seconds: i32 = match value.get(5..) {
+        Some(seconds) if !seconds.is_empty() => seconds.parse()?,
+        _ => 0,
+    };
+
+    let seconds = hours * 60 * 60 + minutes * 60 + seconds;
 
     if value.starts_with('+') {
-        Ok(FixedOffset::east(hours * 60 * 60 + seconds))
+        FixedOffset::east_opt(seconds)
     } else {
-        Ok(FixedOffset::west(hours * 60 * 60 + seconds))
+        FixedOffset::west_opt(seconds)
     }
+    .ok_or_else(|| Error::invalid_value(format!("UTC offset out of range: {}", value)))
 }
 
 #[derive(Debug, Clone)]
 pub struct PropertyValue<T: Debug + Clone> {
     pub value: T,
     pub parameters: ParameterSet,
+
+    /// The (vCard style) group of the property, e.g. `item1` for
+    /// `item1.URL`.
+    pub group: Option<String>,
 }
 
+impl PropertyValue<String> {
+    /// Convert the raw value of a property that isn't otherwise typed (i.e.
+    /// [`Property::Other`]) into a [`Value`].
+    ///
+    /// The type is taken from the `VALUE` parameter, or else is the default
+    /// for the named property, which is `TEXT` for non-standard properties.
+    pub fn to_value(&self, name: &str) -> Result<Value, Error> {
+        let data_type = self
+            .parameters
+            .get_value_data_type()
+            .or_else(|| default_value_type(name))
+            .unwrap_or("TEXT");
+
+        Value::parse(&self.value, data_type, &self.parameters)
+            .map_err(|e| e.with_property(&name.to_ascii_uppercase()))
+    }
+}
+
+/// A value of any of the data types defined by RFC 5545, e.g. for reading the
+/// values of non-standard properties.
+///
+/// The `Display` implementation gives the value as it appears in a content
+/// line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Binary(Vec<u8>),
+    Boolean(bool),
+    CalAddress(Url),
+    Date(NaiveDate),
+    DateTime(IcalDateTime),
+    Duration(Duration),
+    Float(f64),
+    Integer(i32),
+    Period(Period),
+    Recur(RecurRule),
+    Text(String),
+    Time(IcalTime),
+    Uri(Url),
+    UtcOffset(FixedOffset),
+
+    /// A comma-separated list of values, which all have the same type.
+    List(Vec<Value>),
+
+    /// A value of a non-standard (or unknown) type, which is kept as is.
+    Other {
+        data_type: String,
+        value: String,
+    },
+}
+
+impl Value {
+    /// Parse a value of the given data type, e.g. `INTEGER`, where any
+    /// `TZID` is taken from the parameters.
+    ///
+    /// A comma-separated list gives a [`Value::List`], other than for the
+    /// types whose values can themselves contain commas (`BINARY`,
+    /// `CAL-ADDRESS`, `RECUR` and `URI`) and unknown types.
+    pub fn parse(value: &str, data_type: &str, params: &ParameterSet) -> Result<Value, Error> {
+        let parts = match &data_type.to_ascii_uppercase() as &str {
+            "TEXT" => split_escaped(value, ','),
+            "BOOLEAN" | "DATE" | "DATE-TIME" | "DURATION" | "FLOAT" | "INTEGER" | "PERIOD"
+            | "TIME" | "UTC-OFFSET" => value.split(',').collect(),
+            _ => vec![value],
+        };
+
+        let mut values = parts
+            .into_iter()
+            .map(|part| Value::parse_single(part, data_type, params))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if values.len() == 1 {
+            Ok(values.remove(0))
+        } else {
+            Ok(Value::List(values))
+        }
+    }
+
+    fn parse_single(value: &str, data_type: &str, params: &ParameterSet) -> Result<Value, Error> {
+        let invalid = || Error::invalid_value(format!("Invalid {} value {:?}", data_type, value));
+
+        Ok(match &data_type.to_ascii_uppercase() as &str {
+            "BINARY" => Value::Binary(base64::decode(value)?),
+            "BOOLEAN" => {
+                if value.eq_ignore_ascii_case("TRUE") {
+                    Value::Boolean(true)
+                } else if value.eq_ignore_ascii_case("FALSE") {
+                    Value::Boolean(false)
+                } else {
+                    return Err(invalid());
+                }
+            }
+            "CAL-ADDRESS" => Value::CalAddress(value.parse()?),
+            "DATE" => match DateOrDateTime::parse_from(value, params)? {
+                DateOrDateTime::Date(date) => Value::Date(date),
+                DateOrDateTime::DateTime(_) => return Err(invalid()),
+            },
+            "DATE-TIME" => match DateOrDateTime::parse_from(value, params)? {
+                DateOrDateTime::DateTime(date) => Value::DateTime(date),
+                DateOrDateTime::Date(_) => return Err(invalid()),
+            },
+            "DURATION" => Value::Duration(parse_duration(value)?),
+            "FLOAT" => Value::Float(parse_float(value).ok_or_else(invalid)?),
+            "INTEGER" => Value::Integer(value.parse().map_err(|_| invalid())?),
+            "PERIOD" => Value::Period(Period::parse_from(value, params)?),
+            "RECUR" => Value::Recur(value.parse()?),
+            "TEXT" => Value::Text(unescape_lenient(value).into_owned()),
+            "TIME" => Value::Time(IcalTime::parse_from(value, params).ok_or_else(invalid)?),
+            "URI" => Value::Uri(value.parse()?),
+            "UTC-OFFSET" => Value::UtcOffset(parse_offset(value)?),
+            _ => Value::Other {
+                data_type: data_type.to_string(),
+                value: value.to_string(),
+            },
+        })
+    }
+
+    /// The name of the value's data type, as used in the `VALUE` parameter.
+    /// A list has the type of its values (or `TEXT` if it's empty).
+    pub fn data_type(&self) -> &str {
+        match self {
+            Value::Binary(_) => "BINARY",
+            Value::Boolean(_) => "BOOLEAN",
+            Value::CalAddress(_) => "CAL-ADDRESS",
+            Value::Date(_) => "DATE",
+            Value::DateTime(_) => "DATE-TIME",
+            Value::Duration(_) => "DURATION",
+            Value::Float(_) => "FLOAT",
+            Value::Integer(_) => "INTEGER",
+            Value::Period(_) => "PERIOD",
+            Value::Recur(_) => "RECUR",
+            Value::Text(_) => "TEXT",
+            Value::Time(_) => "TIME",
+            Value::Uri(_) => "URI",
+            Value::UtcOffset(_) => "UTC-OFFSET",
+            Value::List(values) => values.first().map_or("TEXT", Value::data_type),
+            Value::Other { data_type, .. } => data_type,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Binary(data) => f.write_str(&base64::encode(data)),
+            Value::Boolean(true) => f.write_str("TRUE"),
+            Value::Boolean(false) => f.write_str("FALSE"),
+            Value::CalAddress(url) | Value::Uri(url) => write!(f, "{}", url),
+            Value::Date(date) => f.write_str(&format_date(date)),
+            Value::DateTime(date) => f.write_str(&format_date_time(date)),
+            Value::Duration(duration) => f.write_str(&format_duration(duration)),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::Integer(value) => write!(f, "{}", value),
+            Value::Period(period) => f.write_str(&format_period(period)),
+            Value::Recur(rule) => write!(f, "{}", rule),
+            Value::Text(text) => f.write_str(&escape(text)),
+            Value::Time(time) => f.write_str(&format_time(time)),
+            Value::UtcOffset(offset) => f.write_str(&format_offset(offset)),
+            Value::List(values) => f.write_str(&format_list(values.iter().map(Value::to_string))),
+            Value::Other { value, .. } => f.write_str(value),
+        }
+    }
+}
+
+/// A calendar user, i.e. the address of an `ATTENDEE` or `ORGANIZER` along
+/// with its parameters, which default as specified by RFC 5545.
+///
+/// Unrecognized parameter values are returned as is, see e.g.
+/// [`PartStat::effective`] for how they should be treated.
+#[derive(Debug, Clone, Copy)]
+pub struct CalendarUser<'a> {
+    property: &'a PropertyValue<Url>,
+}
+
+impl<'a> CalendarUser<'a> {
+    pub fn new(property: &'a PropertyValue<Url>) -> Self {
+        CalendarUser { property }
+    }
+
+    /// The calendar user's address, usually a `mailto:` URI.
+    pub fn address(&self) -> &'a Url {
+        &self.property.value
+    }
+
+    /// The email address of a `mailto:` address, without any query, decoded
+    /// and lowercased so that addresses can be compared.
+    pub fn email(&self) -> Option<String> {
+        let address = self.address();
+        if address.scheme() != "mailto" {
+            return None;
+        }
+
+        let email = percent_encoding::percent_decode_str(address.path())
+            .decode_utf8()
+            .ok()?
+            .trim()
+            .to_lowercase();
+
+        if email.is_empty() {
+            None
+        } else {
+            Some(email)
+        }
+    }
+
+    /// The common name (`CN`) of the calendar user, e.g. to display along
+    /// with the address.
+    pub fn common_name(&self) -> Option<&'a str> {
+        self.property.parameters.get_common_name()
+    }
+
+    pub fn role(&self) -> Role {
+        self.property
+            .parameters
+            .get_role()
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn participation_status(&self) -> PartStat {
+        self.property
+            .parameters
+            .get_participation_status()
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Whether a reply is expected from the calendar user.
+    pub fn rsvp(&self) -> bool {
+        self.property.parameters.get_rsvp().unwrap_or(false)
+    }
+
+    pub fn user_type(&self) -> CuType {
+        self.property
+            .parameters
+            .get_user_type()
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The addresses of the calendar users that have delegated their
+    /// participation to this one.
+    pub fn delegated_from(&self) -> &'a [String] {
+        self.property.parameters.get_delegated_from()
+    }
+
+    /// The addresses of the calendar users that this one has delegated their
+    /// participation to.
+    pub fn delegated_to(&self) -> &'a [String] {
+        self.property.parameters.get_delegated_to()
+    }
+
+    /// The address of the calendar user acting on behalf of this one.
+    pub fn sent_by(&self) -> Option<&'a str> {
+        self.property.parameters.get_sent_by()
+    }
+
+    /// The addresses of the groups that the calendar user is a member of.
+    pub fn member(&self) -> &'a [String] {
+        self.property.parameters.get_member()
+    }
+}
+
+/// A URI or inline binary value, as used by `ATTACH`, `IMAGE` and
+/// `CONFERENCE`.
 #[derive(Debug, Clone)]
 pub enum AttachEnum {
     Url(Url),
@@ -517,7 +1232,31 @@ pub enum AttachEnum {
     Other { data_type: String, value: String },
 }
 
-#[derive(Debug, Clone)]
+impl AttachEnum {
+    fn parse_from(value: &str, params: &ParameterSet) -> Result<Self, Error> {
+        let data_type = match params.get_value_data_type() {
+            Some(data_type) => data_type,
+            None => return Ok(AttachEnum::Url(value.parse()?)),
+        };
+
+        match &data_type.to_ascii_uppercase() as &str {
+            "BINARY" => {
+                if params.get_encoding() != Some(&Encoding::Base64) {
+                    return Err(Error::invalid_value("Unknown encoding for binary value"));
+                }
+
+                Ok(AttachEnum::Binary(base64::decode(value.as_bytes())?))
+            }
+            "URI" => Ok(AttachEnum::Url(value.parse()?)),
+            _ => Ok(AttachEnum::Other {
+                data_type: data_type.to_string(),
+                value: value.to_string(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ClassEnum {
     Public,
     Private,
@@ -525,7 +1264,22 @@ pub enum ClassEnum {
     Other(String),
 }
 
-#[derive(Debug, Clone)]
+impl Default for ClassEnum {
+    /// Components without a `CLASS` are public.
+    fn default() -> Self {
+        ClassEnum::Public
+    }
+}
+
+impl ClassEnum {
+    /// Whether the component should be treated as private, which includes
+    /// any classification that we don't recognise.
+    pub fn is_private(&self) -> bool {
+        matches!(self, ClassEnum::Private | ClassEnum::Other(_))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum StatusEnum {
     Cancelled,
 
@@ -550,15 +1304,28 @@ pub enum DateDateTimeOrPeriod {
 }
 
 impl DateDateTimeOrPeriod {
-    fn parse_from(value: &str, params: &ParameterSet) -> Result<Self, Error> {
-        if let Ok(period) = Period::parse_from(value, params) {
-            Ok(DateDateTimeOrPeriod::Period(period))
-        } else {
-            Ok(match DateOrDateTime::parse_from(value, params)? {
-                DateOrDateTime::Date(d) => DateDateTimeOrPeriod::Date(d),
-                DateOrDateTime::DateTime(d) => DateDateTimeOrPeriod::DateTime(d),
+    /// Parse a comma-separated list of values, which are all of the type
+    /// given by the `VALUE` parameter (`DATE-TIME` by default).
+    fn parse_list(value: &str, params: &ParameterSet) -> Result<Vec<Self>, Error> {
+        let value_type = params.get_value_data_type().unwrap_or("DATE-TIME");
+
+        value
+            .split(',')
+            .map(|value| {
+                if value_type.eq_ignore_ascii_case("PERIOD") {
+                    return Ok(DateDateTimeOrPeriod::Period(Period::parse_from(
+                        value, params,
+                    )?));
+                }
+
+                Ok(
+                    match DateOrDateTime::parse_typed(value, value_type, params)? {
+                        DateOrDateTime::Date(d) => DateDateTimeOrPeriod::Date(d),
+                        DateOrDateTime::DateTime(d) => DateDateTimeOrPeriod::DateTime(d),
+                    },
+                )
             })
-        }
+            .collect()
     }
 }
 
@@ -568,7 +1335,7 @@ impl TryFrom<DateDateTimeOrPeriod> for NaiveDate {
     fn try_from(value: DateDateTimeOrPeriod) -> Result<Self, Self::Error> {
         match value {
             DateDateTimeOrPeriod::Date(d) => Ok(d),
-            _ => bail!("Not a date"),
+            _ => Err(Error::invalid_value("Not a date")),
         }
     }
 }
@@ -579,7 +1346,7 @@ impl TryFrom<DateDateTimeOrPeriod> for NaiveDateTime {
     fn try_from(value: DateDateTimeOrPeriod) -> Result<Self, Self::Error> {
         match value {
             DateDateTimeOrPeriod::DateTime(IcalDateTime::Local(d)) => Ok(d),
-            _ => bail!("Not a date"),
+            _ => Err(Error::invalid_value("Not a date")),
         }
     }
 }
@@ -590,7 +1357,7 @@ impl TryFrom<DateOrDateTime> for DateTime<Utc> {
     fn try_from(value: DateOrDateTime) -> Result<Self, Self::Error> {
         match value {
             DateOrDateTime::DateTime(IcalDateTime::Utc(d)) => Ok(d),
-            _ => bail!("Not a date"),
+            _ => Err(Error::invalid_value("Not a date")),
         }
     }
 }
@@ -601,7 +1368,16 @@ pub enum DateTimeOrDuration {
     Duration(Duration),
 }
 
-#[derive(Debug, Clone)]
+impl PropertyValue<DateTimeOrDuration> {
+    /// Whether a (duration) trigger is relative to the end of the component
+    /// rather than the start, i.e. it has `RELATED=END`.
+    pub fn is_related_to_end(&self) -> bool {
+        matches!(self.value, DateTimeOrDuration::Duration(_))
+            && self.parameters.get_related() == Some(&Related::End)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Period {
     pub start: IcalDateTime,
     pub duration: Duration,
@@ -609,53 +1385,26 @@ pub struct Period {
 
 impl Period {
     fn parse_from(value: &str, params: &ParameterSet) -> Result<Self, Error> {
-        let (start, end) = value.split_once('/').context("invalid period")?;
-
-        println!("start {}, end {}", start, end);
+        let (start, end) = value
+            .split_once('/')
+            .ok_or_else(|| Error::invalid_value("invalid period"))?;
 
         let start = match DateOrDateTime::parse_from(start, params)? {
-            DateOrDateTime::Date(_) => bail!("Invalid start time in period"),
+            DateOrDateTime::Date(_) => {
+                return Err(Error::invalid_value("Invalid start time in period"))
+            }
             DateOrDateTime::DateTime(d) => d,
         };
 
-        let r = regex::Regex::new("(-?P)(?:T?([0-9]+)([WDHMS]))+").unwrap();
-
-        if let Some(captures) = r.captures(end) {
-            let mut iter = captures.iter();
-            iter.next(); // skip full match
-            let period = iter
-                .next()
-                .expect("regex failed to find group")
-                .expect("regex failed to find group");
-
-            let mut duration = Duration::seconds(0);
-
-            for (digits, length) in iter.tuples() {
-                let duration_value: i64 = digits
-                    .expect("regex failed to find group")
-                    .as_str()
-                    .parse()?;
-
-                let duration_part = match length.expect("regex failed to find group").as_str() {
-                    "W" => Duration::weeks(duration_value),
-                    "D" => Duration::days(duration_value),
-                    "H" => Duration::hours(duration_value),
-                    "M" => Duration::minutes(duration_value),
-                    "S" => Duration::seconds(duration_value),
-                    _ => bail!("invalid period duration"),
-                };
-
-                duration = duration_part + duration;
-            }
-
-            if period.as_str() == "-P" {
-                duration = duration * -1;
-            }
+        if end.starts_with(&['P', '+', '-'] as &[_]) {
+            let duration = parse_duration(end)?;
 
             Ok(Period { start, duration })
         } else {
             let end = match DateOrDateTime::parse_from(end, params)? {
-                DateOrDateTime::Date(_) => bail!("Invalid start time in period"),
+                DateOrDateTime::Date(_) => {
+                    return Err(Error::invalid_value("Invalid start time in period"))
+                }
                 DateOrDateTime::DateTime(d) => d,
             };
 
@@ -666,6 +1415,150 @@ impl Period {
     }
 }
 
+/// Parse a `DATE-TIME` value that must be in UTC, e.g. for `DTSTAMP`.
+fn parse_utc_date_time(value: &str, params: &ParameterSet) -> Result<DateTime<Utc>, Error> {
+    match DateOrDateTime::parse_from(value, params)? {
+        DateOrDateTime::DateTime(IcalDateTime::Utc(date)) => Ok(date),
+        _ => Err(Error::invalid_value("Date-time must be UTC")),
+    }
+}
+
+/// Parse a `GEO` value, i.e. a latitude and longitude separated by a
+/// semicolon.
+fn parse_geo(value: &str) -> Result<(f64, f64), Error> {
+    let (latitude, longitude) = value
+        .split_once(';')
+        .ok_or_else(|| Error::invalid_value("GEO must be a latitude and longitude"))?;
+
+    let latitude = parse_float(latitude)
+        .filter(|latitude| (-90.0..=90.0).contains(latitude))
+        .ok_or_else(|| Error::invalid_value(format!("Invalid latitude {:?}", latitude)))?;
+
+    let longitude = parse_float(longitude)
+        .filter(|longitude| (-180.0..=180.0).contains(longitude))
+        .ok_or_else(|| Error::invalid_value(format!("Invalid longitude {:?}", longitude)))?;
+
+    Ok((latitude, longitude))
+}
+
+/// Parse a `FLOAT` value, which (unlike Rust's float parsing) doesn't allow
+/// exponents, infinities or NaN.
+fn parse_float(value: &str) -> Option<f64> {
+    let digits = value.strip_prefix(&['+', '-'] as &[_]).unwrap_or(value);
+    let (integer, fraction) = digits.split_once('.').unwrap_or((digits, "0"));
+
+    let is_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    if !is_digits(integer) || !is_digits(fraction) {
+        return None;
+    }
+
+    value.parse().ok()
+}
+
+/// Parse a `DURATION` value as defined by RFC 5545, e.g. `-P1DT2H30M`.
+///
+/// Unlike ISO 8601 durations, years and months aren't allowed (as they don't
+/// have a fixed length), weeks can't be combined with other units, and the
+/// time units must be consecutive (e.g. `PT1H0M5S` rather than `PT1H5S`).
+pub fn parse_duration(value: &str) -> Result<Duration, Error> {
+    let invalid = || Error::invalid_value(format!("Invalid duration {:?}", value));
+
+    let (negative, rest) = match value.as_bytes().first() {
+        Some(b'-') => (true, &value[1..]),
+        Some(b'+') => (false, &value[1..]),
+        _ => (false, value),
+    };
+    let rest = rest.strip_prefix('P').ok_or_else(invalid)?;
+
+    let (date, time) = match rest.split_once('T') {
+        Some((date, time)) => (date, Some(time)),
+        None => (rest, None),
+    };
+
+    let date = duration_parts(date).ok_or_else(invalid)?;
+    let time = match time {
+        Some(time) => duration_parts(time)
+            .filter(|parts| !parts.is_empty())
+            .ok_or_else(invalid)?,
+        None => Vec::new(),
+    };
+
+    match date.as_slice() {
+        [] if time.is_empty() => return Err(invalid()),
+        [] | [(_, b'D')] => {}
+        [(_, b'W')] if time.is_empty() => {}
+        _ => return Err(invalid()),
+    }
+
+    // The time units must be a run of consecutive units from `HMS`.
+    let first = time
+        .first()
+        .map_or(Some(0), |(_, unit)| b"HMS".iter().position(|u| u == unit))
+        .ok_or_else(invalid)?;
+    if time.len() > 3 - first
+        || time
+            .iter()
+            .zip(&b"HMS"[first..])
+            .any(|((_, unit), expected)| unit != expected)
+    {
+        return Err(invalid());
+    }
+
+    let mut duration = Duration::zero();
+    for (value, unit) in date.into_iter().chain(time) {
+        let unit_seconds = match unit {
+            b'W' => 7 * 24 * 60 * 60,
+            b'D' => 24 * 60 * 60,
+            b'H' => 60 * 60,
+            b'M' => 60,
+            _ => 1,
+        };
+
+        duration = checked_seconds(value, unit_seconds)
+            .and_then(|part| duration.checked_add(&part))
+            .ok_or_else(|| Error::invalid_value("Duration out of range"))?;
+    }
+
+    Ok(if negative { -duration } else { duration })
+}
+
+/// Split part of a duration into numbers and their units, e.g. `1H30M`.
+fn duration_parts(mut value: &str) -> Option<Vec<(i64, u8)>> {
+    let mut parts = Vec::new();
+
+    while !value.is_empty() {
+        let digits = value.bytes().take_while(u8::is_ascii_digit).count();
+        if digits == 0 {
+            return None;
+        }
+
+        // Overly large numbers get treated as out of range later on.
+        let number = value[..digits].parse().unwrap_or(i64::MAX);
+
+        let unit = *value.as_bytes().get(digits)?;
+        if !b"WDHMS".contains(&unit) {
+            return None;
+        }
+
+        parts.push((number, unit));
+        value = &value[digits + 1..];
+    }
+
+    Some(parts)
+}
+
+/// Create a duration of `value` lots of `unit_seconds`, or `None` if it
+/// doesn't fit in a `Duration`.
+fn checked_seconds(value: i64, unit_seconds: i64) -> Option<Duration> {
+    let seconds = value.checked_mul(unit_seconds)?;
+
+    if seconds.checked_abs()? > MAX_DURATION_SECONDS {
+        return None;
+    }
+
+    Some(Duration::seconds(seconds))
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum IcalDateTime {
     Local(NaiveDateTime),
@@ -696,39 +1589,68 @@ impl IcalDateTime {
                     date: right,
                     tzid: right_tzid,
                 },
-            ) => {
-                if left_tzid == right_tzid {
-                    return Ok(*left - *right);
-                }
+            ) if left_tzid == right_tzid => {
+                return Ok(*left - *right);
             }
             _ => {}
         }
 
-        let cal = vcalendar.context("Mismatched IcalDateTime")?;
-
-        let left: DateTime<FixedOffset>;
-        let right: DateTime<FixedOffset>;
+        let cal = vcalendar.ok_or_else(|| Error::timezone_mismatch("Mismatched IcalDateTime"))?;
 
-        match self {
-            IcalDateTime::Utc(t) => left = t.with_timezone(&FixedOffset::east(0)),
-            IcalDateTime::TZ { .. } => {
-                left = cal.get_time(self)?;
+        let left = match self {
+            IcalDateTime::Utc(t) => t.with_timezone(&FixedOffset::east(0)),
+            IcalDateTime::TZ { .. } => cal.get_time(self)?,
+            IcalDateTime::Local(_) => {
+                return Err(Error::timezone_mismatch("Mismatched IcalDateTime"))
             }
-            IcalDateTime::Local(_) => bail!("Mismatched IcalDateTime"),
-        }
+        };
 
-        match other {
-            IcalDateTime::Utc(t) => right = t.with_timezone(&FixedOffset::east(0)),
-            IcalDateTime::TZ { .. } => {
-                right = cal.get_time(self)?;
+        let right = match other {
+            IcalDateTime::Utc(t) => t.with_timezone(&FixedOffset::east(0)),
+            IcalDateTime::TZ { .. } => cal.get_time(other)?,
+            IcalDateTime::Local(_) => {
+                return Err(Error::timezone_mismatch("Mismatched IcalDateTime"))
             }
-            IcalDateTime::Local(_) => bail!("Mismatched IcalDateTime"),
-        }
+        };
 
         Ok(left - right)
     }
 }
 
+/// A `TIME` value, which like a date-time can be local, UTC or in a given
+/// timezone.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum IcalTime {
+    Local(NaiveTime),
+    Utc(NaiveTime),
+    TZ { time: NaiveTime, tzid: String },
+}
+
+impl IcalTime {
+    fn parse_from(value: &str, params: &ParameterSet) -> Option<Self> {
+        let (time, is_utc) = match value.strip_suffix('Z') {
+            Some(time) => (time, true),
+            None => (value, false),
+        };
+
+        if time.len() != 6 || !time.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let time = NaiveTime::parse_from_str(time, "%H%M%S").ok()?;
+
+        Some(if is_utc {
+            IcalTime::Utc(time)
+        } else if let Some(tzid) = params.get_tzid() {
+            IcalTime::TZ {
+                time,
+                tzid: tzid.to_string(),
+            }
+        } else {
+            IcalTime::Local(time)
+        })
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum DateOrDateTime {
     Date(NaiveDate),
@@ -750,7 +1672,7 @@ impl DateOrDateTime {
             } else {
                 Ok(DateOrDateTime::DateTime(IcalDateTime::Local(
                     NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
-                        .context(format!("value: {}", value))?,
+                        .map_err(|e| Error::invalid_value(format!("{}: {}", e, value)))?,
                 )))
             }
         } else {
@@ -759,6 +1681,35 @@ impl DateOrDateTime {
             )?))
         }
     }
+
+    /// Parse a comma-separated list of values, which are all of the type
+    /// given by the `VALUE` parameter (`DATE-TIME` by default).
+    fn parse_list(value: &str, params: &ParameterSet) -> Result<Vec<Self>, Error> {
+        let value_type = params.get_value_data_type().unwrap_or("DATE-TIME");
+
+        value
+            .split(',')
+            .map(|value| DateOrDateTime::parse_typed(value, value_type, params))
+            .collect()
+    }
+
+    /// Parse a value that must be of the given type, i.e. `DATE` or
+    /// `DATE-TIME`.
+    fn parse_typed(value: &str, value_type: &str, params: &ParameterSet) -> Result<Self, Error> {
+        let parsed = DateOrDateTime::parse_from(value, params)?;
+
+        let is_date = matches!(parsed, DateOrDateTime::Date(_));
+        if value_type.eq_ignore_ascii_case("DATE") && is_date
+            || value_type.eq_ignore_ascii_case("DATE-TIME") && !is_date
+        {
+            Ok(parsed)
+        } else {
+            Err(Error::invalid_value(format!(
+                "{:?} is not a valid {} value",
+                value, value_type
+            )))
+        }
+    }
 }
 
 impl TryFrom<DateOrDateTime> for NaiveDate {
@@ -767,7 +1718,7 @@ impl TryFrom<DateOrDateTime> for NaiveDate {
     fn try_from(value: DateOrDateTime) -> Result<Self, Self::Error> {
         match value {
             DateOrDateTime::Date(d) => Ok(d),
-            _ => bail!("Not a date"),
+            _ => Err(Error::invalid_value("Not a date")),
         }
     }
 }
@@ -778,7 +1729,7 @@ impl TryFrom<DateOrDateTime> for NaiveDateTime {
     fn try_from(value: DateOrDateTime) -> Result<Self, Self::Error> {
         match value {
             DateOrDateTime::DateTime(IcalDateTime::Local(d)) => Ok(d),
-            _ => bail!("Not a date"),
+            _ => Err(Error::invalid_value("Not a date")),
         }
     }
 }
@@ -789,7 +1740,38 @@ impl TryFrom<DateDateTimeOrPeriod> for DateTime<Utc> {
     fn try_from(value: DateDateTimeOrPeriod) -> Result<Self, Self::Error> {
         match value {
             DateDateTimeOrPeriod::DateTime(IcalDateTime::Utc(d)) => Ok(d),
-            _ => bail!("Not a date"),
+            _ => Err(Error::invalid_value("Not a date")),
+        }
+    }
+}
+
+impl StatusEnum {
+    pub fn as_str(&self) -> &str {
+        match self {
+            StatusEnum::Cancelled => "CANCELLED",
+            StatusEnum::Tentative => "TENTATIVE",
+            StatusEnum::Confirmed => "CONFIRMED",
+            StatusEnum::NeesAction => "NEEDS-ACTION",
+            StatusEnum::Completed => "COMPLETED",
+            StatusEnum::InProgress => "IN-PROCESS",
+            StatusEnum::Draft => "DRAFT",
+            StatusEnum::Final => "FINAL",
+            StatusEnum::Other(value) => value,
+        }
+    }
+
+    /// Whether the status can be used by the given type of component, e.g.
+    /// `VEVENT`. Unknown statuses are allowed anywhere.
+    pub fn is_valid_for(&self, component: &str) -> bool {
+        match self {
+            StatusEnum::Cancelled | StatusEnum::Other(_) => true,
+            StatusEnum::Tentative | StatusEnum::Confirmed => {
+                component.eq_ignore_ascii_case("VEVENT")
+            }
+            StatusEnum::NeesAction | StatusEnum::Completed | StatusEnum::InProgress => {
+                component.eq_ignore_ascii_case("VTODO")
+            }
+            StatusEnum::Draft | StatusEnum::Final => component.eq_ignore_ascii_case("VJOURNAL"),
         }
     }
 }
@@ -801,11 +1783,164 @@ pub enum TransparencyEnum {
     Other(String),
 }
 
-#[derive(Debug, Clone)]
+/// The value of a `REQUEST-STATUS` property, e.g.
+/// `3.1;Invalid property value;DTSTART:96-Apr-01`.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RequestStatus {
-    code: u16,
+    code: RequestStatusCode,
     description: String,
-    data: String,
+    data: Option<String>,
+}
+
+impl RequestStatus {
+    pub fn new(code: RequestStatusCode, description: String, data: Option<String>) -> Self {
+        RequestStatus {
+            code,
+            description,
+            data,
+        }
+    }
+
+    pub fn code(&self) -> RequestStatusCode {
+        self.code
+    }
+
+    /// The human readable description of the status.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// The data that caused the status, if any, e.g. the offending property.
+    pub fn exception_data(&self) -> Option<&str> {
+        self.data.as_deref()
+    }
+}
+
+impl FromStr for RequestStatus {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let parts = split_escaped(value, ';');
+
+        let (code, description) = match parts.as_slice() {
+            [code, description, ..] => (code.parse()?, unescape_lenient(description).into_owned()),
+            _ => {
+                return Err(Error::invalid_value(
+                    "REQUEST-STATUS must have a code and a description",
+                ))
+            }
+        };
+
+        // The exception data should have any semicolons escaped, but often
+        // doesn't, e.g. `3.7;Invalid user;ATTENDEE;CN=Foo:mailto:foo@example.com`.
+        let data = match parts.get(2..) {
+            Some(data) if !data.is_empty() => Some(
+                data.iter()
+                    .map(|part| unescape_lenient(part))
+                    .collect::<Vec<_>>()
+                    .join(";"),
+            ),
+            _ => None,
+        };
+
+        Ok(RequestStatus {
+            code,
+            description,
+            data,
+        })
+    }
+}
+
+impl fmt::Display for RequestStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{};{}", self.code, escape(&self.description))?;
+
+        if let Some(data) = &self.data {
+            write!(f, ";{}", escape(data))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The code of a [`RequestStatus`], e.g. `2.0` or `3.1.1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RequestStatusCode {
+    class: u16,
+    subcode: u16,
+    detail: Option<u16>,
+}
+
+impl RequestStatusCode {
+    pub fn new(class: u16, subcode: u16, detail: Option<u16>) -> Self {
+        RequestStatusCode {
+            class,
+            subcode,
+            detail,
+        }
+    }
+
+    /// The class of the status, i.e. 1 for preliminary success, 2 for
+    /// success, 3 for a client error and 4 for a scheduling error.
+    pub fn class(&self) -> u16 {
+        self.class
+    }
+
+    pub fn subcode(&self) -> u16 {
+        self.subcode
+    }
+
+    /// The optional third level of the code, e.g. the second 1 in `3.1.1`.
+    pub fn detail(&self) -> Option<u16> {
+        self.detail
+    }
+
+    /// Whether the request succeeded, possibly with some fallback.
+    pub fn is_success(&self) -> bool {
+        self.class == 2
+    }
+}
+
+impl FromStr for RequestStatusCode {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::invalid_value(format!("Invalid status code {:?}", value));
+
+        let parse = |part: &str| {
+            if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            part.parse().map_err(|_| invalid())
+        };
+
+        let mut parts = value.split('.');
+        let class = parse(parts.next().unwrap_or_default())?;
+        let subcode = parse(parts.next().ok_or_else(invalid)?)?;
+        let detail = parts.next().map(parse).transpose()?;
+
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+
+        Ok(RequestStatusCode {
+            class,
+            subcode,
+            detail,
+        })
+    }
+}
+
+impl fmt::Display for RequestStatusCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.class, self.subcode)?;
+
+        if let Some(detail) = self.detail {
+            write!(f, ".{}", detail)?;
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -825,32 +1960,40 @@ impl Frequency {
     ///
     /// Note for years and dates the day gets reset to 1 (as not all days are
     /// valid for all years and months).
-    pub fn advance_date<T: ExtendedDatelike>(self, date: T, interval: u64) -> T {
-        match self {
-            Frequency::Secondly => date + Duration::seconds(interval as i64),
-            Frequency::Minutely => date + Duration::minutes(interval as i64),
-            Frequency::Hourly => date + Duration::hours(interval as i64),
-            Frequency::Daily => date + Duration::days(interval as i64),
-            Frequency::Weekly => date + Duration::days(7 * interval as i64),
+    ///
+    /// Returns `None` if the new date would be after the year 9999.
+    pub fn advance_date<T: ExtendedDatelike>(self, date: T, interval: u64) -> Option<T> {
+        let interval = i64::try_from(interval).ok()?;
+
+        let advanced = match self {
+            Frequency::Secondly => date.checked_add(checked_seconds(interval, 1)?)?,
+            Frequency::Minutely => date.checked_add(checked_seconds(interval, 60)?)?,
+            Frequency::Hourly => date.checked_add(checked_seconds(interval, 60 * 60)?)?,
+            Frequency::Daily => date.checked_add(checked_seconds(interval, 24 * 60 * 60)?)?,
+            Frequency::Weekly => date.checked_add(checked_seconds(interval, 7 * 24 * 60 * 60)?)?,
             Frequency::Monthly => {
                 // Chrono doesn't currently have a way of adding months, c.f.
                 // chronotope/chrono#474.
 
-                let current_month = date.month0();
+                let months = i64::from(date.month0()).checked_add(interval)?;
+                let year = i64::from(date.year()).checked_add(months / 12)?;
 
-                date.with_day(1)
-                    .expect("valid month")
-                    .with_month0((current_month + interval as u32) % 12)
-                    .expect("valid month")
-                    .with_year(date.year() + (current_month + interval as u32) as i32 / 12)
-                    .expect("valid month")
+                date.with_day(1)?
+                    .with_month0((months % 12) as u32)?
+                    .with_year(i32::try_from(year).ok()?)?
             }
-            Frequency::Yearly => date
-                .with_day(1)
-                .expect("valid month")
-                .with_year(date.year() + interval as i32)
-                .expect("year increment"),
+            Frequency::Yearly => {
+                let year = i64::from(date.year()).checked_add(interval)?;
+
+                date.with_day(1)?.with_year(i32::try_from(year).ok()?)?
+            }
+        };
+
+        if advanced.year() > MAX_YEAR {
+            return None;
         }
+
+        Some(advanced)
     }
 }
 
@@ -879,37 +2022,45 @@ pub struct RecurRule {
     pub week_start: Weekday,
 }
 
+#[allow(clippy::wrong_self_convention)]
 pub trait Offseter {
-    fn to_instance(&self, d: NaiveDateTime) -> DateTime<FixedOffset>;
-    fn from_instance(&self, d: DateTime<FixedOffset>) -> NaiveDateTime;
+    fn to_instance(&self, d: NaiveDateTime) -> Result<DateTime<FixedOffset>, Error>;
+    fn from_instance(&self, d: DateTime<FixedOffset>) -> Result<NaiveDateTime, Error>;
 }
 
 impl Offseter for FixedOffset {
-    fn to_instance(&self, d: NaiveDateTime) -> DateTime<FixedOffset> {
+    fn to_instance(&self, d: NaiveDateTime) -> Result<DateTime<FixedOffset>, Error> {
         self.from_local_datetime(&d)
             .earliest()
-            .expect("valid timezone date")
+            .ok_or_else(|| Error::invalid_value(format!("Date out of range: {}", d)))
     }
 
-    fn from_instance(&self, d: DateTime<FixedOffset>) -> NaiveDateTime {
-        d.naive_utc() + *self
+    fn from_instance(&self, d: DateTime<FixedOffset>) -> Result<NaiveDateTime, Error> {
+        d.naive_utc()
+            .checked_add_signed(Duration::seconds(self.local_minus_utc().into()))
+            .ok_or_else(|| Error::invalid_value(format!("Date out of range: {}", d)))
     }
 }
 
 impl RecurRule {
+    /// Fails if the `UNTIL` date can't be converted by the offseter.
+    ///
+    /// The iterator returns an error (and then stops) if no occurrence is
+    /// found within [`ParseLimits::max_recurrence_steps`] periods.
     pub fn from_date(
         &self,
         date: NaiveDateTime,
         offseter: &dyn Offseter,
-    ) -> impl Iterator<Item = NaiveDateTime> {
+        limits: &ParseLimits,
+    ) -> Result<impl Iterator<Item = Result<NaiveDateTime, Error>>, Error> {
         let (max_count, until) = match self.end_condition {
             EndCondition::Count(c) => (Some(c), None),
             EndCondition::Until(t) => (None, Some(t)),
-            EndCondition::UntilUtc(t) => (None, Some(offseter.from_instance(t.into()))),
+            EndCondition::UntilUtc(t) => (None, Some(offseter.from_instance(t.into())?)),
             _ => (None, None),
         };
 
-        RecurIter {
+        Ok(RecurIter {
             recur: self.clone(),
             next_date: Some(date),
             queue: VecDeque::new(),
@@ -917,9 +2068,14 @@ impl RecurRule {
             max_count,
             until,
             previous_date: None,
-        }
+            max_steps: limits.max_recurrence_steps,
+        })
     }
 
+    /// Fails if the `UNTIL` date can't be converted by the offseter. The
+    /// iterator stops at the first occurrence that can't be converted, and
+    /// returns an error if the rule exceeds the limits, as
+    /// [`RecurRule::from_date`].
     pub fn from_naive_date_with_extras<
         'a,
         T: ToNaive + 'a,
@@ -932,7 +2088,8 @@ impl RecurRule {
         rdates: I,
         exdates: &'a [E],
         offseter: O,
-    ) -> impl Iterator<Item = T> + 'a
+        limits: &ParseLimits,
+    ) -> Result<impl Iterator<Item = Result<T, Error>> + 'a, Error>
     where
         T::Naive: PartialEq<E>,
     {
@@ -943,7 +2100,7 @@ impl RecurRule {
                 None,
                 Some(
                     offseter
-                        .from_instance(t.into())
+                        .from_instance(t.into())?
                         .to_naive()
                         .to_naive_datetime(),
                 ),
@@ -959,29 +2116,36 @@ impl RecurRule {
             max_count,
             until,
             previous_date: None,
+            max_steps: limits.max_recurrence_steps,
         };
 
-        iter.merge(rdates)
-            .filter(move |d| exdates.iter().all(|ex| !d.eq(ex)))
-            .dedup()
-            .map(move |d| T::from_naive(d, &offseter))
+        Ok(iter
+            .merge_by(rdates.into_iter().map(Ok), occurrence_order)
+            .filter(move |d| match d {
+                Ok(d) => exdates.iter().all(|ex| !d.eq(ex)),
+                Err(_) => true,
+            })
+            .dedup_by(same_occurrence)
+            .map_while(move |d| match d {
+                Ok(d) => T::from_naive(d, &offseter).ok().map(Ok),
+                Err(e) => Some(Err(e)),
+            }))
     }
 
-    pub fn from_date_with_extras<
-        'a,
-        T: ToNaive + 'a,
-        E,
-        O: Offseter + 'a,
-        I: IntoIterator<Item = T> + 'a,
-    >(
+    /// Fails if the `UNTIL` date can't be converted by the offseter. The
+    /// iterator stops at the first occurrence that can't be converted, and
+    /// returns an error if the rule exceeds the limits, as
+    /// [`RecurRule::from_date`].
+    pub fn from_date_with_extras<'a, T, E, O: Offseter + 'a, I: IntoIterator<Item = T> + 'a>(
         &self,
         date: T,
         rdates: I,
         exdates: &'a [E],
         offseter: O,
-    ) -> impl Iterator<Item = T> + 'a
+        limits: &ParseLimits,
+    ) -> Result<impl Iterator<Item = Result<T, Error>> + 'a, Error>
     where
-        T: PartialEq<E>,
+        T: ToNaive + PartialEq<E> + 'a,
         T::Naive: PartialEq,
     {
         let (max_count, until) = match self.end_condition {
@@ -991,7 +2155,7 @@ impl RecurRule {
                 None,
                 Some(
                     offseter
-                        .from_instance(t.into())
+                        .from_instance(t.into())?
                         .to_naive()
                         .to_naive_datetime(),
                 ),
@@ -1007,15 +2171,45 @@ impl RecurRule {
             max_count,
             until,
             previous_date: None,
+            max_steps: limits.max_recurrence_steps,
         };
 
-        iter.map(move |d| T::from_naive(d, &offseter))
-            .merge(rdates)
-            .dedup()
-            .filter(move |d| exdates.iter().all(|ex| !d.eq(ex)))
+        Ok(iter
+            .map_while(move |d| match d {
+                Ok(d) => T::from_naive(d, &offseter).ok().map(Ok),
+                Err(e) => Some(Err(e)),
+            })
+            .merge_by(rdates.into_iter().map(Ok), occurrence_order)
+            .dedup_by(same_occurrence)
+            .filter(move |d| match d {
+                Ok(d) => exdates.iter().all(|ex| !d.eq(ex)),
+                Err(_) => true,
+            }))
     }
 }
 
+/// The order to merge occurrences in, with errors coming out as soon as they
+/// are hit.
+fn occurrence_order<T: PartialOrd>(a: &Result<T, Error>, b: &Result<T, Error>) -> bool {
+    match (a, b) {
+        (Ok(a), Ok(b)) => a <= b,
+        (Err(_), _) => true,
+        (_, Err(_)) => false,
+    }
+}
+
+fn same_occurrence<T: PartialEq>(a: &Result<T, Error>, b: &Result<T, Error>) -> bool {
+    matches!((a, b), (Ok(a), Ok(b)) if a == b)
+}
+
+fn invalid_option(part: &str) -> Error {
+    Error::invalid_recur_rule(format!("Invalid recur rule option: {}", part))
+}
+
+fn invalid_date(part: &str) -> Error {
+    Error::invalid_recur_rule(format!("Invalid recur rule date: {}", part))
+}
+
 impl FromStr for RecurRule {
     type Err = Error;
 
@@ -1035,9 +2229,9 @@ impl FromStr for RecurRule {
         let mut week_start = Weekday::Mon;
 
         for part in rule_value_string.split(';') {
-            let split_pos = part
-                .find('=')
-                .ok_or_else(|| format_err!("Invalid recur rule: '{}'", part))?;
+            let split_pos = part.find('=').ok_or_else(|| {
+                Error::invalid_recur_rule(format!("Invalid recur rule: '{}'", part))
+            })?;
             let (name, tail) = part.split_at(split_pos);
             let value = &tail[1..];
 
@@ -1051,49 +2245,53 @@ impl FromStr for RecurRule {
                         "WEEKLY" => Frequency::Weekly,
                         "MONTHLY" => Frequency::Monthly,
                         "YEARLY" => Frequency::Yearly,
-                        _ => bail!("Invalid frequency: '{}'", value),
+                        _ => {
+                            return Err(Error::invalid_recur_rule(format!(
+                                "Invalid frequency: '{}'",
+                                value
+                            )))
+                        }
                     });
                 }
                 "UNTIL" => {
                     end_condition = if value.contains('T') {
                         if value.ends_with('Z') {
                             let parsed = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ")
-                                .with_context(|| format!("Invalid recur rule date: {}", part))?;
+                                .map_err(|_| invalid_date(part))?;
                             EndCondition::UntilUtc(DateTime::from_utc(parsed, Utc))
                         } else {
                             let parsed = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
-                                .with_context(|| format!("Invalid recur rule date: {}", part))?;
+                                .map_err(|_| invalid_date(part))?;
                             EndCondition::Until(parsed)
                         }
                     } else {
                         let parsed = NaiveDateTime::parse_from_str(value, "%Y%m%d")
-                            .with_context(|| format!("Invalid recur rule date: {}", part))?;
+                            .map_err(|_| invalid_date(part))?;
                         EndCondition::Until(parsed)
                     }
                 }
                 "COUNT" => {
-                    end_condition = EndCondition::Count(
-                        value
-                            .parse::<u64>()
-                            .with_context(|| format!("Invalid recur rule option: {}", part))?,
-                    )
+                    end_condition =
+                        EndCondition::Count(value.parse::<u64>().map_err(|_| invalid_option(part))?)
                 }
                 "INTERVAL" => {
-                    interval = value
-                        .parse::<u64>()
-                        .with_context(|| format!("Invalid recur rule option: {}", part))?
+                    interval = value.parse::<u64>().map_err(|_| invalid_option(part))?;
+
+                    if interval == 0 {
+                        return Err(invalid_option(part));
+                    }
                 }
                 "BYSECOND" => {
                     by_second = value
                         .split(',')
                         .map(|s| s.parse::<u8>())
                         .collect::<Result<Vec<_>, _>>()
-                        .with_context(|| format!("Invalid recur rule option: {}", part))?;
+                        .map_err(|_| invalid_option(part))?;
 
                     // Ensure that seconds are in the appropriate range
                     for s in &by_second {
                         if !(0..=60).contains(s) {
-                            bail!("Invalid recur rule option: {}", part)
+                            return Err(invalid_option(part));
                         }
                     }
                 }
@@ -1102,12 +2300,12 @@ impl FromStr for RecurRule {
                         .split(',')
                         .map(|s| s.parse::<u8>())
                         .collect::<Result<Vec<_>, _>>()
-                        .with_context(|| format!("Invalid recur rule option: {}", part))?;
+                        .map_err(|_| invalid_option(part))?;
 
                     // Ensure that minutes are in the appropriate range
                     for s in &by_minute {
                         if !(0..=60).contains(s) {
-                            bail!("Invalid recur rule option: {}", part)
+                            return Err(invalid_option(part));
                         }
                     }
                 }
@@ -1116,20 +2314,31 @@ impl FromStr for RecurRule {
                         .split(',')
                         .map(|s| s.parse::<u8>())
                         .collect::<Result<Vec<_>, _>>()
-                        .with_context(|| format!("Invalid recur rule option: {}", part))?;
+                        .map_err(|_| invalid_option(part))?;
 
                     // Ensure that hours are in the appropriate range
                     for s in &by_hour {
                         if !(0..=24).contains(s) {
-                            bail!("Invalid recur rule option: {}", part)
+                            return Err(invalid_option(part));
                         }
                     }
                 }
                 "BYDAY" => {
                     for val in value.split_terminator(',') {
-                        let re = regex::Regex::new(r"^([+-]?[0-9]+)")?;
-                        let num = if let Some(mat) = re.find(val) {
-                            Some(mat.as_str().parse()?)
+                        // The weekday may be prefixed by a signed ordinal, e.g. `-1SU`.
+                        let ordinal_len = val
+                            .find(|c: char| c.is_ascii_alphabetic())
+                            .unwrap_or(val.len());
+                        let num = if ordinal_len > 0 {
+                            let num: i8 = val[..ordinal_len]
+                                .parse()
+                                .map_err(|_| invalid_option(part))?;
+
+                            if !(1..=53).contains(&num.unsigned_abs()) {
+                                return Err(invalid_option(part));
+                            }
+
+                            Some(num)
                         } else {
                             None
                         };
@@ -1151,7 +2360,7 @@ impl FromStr for RecurRule {
                         } else if val.ends_with("SU") {
                             Weekday::Sun
                         } else {
-                            bail!("Invalid recur rule option: {}", part)
+                            return Err(invalid_option(part));
                         };
 
                         by_day.push((num, weekday));
@@ -1162,12 +2371,12 @@ impl FromStr for RecurRule {
                         .split(',')
                         .map(|s| s.parse::<i8>())
                         .collect::<Result<Vec<_>, _>>()
-                        .with_context(|| format!("Invalid recur rule option: {}", part))?;
+                        .map_err(|_| invalid_option(part))?;
 
                     // Ensure that hours are in the appropriate range
                     for s in &by_month_day {
-                        if !(1..=31).contains(&s.abs()) {
-                            bail!("Invalid recur rule option: {}", part)
+                        if !(1..=31).contains(&s.unsigned_abs()) {
+                            return Err(invalid_option(part));
                         }
                     }
                 }
@@ -1176,12 +2385,12 @@ impl FromStr for RecurRule {
                         .split(',')
                         .map(|s| s.parse::<i16>())
                         .collect::<Result<Vec<_>, _>>()
-                        .with_context(|| format!("Invalid recur rule option: {}", part))?;
+                        .map_err(|_| invalid_option(part))?;
 
                     // Ensure that hours are in the appropriate range
                     for s in &by_year_day {
-                        if !(1..=366).contains(&s.abs()) {
-                            bail!("Invalid recur rule option: {}", part)
+                        if !(1..=366).contains(&s.unsigned_abs()) {
+                            return Err(invalid_option(part));
                         }
                     }
                 }
@@ -1190,12 +2399,12 @@ impl FromStr for RecurRule {
                         .split(',')
                         .map(|s| s.parse::<i16>())
                         .collect::<Result<Vec<_>, _>>()
-                        .with_context(|| format!("Invalid recur rule option: {}", part))?;
+                        .map_err(|_| invalid_option(part))?;
 
                     // Ensure that hours are in the appropriate range
                     for s in &by_week_number {
-                        if !(1..=53).contains(&s.abs()) {
-                            bail!("Invalid recur rule option: {}", part)
+                        if !(1..=53).contains(&s.unsigned_abs()) {
+                            return Err(invalid_option(part));
                         }
                     }
                 }
@@ -1204,12 +2413,12 @@ impl FromStr for RecurRule {
                         .split(',')
                         .map(|s| s.parse::<u16>())
                         .collect::<Result<Vec<_>, _>>()
-                        .with_context(|| format!("Invalid recur rule option: {}", part))?;
+                        .map_err(|_| invalid_option(part))?;
 
                     // Ensure that hours are in the appropriate range
                     for s in &by_month {
                         if !(1..=12).contains(s) {
-                            bail!("Invalid recur rule option: {}", part)
+                            return Err(invalid_option(part));
                         }
                     }
                 }
@@ -1218,12 +2427,12 @@ impl FromStr for RecurRule {
                         .split(',')
                         .map(|s| s.parse::<i16>())
                         .collect::<Result<Vec<_>, _>>()
-                        .with_context(|| format!("Invalid recur rule option: {}", part))?;
+                        .map_err(|_| invalid_option(part))?;
 
                     // Ensure that hours are in the appropriate range
                     for s in &by_set_pos {
-                        if !(1..=366).contains(&s.abs()) {
-                            bail!("Invalid recur rule option: {}", part)
+                        if !(1..=366).contains(&s.unsigned_abs()) {
+                            return Err(invalid_option(part));
                         }
                     }
                 }
@@ -1236,39 +2445,45 @@ impl FromStr for RecurRule {
                         "FR" => Weekday::Fri,
                         "SA" => Weekday::Sat,
                         "SU" => Weekday::Sun,
-                        _ => bail!("Invalid recur rule option: {}", part),
+                        _ => return Err(invalid_option(part)),
                     };
                 }
-                _ => bail!("Invalid recur rule option: '{}'", part),
+                _ => {
+                    return Err(Error::invalid_recur_rule(format!(
+                        "Invalid recur rule option: '{}'",
+                        part
+                    )))
+                }
             }
         }
 
-        let frequency = frequency.ok_or_else(|| format_err!("Missing FREQ in RRULE"))?;
+        let frequency =
+            frequency.ok_or_else(|| Error::invalid_recur_rule("Missing FREQ in RRULE"))?;
 
         if !by_week_number.is_empty() && frequency != Frequency::Yearly {
-            bail!(
-                "Invalid recur rule combination: cannot combine BYWEEKNO with non-YEARLY frequency"
-            );
+            return Err(Error::invalid_recur_rule(
+                "Invalid recur rule combination: cannot combine BYWEEKNO with non-YEARLY frequency",
+            ));
         }
 
         if !by_year_day.is_empty()
             && [Frequency::Daily, Frequency::Weekly, Frequency::Monthly].contains(&frequency)
         {
-            bail!(
-                "Invalid recur rule combination: cannot combine BYYEARDAY with DAILY/WEEKLY/MONTHLY frequency"
-            );
+            return Err(Error::invalid_recur_rule(
+                "Invalid recur rule combination: cannot combine BYYEARDAY with DAILY/WEEKLY/MONTHLY frequency",
+            ));
         }
 
         if !by_month_day.is_empty() && frequency == Frequency::Weekly {
-            bail!(
-                "Invalid recur rule combination: cannot combine BYMONTHDAY with WEEKLY frequency"
-            );
+            return Err(Error::invalid_recur_rule(
+                "Invalid recur rule combination: cannot combine BYMONTHDAY with WEEKLY frequency",
+            ));
         }
 
         if frequency != Frequency::Monthly && frequency != Frequency::Yearly {
             for (i, _) in &by_day {
                 if i.is_some() {
-                    bail!("Invalid recur rule combination: cannot have integer in BYDAY when frequency is not MONTHLY or YEARLY")
+                    return Err(Error::invalid_recur_rule("Invalid recur rule combination: cannot have integer in BYDAY when frequency is not MONTHLY or YEARLY"));
                 }
             }
         }
@@ -1291,19 +2506,108 @@ impl FromStr for RecurRule {
     }
 }
 
+impl fmt::Display for RecurRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let frequency = match self.frequency {
+            Frequency::Secondly => "SECONDLY",
+            Frequency::Minutely => "MINUTELY",
+            Frequency::Hourly => "HOURLY",
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        };
+        write!(f, "FREQ={}", frequency)?;
+
+        match &self.end_condition {
+            EndCondition::Count(count) => write!(f, ";COUNT={}", count)?,
+            EndCondition::Until(until) => write!(f, ";UNTIL={}", until.format("%Y%m%dT%H%M%S"))?,
+            EndCondition::UntilUtc(until) => write!(f, ";UNTIL={}", format_utc(until))?,
+            EndCondition::Infinite => {}
+        }
+
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+
+        fn write_list<T: fmt::Display>(
+            f: &mut fmt::Formatter<'_>,
+            name: &str,
+            values: &[T],
+        ) -> fmt::Result {
+            if !values.is_empty() {
+                write!(f, ";{}={}", name, values.iter().join(","))?;
+            }
+            Ok(())
+        }
+
+        write_list(f, "BYSECOND", &self.by_second)?;
+        write_list(f, "BYMINUTE", &self.by_minute)?;
+        write_list(f, "BYHOUR", &self.by_hour)?;
+
+        let by_day = self
+            .by_day
+            .iter()
+            .map(|(n, day)| match n {
+                Some(n) => format!("{}{}", n, weekday_name(*day)),
+                None => weekday_name(*day).to_string(),
+            })
+            .collect::<Vec<_>>();
+        write_list(f, "BYDAY", &by_day)?;
+
+        write_list(f, "BYMONTHDAY", &self.by_month_day)?;
+        write_list(f, "BYYEARDAY", &self.by_year_day)?;
+        write_list(f, "BYWEEKNO", &self.by_week_number)?;
+        write_list(f, "BYMONTH", &self.by_month)?;
+        write_list(f, "BYSETPOS", &self.by_set_pos)?;
+
+        if self.week_start != Weekday::Mon {
+            write!(f, ";WKST={}", weekday_name(self.week_start))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn weekday_name(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
 pub trait ExtendedDatelike: Datelike + Add<Duration, Output = Self> + PartialOrd + Copy {
     fn same_day(&self, other: &Self) -> bool {
         self.year() == other.year() && self.ordinal() == other.ordinal()
     }
+
+    /// Add the duration, returning `None` on overflow.
+    fn checked_add(self, rhs: Duration) -> Option<Self>;
 }
 pub trait ExtendedDateTimelike: Timelike + ExtendedDatelike {}
 
-impl<T> ExtendedDatelike for T where T: Datelike + Add<Duration, Output = Self> + PartialOrd + Copy {}
+impl ExtendedDatelike for NaiveDate {
+    fn checked_add(self, rhs: Duration) -> Option<Self> {
+        self.checked_add_signed(rhs)
+    }
+}
+
+impl ExtendedDatelike for NaiveDateTime {
+    fn checked_add(self, rhs: Duration) -> Option<Self> {
+        self.checked_add_signed(rhs)
+    }
+}
+
 impl<T> ExtendedDateTimelike for T where T: Timelike + ExtendedDatelike {}
 
 pub trait Expandable: Sized + PartialOrd + Copy {
     fn expand_date_set(&self, recur: &RecurRule) -> Vec<Self>;
-    fn advance(self, frequency: Frequency, interval: u64) -> Self;
+    fn advance(self, frequency: Frequency, interval: u64) -> Option<Self>;
 
     fn less_than_or_equal_local_datetime(&self, d: NaiveDateTime) -> bool;
     fn to_naive_datetime(&self) -> NaiveDateTime;
@@ -1314,10 +2618,29 @@ impl Expandable for NaiveDate {
         expand_dates(recur, vec![*self])
     }
 
-    fn advance(self, frequency: Frequency, interval: u64) -> Self {
+    fn advance(self, frequency: Frequency, interval: u64) -> Option<Self> {
+        // Sub-daily frequencies need to be applied enough times to reach the
+        // next day, otherwise we'd never make progress.
+        let seconds = match frequency {
+            Frequency::Secondly => Some(1),
+            Frequency::Minutely => Some(60),
+            Frequency::Hourly => Some(60 * 60),
+            _ => None,
+        };
+
+        let interval = match seconds {
+            Some(seconds) => {
+                let step = interval.checked_mul(seconds)?.max(1);
+                let steps = (24 * 60 * 60 - 1) / step + 1;
+
+                interval.checked_mul(steps)?
+            }
+            None => interval,
+        };
+
         frequency
             .advance_date(self.and_hms(0, 0, 0), interval)
-            .date()
+            .map(|d| d.date())
     }
 
     fn less_than_or_equal_local_datetime(&self, d: NaiveDateTime) -> bool {
@@ -1335,7 +2658,7 @@ impl Expandable for NaiveDateTime {
         expand_times(recur, date_set)
     }
 
-    fn advance(self, frequency: Frequency, interval: u64) -> Self {
+    fn advance(self, frequency: Frequency, interval: u64) -> Option<Self> {
         frequency.advance_date(self, interval)
     }
 
@@ -1377,11 +2700,11 @@ impl<T: Expandable> Expandable for NaivePeriod<T> {
             .collect()
     }
 
-    fn advance(self, frequency: Frequency, interval: u64) -> Self {
-        NaivePeriod {
+    fn advance(self, frequency: Frequency, interval: u64) -> Option<Self> {
+        Some(NaivePeriod {
             duration: self.duration,
-            start: self.start.advance(frequency, interval),
-        }
+            start: self.start.advance(frequency, interval)?,
+        })
     }
 
     fn less_than_or_equal_local_datetime(&self, d: NaiveDateTime) -> bool {
@@ -1421,11 +2744,11 @@ impl<T: ToNaive> ToNaive for ToNaivePeriod<T> {
         }
     }
 
-    fn from_naive(naive: Self::Naive, offseter: &dyn Offseter) -> Self {
-        ToNaivePeriod {
-            start: T::from_naive(naive.start, offseter),
+    fn from_naive(naive: Self::Naive, offseter: &dyn Offseter) -> Result<Self, Error> {
+        Ok(ToNaivePeriod {
+            start: T::from_naive(naive.start, offseter)?,
             duration: naive.duration,
-        }
+        })
     }
 }
 
@@ -1433,7 +2756,7 @@ pub trait ToNaive: PartialOrd + Copy {
     type Naive: Expandable + Debug;
 
     fn to_naive(&self) -> Self::Naive;
-    fn from_naive(naive: Self::Naive, offseter: &dyn Offseter) -> Self;
+    fn from_naive(naive: Self::Naive, offseter: &dyn Offseter) -> Result<Self, Error>;
 }
 
 impl<T> ToNaive for T
@@ -1446,8 +2769,8 @@ where
         *self
     }
 
-    fn from_naive(naive: Self::Naive, _: &dyn Offseter) -> Self {
-        naive
+    fn from_naive(naive: Self::Naive, _: &dyn Offseter) -> Result<Self, Error> {
+        Ok(naive)
     }
 }
 
@@ -1458,8 +2781,8 @@ impl ToNaive for Date<FixedOffset> {
         self.naive_local()
     }
 
-    fn from_naive(naive: Self::Naive, offseter: &dyn Offseter) -> Self {
-        offseter.to_instance(naive.and_hms(0, 0, 0)).date()
+    fn from_naive(naive: Self::Naive, offseter: &dyn Offseter) -> Result<Self, Error> {
+        Ok(offseter.to_instance(naive.and_hms(0, 0, 0))?.date())
     }
 }
 
@@ -1470,7 +2793,7 @@ impl ToNaive for DateTime<FixedOffset> {
         self.naive_local()
     }
 
-    fn from_naive(naive: Self::Naive, offseter: &dyn Offseter) -> Self {
+    fn from_naive(naive: Self::Naive, offseter: &dyn Offseter) -> Result<Self, Error> {
         offseter.to_instance(naive)
     }
 }
@@ -1482,8 +2805,8 @@ impl ToNaive for DateTime<Utc> {
         self.naive_local()
     }
 
-    fn from_naive(naive: Self::Naive, offseter: &dyn Offseter) -> Self {
-        offseter.to_instance(naive).with_timezone(&Utc)
+    fn from_naive(naive: Self::Naive, offseter: &dyn Offseter) -> Result<Self, Error> {
+        Ok(offseter.to_instance(naive)?.with_timezone(&Utc))
     }
 }
 
@@ -1495,36 +2818,55 @@ pub struct RecurIter<T> {
     until: Option<NaiveDateTime>,
     count: u64,
     previous_date: Option<T>,
+
+    /// The number of periods to step through looking for the next
+    /// occurrence before giving up, c.f.
+    /// [`ParseLimits::max_recurrence_steps`].
+    max_steps: usize,
 }
 
 impl<T> Iterator for RecurIter<T>
 where
     T: Expandable + PartialEq,
 {
-    type Item = T;
+    type Item = Result<T, Error>;
+
+    fn next(&mut self) -> Option<Result<T, Error>> {
+        let mut steps = 0;
 
-    fn next(&mut self) -> Option<T> {
         while self.queue.is_empty() {
             let curr_date = self.next_date.take()?;
 
-            self.next_date = Some(curr_date.advance(self.recur.frequency, self.recur.interval));
+            // Having taken the next date, the iterator is finished after
+            // returning the error.
+            if steps >= self.max_steps {
+                return Some(Err(Error::limit_exceeded(format!(
+                    "No occurrence of the recurrence rule within {} steps",
+                    self.max_steps
+                ))
+                .with_property("RRULE")));
+            }
+            steps += 1;
+
+            // We stop if advancing doesn't make progress, rather than looping
+            // forever.
+            self.next_date = curr_date
+                .advance(self.recur.frequency, self.recur.interval)
+                .filter(|next_date| *next_date > curr_date);
 
             let mut date_set = curr_date.expand_date_set(&self.recur);
 
             if !self.recur.by_set_pos.is_empty() {
+                let len = date_set.len() as i64;
+
+                // Positions outside of the set are ignored.
                 date_set = self
                     .recur
                     .by_set_pos
                     .iter()
-                    .copied()
-                    .map(|p| {
-                        if p > 0 {
-                            p - 1
-                        } else {
-                            p.rem_euclid(self.recur.by_set_pos.len() as i16)
-                        }
-                    })
-                    .map(|pos| date_set[pos as usize])
+                    .map(|&p| if p > 0 { p as i64 - 1 } else { len + p as i64 })
+                    .filter_map(|pos| usize::try_from(pos).ok())
+                    .filter_map(|pos| date_set.get(pos).copied())
                     .collect();
             }
 
@@ -1553,7 +2895,7 @@ where
 
             self.previous_date = Some(to_return);
 
-            return Some(to_return);
+            return Some(Ok(to_return));
         }
 
         None
@@ -1574,10 +2916,7 @@ where
             | Frequency::Daily
             | Frequency::Weekly
             | Frequency::Monthly => {
-                date_set = date_set
-                    .into_iter()
-                    .filter(|d| recur.by_month.contains(&(d.month() as u16)))
-                    .collect();
+                date_set.retain(|d| recur.by_month.contains(&(d.month() as u16)));
             }
             Frequency::Yearly => {
                 date_set = date_set
@@ -1586,7 +2925,7 @@ where
                         recur
                             .by_month
                             .iter()
-                            .map(move |&s| d.with_month(s as u32).expect("month expansion"))
+                            .filter_map(move |&s| d.with_month(s as u32))
                     })
                     .collect();
             }
@@ -1600,7 +2939,17 @@ where
             | Frequency::Hourly
             | Frequency::Daily
             | Frequency::Weekly
-            | Frequency::Monthly => panic!("BYWEEKNO cannot be specified unless FREQ=YEARLY"),
+            | Frequency::Monthly => {
+                // BYWEEKNO is only valid for YEARLY rules, which is checked
+                // when parsing, so we just limit here.
+                date_set.retain(|d| {
+                    let weeks_in_year = get_weeks_in_year(recur.week_start, *d) as i32;
+
+                    recur.by_week_number.iter().any(|&s| {
+                        resolve_position(s as i32, weeks_in_year) == Some(d.iso_week().week())
+                    })
+                });
+            }
 
             Frequency::Yearly => {
                 let week_start = recur.week_start;
@@ -1608,15 +2957,13 @@ where
                 date_set = date_set
                     .into_iter()
                     .flat_map(|d| {
-                        recur.by_week_number.iter().copied().map(move |mut s| {
-                            if s < 0 {
-                                let weeks_in_year = get_weeks_in_year(week_start, d);
-                                s %= weeks_in_year as i16;
-                            }
+                        let weeks_in_year = get_weeks_in_year(week_start, d) as i32;
 
-                            let diff = s as u32 - d.iso_week().week();
+                        recur.by_week_number.iter().filter_map(move |&s| {
+                            let week = resolve_position(s as i32, weeks_in_year)?;
+                            let diff = week as i64 - d.iso_week().week() as i64;
 
-                            d + Duration::days(diff as i64)
+                            d.checked_add(Duration::weeks(diff))
                         })
                     })
                     .collect();
@@ -1632,34 +2979,26 @@ where
             | Frequency::Daily
             | Frequency::Weekly
             | Frequency::Monthly => {
-                date_set = date_set
-                    .into_iter()
-                    .filter(|&d| {
-                        let days_in_year = get_days_in_year(d) as i16;
-
-                        let by_year_day: Vec<_> = recur
-                            .by_year_day
-                            .iter()
-                            .map(|&s| if s > 0 { s - 1 } else { s + days_in_year })
-                            .map(|s| (s % days_in_year) as u32 + 1)
-                            .collect();
-
-                        by_year_day.contains(&(d.ordinal() as u32))
-                    })
-                    .collect();
+                date_set.retain(|&d| {
+                    let days_in_year = get_days_in_year(d) as i32;
+
+                    recur
+                        .by_year_day
+                        .iter()
+                        .any(|&s| resolve_position(s as i32, days_in_year) == Some(d.ordinal()))
+                });
             }
             Frequency::Yearly => {
                 date_set = date_set
                     .into_iter()
                     .flat_map(|d| {
-                        let days_in_year = get_days_in_year(d) as i16;
+                        let days_in_year = get_days_in_year(d) as i32;
 
                         recur
                             .by_year_day
                             .iter()
-                            .map(move |&s| if s > 0 { s - 1 } else { s + days_in_year })
-                            .map(move |s| (s % days_in_year as i16) as u32 + 1)
-                            .map(move |s| d.with_ordinal(s).expect("year day expansion"))
+                            .filter_map(move |&s| resolve_position(s as i32, days_in_year))
+                            .filter_map(move |s| d.with_ordinal(s))
                     })
                     .collect();
             }
@@ -1673,35 +3012,27 @@ where
             | Frequency::Hourly
             | Frequency::Daily
             | Frequency::Weekly => {
-                date_set = date_set
-                    .into_iter()
-                    .filter(|&d| {
-                        let days_in_month = get_days_in_month(d) as i8;
-
-                        let by_month_day: Vec<_> = recur
-                            .by_month_day
-                            .iter()
-                            .map(|&s| if s > 0 { s - 1 } else { s + days_in_month })
-                            .map(|s| (s % days_in_month) as u32 + 1)
-                            .collect();
-
-                        by_month_day.contains(&d.day())
-                    })
-                    .collect();
+                date_set.retain(|&d| {
+                    let days_in_month = get_days_in_month(d) as i32;
+
+                    recur
+                        .by_month_day
+                        .iter()
+                        .any(|&s| resolve_position(s as i32, days_in_month) == Some(d.day()))
+                });
             }
 
             Frequency::Monthly | Frequency::Yearly => {
                 date_set = date_set
                     .into_iter()
                     .flat_map(|d| {
-                        let days_in_month = get_days_in_month(d) as i8;
+                        let days_in_month = get_days_in_month(d) as i32;
 
                         recur
                             .by_month_day
                             .iter()
-                            .map(move |&s| if s > 0 { s - 1 } else { s + days_in_month })
-                            .map(move |s| (s % days_in_month) as u32 + 1)
-                            .map(move |s| d.with_day(s).expect("month day expansion"))
+                            .filter_map(move |&s| resolve_position(s as i32, days_in_month))
+                            .filter_map(move |s| d.with_day(s))
                     })
                     .collect()
             }
@@ -1715,10 +3046,7 @@ where
             Frequency::Secondly | Frequency::Minutely | Frequency::Hourly | Frequency::Daily => {
                 // We ignore the numeric part of BYDAY rule, as it can
                 // only be set for MONTHLY or YEARLY.
-                date_set = date_set
-                    .into_iter()
-                    .filter(|d| recur.by_day.iter().any(|&(_, day)| day == d.weekday()))
-                    .collect();
+                date_set.retain(|d| recur.by_day.iter().any(|&(_, day)| day == d.weekday()));
             }
             Frequency::Weekly => {
                 date_set = date_set
@@ -1744,10 +3072,7 @@ where
                 date_set = date_set
                     .into_iter()
                     .flat_map(|d| {
-                        let month_start = d.with_day(1).expect("month start");
-                        let month_end = (month_start + Duration::days(32))
-                            .with_day(1)
-                            .expect("month_end");
+                        let (month_start, month_end) = get_month_bounds(d);
 
                         recur.by_day.iter().flat_map(move |&(num, day)| {
                             let dates = get_weekdays_in_period(month_start, month_end, day, num);
@@ -1779,30 +3104,16 @@ where
                     .flat_map(|d| {
                         let (start, end) = match freq {
                             Frequency::Weekly => {
-                                let diff = d.weekday().num_days_from_monday()
-                                    - week_start.num_days_from_monday();
-
-                                let start = d + -Duration::days(diff as i64);
+                                let start = get_start_of_week(week_start, d);
 
                                 (start, start + Duration::days(7))
                             }
-                            Frequency::Monthly => {
-                                let month_start = d.with_day(1).expect("month start");
-                                let month_end = (month_start + Duration::days(32))
-                                    .with_day(1)
-                                    .expect("month_end");
-
-                                (month_start, month_end)
-                            }
-                            Frequency::Yearly => {
-                                let start = d
-                                    .with_day(1)
-                                    .and_then(|d| d.with_month(1))
-                                    .expect("year start");
+                            Frequency::Monthly => get_month_bounds(d),
+                            _ => {
+                                let start = d + Duration::days(-(d.ordinal0() as i64));
 
-                                (start, start.with_year(start.year() + 1).expect("year end"))
+                                (start, start + Duration::days(get_days_in_year(d) as i64))
                             }
-                            _ => unreachable!(),
                         };
 
                         recur.by_day.iter().flat_map(move |&(num, day)| {
@@ -1831,10 +3142,7 @@ where
     if !recur.by_hour.is_empty() {
         match recur.frequency {
             Frequency::Secondly | Frequency::Minutely | Frequency::Hourly => {
-                date_set = date_set
-                    .into_iter()
-                    .filter(|d| recur.by_hour.contains(&(d.hour() as u8)))
-                    .collect();
+                date_set.retain(|d| recur.by_hour.contains(&(d.hour() as u8)));
             }
 
             Frequency::Daily | Frequency::Weekly | Frequency::Monthly | Frequency::Yearly => {
@@ -1844,7 +3152,7 @@ where
                         recur
                             .by_hour
                             .iter()
-                            .map(move |&s| d.with_hour(s as u32).expect("hour expansion"))
+                            .filter_map(move |&s| d.with_hour(s as u32))
                     })
                     .collect();
             }
@@ -1854,10 +3162,7 @@ where
     if !recur.by_minute.is_empty() {
         match recur.frequency {
             Frequency::Secondly | Frequency::Minutely => {
-                date_set = date_set
-                    .into_iter()
-                    .filter(|d| recur.by_minute.contains(&(d.minute() as u8)))
-                    .collect();
+                date_set.retain(|d| recur.by_minute.contains(&(d.minute() as u8)));
             }
 
             Frequency::Hourly
@@ -1871,7 +3176,7 @@ where
                         recur
                             .by_minute
                             .iter()
-                            .map(move |&s| d.with_minute(s as u32).expect("minute expansion"))
+                            .filter_map(move |&s| d.with_minute(s as u32))
                     })
                     .collect();
             }
@@ -1881,10 +3186,7 @@ where
     if !recur.by_second.is_empty() {
         match recur.frequency {
             Frequency::Secondly => {
-                date_set = date_set
-                    .into_iter()
-                    .filter(|d| recur.by_second.contains(&(d.second() as u8)))
-                    .collect();
+                date_set.retain(|d| recur.by_second.contains(&(d.second() as u8)));
             }
 
             Frequency::Minutely
@@ -1899,7 +3201,7 @@ where
                         recur
                             .by_second
                             .iter()
-                            .map(move |&s| d.with_second(s as u32).expect("second expansion"))
+                            .filter_map(move |&s| d.with_second(s as u32))
                     })
                     .collect();
             }
@@ -1926,13 +3228,43 @@ fn get_weeks_in_year<D: Datelike>(week_start: Weekday, date: D) -> u32 {
 }
 
 fn get_days_in_month<D: Datelike>(date: D) -> u32 {
-    for &days in &[31u32, 30, 29, 28] {
-        if date.with_day(days).is_some() {
-            return days;
-        }
+    match date.month() {
+        2 if get_days_in_year(date) == 366 => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
     }
+}
+
+/// Return the first day of the date's month and the first day of the next
+/// month.
+fn get_month_bounds<T>(date: T) -> (T, T)
+where
+    T: ExtendedDatelike,
+{
+    let start = date + Duration::days(1 - date.day() as i64);
+
+    (
+        start,
+        start + Duration::days(get_days_in_month(date) as i64),
+    )
+}
+
+/// Convert a 1-based position that may count backwards from the end (e.g.
+/// `-1` for the last day of the month) into a 1-based position counting
+/// forwards. Returns `None` if the position is out of range.
+fn resolve_position(position: i32, len: i32) -> Option<u32> {
+    let position = if position < 0 {
+        len + position + 1
+    } else {
+        position
+    };
 
-    panic!("Days in month {} was not 31, 30, 29 or 28", date.month())
+    if (1..=len).contains(&position) {
+        Some(position as u32)
+    } else {
+        None
+    }
 }
 
 fn get_weekdays_in_period<T>(start: T, end: T, day: Weekday, num: Option<i8>) -> Vec<T>
@@ -1955,19 +3287,10 @@ where
         date = date + Duration::days(7);
     }
     if let Some(num) = num {
-        if num > 0 {
-            if let Some(&date) = potential_dates.get(num as usize - 1) {
-                vec![date]
-            } else {
-                vec![]
-            }
-        } else if let Some(&date) =
-            potential_dates.get((num as i32).rem_euclid(potential_dates.len() as i32) as usize)
-        {
-            vec![date]
-        } else {
-            vec![]
-        }
+        resolve_position(num as i32, potential_dates.len() as i32)
+            .and_then(|pos| potential_dates.get(pos as usize - 1))
+            .map(|&date| vec![date])
+            .unwrap_or_default()
     } else {
         // We add every specified weekday in the month.
         potential_dates
@@ -1984,7 +3307,7 @@ where
 
     // We always want the start of the week to be *before* the given date.
     if difference > 0 {
-        difference = difference - 7;
+        difference -= 7;
     }
 
     let start_date = date + Duration::days(difference);
@@ -1998,6 +3321,470 @@ where
 mod tests {
     use super::*;
     use crate::components::{OffsetRule, VTimeZone};
+    use crate::parameters::FbType;
+
+    #[test]
+    fn to_parsed() {
+        let input = "BEGIN:VEVENT\r
+SUMMARY;LANGUAGE=en:Lunch\\, maybe\r
+CATEGORIES:WORK,FOOD\\, DRINK\r
+DTSTART;TZID=Europe/London:20200722T140000\r
+DTEND;VALUE=DATE:20200723\r
+DTSTAMP:20200730T163634Z\r
+CLASS:CONFIDENTIAL\r
+STATUS:TENTATIVE\r
+GEO:37.386013;-122.082932\r
+DURATION:PT1H0M30S\r
+COMPLETED:20200102T090000Z\r
+LAST-MODIFIED:20200101T120000Z\r
+REQUEST-STATUS:2.0;Success\r
+REQUEST-STATUS:3.1;Invalid property value;DTSTART:96-Apr-01\r
+TRIGGER;RELATED=END:-PT15M\r
+TRIGGER;VALUE=DATE-TIME:20200101T100000Z\r
+FREEBUSY;FBTYPE=BUSY-TENTATIVE:20200101T100000Z/PT1H,20200102T100000Z/P1D\r
+EXDATE;TZID=Europe/London:20220301T100000,20220308T100000\r
+RDATE;VALUE=DATE:20200101,20200102\r
+RDATE;VALUE=PERIOD:20200101T100000Z/PT1H,20200102T100000Z/PT2H\r
+NAME;LANGUAGE=en:Team\\, shared\r
+COLOR:turquoise\r
+IMAGE;VALUE=BINARY;ENCODING=BASE64;DISPLAY=GRAPHIC:AAEC\r
+CONFERENCE;VALUE=URI;FEATURE=AUDIO,VIDEO;LABEL=Join:https://example.com/meet\r
+REFRESH-INTERVAL;VALUE=DURATION:PT12H\r
+SOURCE;VALUE=URI:https://example.com/calendar.ics\r
+TZOFFSETFROM:+0530\r
+SEQUENCE:1\r
+RRULE:FREQ=YEARLY;COUNT=5;INTERVAL=2;BYDAY=-1SU,MO;BYMONTH=3,4;WKST=SU\r
+X-CUSTOM;X-PARAM=a:Some\\, value\r
+END:VEVENT\r
+";
+        let component = parser::Component::from_str_to_stream(input)
+            .unwrap()
+            .remove(0);
+
+        for parsed in component.properties {
+            let property = Property::try_from(parsed.clone()).unwrap();
+            assert_eq!(property.to_parsed(), parsed);
+        }
+    }
+
+    #[test]
+    fn property_group() {
+        let input =
+            "BEGIN:VEVENT\r\nitem1.URL:https://example.com\r\nSUMMARY:Test\r\nEND:VEVENT\r\n";
+        let mut component = parser::Component::from_str_to_stream(input)
+            .unwrap()
+            .remove(0);
+
+        let url = Property::try_from(component.properties.remove(0)).unwrap();
+        match url {
+            Property::URL(value) => assert_eq!(value.group.as_deref(), Some("item1")),
+            p => panic!("Unexpected property {:?}", p),
+        }
+
+        let summary = Property::try_from(component.properties.remove(0)).unwrap();
+        match summary {
+            Property::Summary(value) => assert_eq!(value.group, None),
+            p => panic!("Unexpected property {:?}", p),
+        }
+    }
+
+    #[test]
+    fn class_status_geo() {
+        match parse_property("VEVENT", "CLASS:private").unwrap() {
+            Property::Class(class) => assert_eq!(class.value, ClassEnum::Private),
+            p => panic!("Unexpected property {:?}", p),
+        }
+        match parse_property("VEVENT", "CLASS:X-SECRET").unwrap() {
+            Property::Class(class) => {
+                assert_eq!(class.value, ClassEnum::Other("X-SECRET".to_string()));
+                assert!(class.value.is_private());
+            }
+            p => panic!("Unexpected property {:?}", p),
+        }
+        assert!(!ClassEnum::default().is_private());
+
+        match parse_property("VEVENT", "STATUS:Cancelled").unwrap() {
+            Property::Status(status) => assert_eq!(status.value, StatusEnum::Cancelled),
+            p => panic!("Unexpected property {:?}", p),
+        }
+        match parse_property("VEVENT", "STATUS:IN-PROCESS").unwrap() {
+            Property::Status(status) => {
+                assert_eq!(status.value, StatusEnum::InProgress);
+                assert!(status.value.is_valid_for("VTODO"));
+                assert!(!status.value.is_valid_for("VEVENT"));
+            }
+            p => panic!("Unexpected property {:?}", p),
+        }
+
+        match parse_property("VEVENT", "GEO:-90;+180.0").unwrap() {
+            Property::Geo(geo) => assert_eq!(geo.value, (-90.0, 180.0)),
+            p => panic!("Unexpected property {:?}", p),
+        }
+        for value in &[
+            "GEO:90.5;0",
+            "GEO:0;-180.1",
+            "GEO:1e1;0",
+            "GEO:NaN;0",
+            "GEO:1.;0",
+            "GEO:0,0",
+            "GEO:0;0;0",
+        ] {
+            assert!(
+                matches!(
+                    parse_property("VEVENT", value),
+                    Err(Error::InvalidValue { .. })
+                ),
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn duration() {
+        for (value, seconds) in [
+            ("P1W", 7 * 24 * 60 * 60),
+            ("+P2D", 2 * 24 * 60 * 60),
+            ("-PT15M", -15 * 60),
+            ("P1DT2H", 26 * 60 * 60),
+            ("PT1H0M5S", 60 * 60 + 5),
+            ("PT90S", 90),
+            ("P0D", 0),
+        ] {
+            assert_eq!(
+                parse_duration(value).unwrap(),
+                Duration::seconds(seconds),
+                "{}",
+                value
+            );
+        }
+
+        for value in [
+            "",
+            "P",
+            "PT",
+            "-P",
+            "1H",
+            "P1H",
+            "PT1D",
+            "P1W2D",
+            "P1WT1H",
+            "PT1H5S",
+            "PT5S1M",
+            "PT1H1H",
+            "P1Y",
+            "P1M",
+            "P1DT",
+            "PT-1H",
+            "P 1D",
+            "PT1.5H",
+            "P1D1D",
+            "P99999999999999999999D",
+            "P9999999999999W",
+        ] {
+            assert!(
+                matches!(parse_duration(value), Err(Error::InvalidValue { .. })),
+                "{:?}",
+                value
+            );
+        }
+
+        for (seconds, formatted) in [
+            (0, "PT0S"),
+            (7 * 24 * 60 * 60, "P1W"),
+            (-15 * 60, "-PT15M"),
+            (26 * 60 * 60, "P1DT2H"),
+            (60 * 60 + 5, "PT1H0M5S"),
+            (24 * 60 * 60 + 5, "P1DT5S"),
+        ] {
+            let duration = Duration::seconds(seconds);
+            assert_eq!(format_duration(&duration), formatted);
+            assert_eq!(parse_duration(formatted).unwrap(), duration);
+        }
+    }
+
+    #[test]
+    fn trigger_and_free_busy() {
+        match parse_property("VALARM", "TRIGGER;RELATED=end:-PT5M").unwrap() {
+            Property::Trigger(trigger) => {
+                assert!(trigger.is_related_to_end());
+                assert!(
+                    matches!(trigger.value, DateTimeOrDuration::Duration(d) if d == Duration::minutes(-5))
+                );
+            }
+            p => panic!("Unexpected property {:?}", p),
+        }
+        match parse_property("VALARM", "TRIGGER:PT0S").unwrap() {
+            Property::Trigger(trigger) => assert!(!trigger.is_related_to_end()),
+            p => panic!("Unexpected property {:?}", p),
+        }
+
+        let line = "FREEBUSY;FBTYPE=FREE:20200101T100000Z/20200101T113000Z";
+        match parse_property("VALARM", line).unwrap() {
+            Property::FreeBusyTime(free_busy) => {
+                assert_eq!(
+                    free_busy.parameters.get_free_busy_type(),
+                    Some(&FbType::Free)
+                );
+                assert_eq!(free_busy.value.len(), 1);
+                assert_eq!(free_busy.value[0].duration, Duration::minutes(90));
+            }
+            p => panic!("Unexpected property {:?}", p),
+        }
+
+        for line in [
+            "TRIGGER:P",
+            "TRIGGER;RELATED=MIDDLE:PT5M",
+            "TRIGGER;VALUE=DATE-TIME:20200101T100000",
+            "TRIGGER;VALUE=DATE-TIME;RELATED=END:20200101T100000Z",
+            "FREEBUSY:20200101T100000/PT1H",
+            "FREEBUSY:20200101T100000Z/-PT1H",
+            "FREEBUSY:20200101T100000Z/PT1H,",
+            "DURATION:PT1H5S",
+        ] {
+            assert!(
+                matches!(
+                    parse_property("VALARM", line),
+                    Err(Error::InvalidValue { .. })
+                ),
+                "{}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn request_status() {
+        let status: RequestStatus = "3.1.2;Invalid\\; bad value;DTSTART:96-Apr-01"
+            .parse()
+            .unwrap();
+        assert_eq!(status.code(), RequestStatusCode::new(3, 1, Some(2)));
+        assert!(!status.code().is_success());
+        assert_eq!(status.description(), "Invalid; bad value");
+        assert_eq!(status.exception_data(), Some("DTSTART:96-Apr-01"));
+
+        // Unescaped semicolons in the data are kept.
+        let status: RequestStatus = "3.7;Invalid user;ATTENDEE;CN=A:mailto:a@example.com"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            status.exception_data(),
+            Some("ATTENDEE;CN=A:mailto:a@example.com")
+        );
+
+        let status: RequestStatus = "2.0;Success".parse().unwrap();
+        assert!(status.code().is_success());
+        assert_eq!(status.exception_data(), None);
+        assert_eq!(status.to_string(), "2.0;Success");
+
+        for value in [
+            "2.0",
+            "2;Success",
+            "2.;Success",
+            "a.0;Success",
+            "2.0.0.0;Success",
+            "",
+        ] {
+            assert!(
+                matches!(
+                    value.parse::<RequestStatus>(),
+                    Err(Error::InvalidValue { .. })
+                ),
+                "{:?}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn utc_date_times() {
+        match parse_property("VTODO", "LAST-MODIFIED:20200101T120000Z").unwrap() {
+            Property::LastModified(v) => {
+                assert_eq!(v.value, Utc.ymd(2020, 1, 1).and_hms(12, 0, 0))
+            }
+            p => panic!("Unexpected property {:?}", p),
+        }
+        assert!(matches!(
+            parse_property("VTODO", "COMPLETED:20200101T120000Z").unwrap(),
+            Property::Completed(_)
+        ));
+
+        for line in [
+            "COMPLETED:20200101T120000",
+            "COMPLETED;TZID=Europe/London:20200101T120000",
+            "LAST-MODIFIED:20200101",
+        ] {
+            let err = parse_property("VTODO", line).unwrap_err();
+            assert!(matches!(err, Error::InvalidValue { .. }), "{}", line);
+            assert!(err.context().property.is_some());
+        }
+    }
+
+    #[test]
+    fn multi_valued_dates() {
+        let line = "EXDATE;TZID=Europe/London:20220301T100000,20220308T100000";
+        match parse_property("VEVENT", line).unwrap() {
+            Property::ExceptionDateTimes(v) => assert_eq!(
+                v.value,
+                vec![
+                    DateOrDateTime::DateTime(IcalDateTime::TZ {
+                        date: NaiveDate::from_ymd(2022, 3, 1).and_hms(10, 0, 0),
+                        tzid: "Europe/London".to_string(),
+                    }),
+                    DateOrDateTime::DateTime(IcalDateTime::TZ {
+                        date: NaiveDate::from_ymd(2022, 3, 8).and_hms(10, 0, 0),
+                        tzid: "Europe/London".to_string(),
+                    }),
+                ]
+            ),
+            p => panic!("Unexpected property {:?}", p),
+        }
+
+        let line = "RDATE;VALUE=PERIOD:20200101T100000Z/PT1H,20200102T100000Z/PT2H";
+        match parse_property("VEVENT", line).unwrap() {
+            Property::RecurrenceDateTimes(v) => {
+                assert_eq!(v.value.len(), 2);
+                assert!(v
+                    .value
+                    .iter()
+                    .all(|value| matches!(value, DateDateTimeOrPeriod::Period(_))));
+            }
+            p => panic!("Unexpected property {:?}", p),
+        }
+
+        // The type comes from the VALUE parameter, not from the values.
+        for line in [
+            "EXDATE:20200101",
+            "EXDATE;VALUE=DATE:20200101T100000Z",
+            "EXDATE;VALUE=DATE:20200101,20200102T100000",
+            "EXDATE;VALUE=PERIOD:20200101T100000Z/PT1H",
+            "RDATE:20200101T100000Z/PT1H",
+            "RDATE;VALUE=PERIOD:20200101T100000Z",
+            "RDATE:20200101T100000Z,",
+        ] {
+            let err = parse_property("VEVENT", line).unwrap_err();
+            assert!(matches!(err, Error::InvalidValue { .. }), "{}", line);
+        }
+    }
+
+    #[test]
+    fn text_values() {
+        match parse_property("VEVENT", "CATEGORIES:Work\\, urgent,Home").unwrap() {
+            Property::Categories(v) => assert_eq!(v.value, ["Work, urgent", "Home"]),
+            p => panic!("Unexpected property {:?}", p),
+        }
+        match parse_property("VEVENT", "RESOURCES:Projector,Room\\,1").unwrap() {
+            Property::Resources(v) => assert_eq!(v.value, ["Projector", "Room,1"]),
+            p => panic!("Unexpected property {:?}", p),
+        }
+
+        // Outlook escapes colons.
+        match parse_property("VEVENT", "DESCRIPTION:Join at https\\://example.com").unwrap() {
+            Property::Description(v) => assert_eq!(v.value, "Join at https://example.com"),
+            p => panic!("Unexpected property {:?}", p),
+        }
+    }
+
+    #[test]
+    fn typed_values() {
+        let parse = |line: &str| match parse_property("VEVENT", line).unwrap() {
+            Property::Other(name, v) => v.to_value(&name).map(|value| (value, v.value)),
+            p => panic!("Unexpected property {:?}", p),
+        };
+
+        for (line, expected) in [
+            ("X-A;VALUE=BOOLEAN:TRUE", Value::Boolean(true)),
+            ("X-A;VALUE=INTEGER:-12", Value::Integer(-12)),
+            ("X-A;VALUE=FLOAT:1.5", Value::Float(1.5)),
+            (
+                "X-A;VALUE=TIME:123000Z",
+                Value::Time(IcalTime::Utc(NaiveTime::from_hms(12, 30, 0))),
+            ),
+            (
+                "X-A;VALUE=TIME;TZID=Europe/London:083000",
+                Value::Time(IcalTime::TZ {
+                    time: NaiveTime::from_hms(8, 30, 0),
+                    tzid: "Europe/London".to_string(),
+                }),
+            ),
+            (
+                "X-A;VALUE=URI:http://example.com/a,b",
+                Value::Uri("http://example.com/a,b".parse().unwrap()),
+            ),
+            (
+                "X-A;VALUE=CAL-ADDRESS:mailto:a@example.com",
+                Value::CalAddress("mailto:a@example.com".parse().unwrap()),
+            ),
+            (
+                "X-A;VALUE=UTC-OFFSET:-0500",
+                Value::UtcOffset(FixedOffset::west(5 * 3600)),
+            ),
+            (
+                "X-A;VALUE=UTC-OFFSET:-075258",
+                Value::UtcOffset(FixedOffset::west(7 * 3600 + 52 * 60 + 58)),
+            ),
+            ("X-A;VALUE=BINARY:AAEC", Value::Binary(vec![0, 1, 2])),
+            (
+                "X-A;VALUE=DATE:20200101",
+                Value::Date(NaiveDate::from_ymd(2020, 1, 1)),
+            ),
+            (
+                "X-A;VALUE=DURATION:PT15M",
+                Value::Duration(Duration::minutes(15)),
+            ),
+            ("X-A:Some\\, text", Value::Text("Some, text".to_string())),
+            (
+                "X-A:a,b\\,c",
+                Value::List(vec![
+                    Value::Text("a".to_string()),
+                    Value::Text("b,c".to_string()),
+                ]),
+            ),
+            (
+                "X-A;VALUE=INTEGER:1,2",
+                Value::List(vec![Value::Integer(1), Value::Integer(2)]),
+            ),
+            (
+                "X-A;VALUE=X-CUSTOM:a,b",
+                Value::Other {
+                    data_type: "X-CUSTOM".to_string(),
+                    value: "a,b".to_string(),
+                },
+            ),
+            // IANA properties default to their standard type.
+            ("METHOD:PUBLISH", Value::Text("PUBLISH".to_string())),
+        ] {
+            let (value, text) = parse(line).unwrap();
+            assert_eq!(value, expected, "{}", line);
+            assert_eq!(value.to_string(), text, "{}", line);
+        }
+
+        assert_eq!(
+            parse("X-A;VALUE=INTEGER:1,2").unwrap().0.data_type(),
+            "INTEGER"
+        );
+
+        // Unknown escapes, e.g. Outlook's escaped colons, are read leniently.
+        for (line, expected) in [("X-FOO:a\\:b", "a:b"), ("X-A:a\\x", "ax")] {
+            let (value, _) = parse(line).unwrap();
+            assert_eq!(value, Value::Text(expected.to_string()), "{}", line);
+        }
+
+        for line in [
+            "X-A;VALUE=BOOLEAN:YES",
+            "X-A;VALUE=INTEGER:1.5",
+            "X-A;VALUE=FLOAT:1e5",
+            "X-A;VALUE=TIME:1230",
+            "X-A;VALUE=DATE:20200101T100000",
+            "X-A;VALUE=DATE-TIME:20200101",
+            "X-A;VALUE=UTC-OFFSET:0500",
+            "X-A;VALUE=UTC-OFFSET:+05000",
+        ] {
+            let err = parse(line).unwrap_err();
+            assert!(matches!(err, Error::InvalidValue { .. }), "{}", line);
+            assert_eq!(err.context().property.as_deref(), Some("X-A"));
+        }
+    }
 
     #[test]
     fn parse_period() {
@@ -2013,77 +3800,97 @@ mod tests {
         assert_eq!(
             Frequency::Secondly
                 .advance_date::<NaiveDateTime>("2000-01-01T00:00:00".parse().unwrap(), 2),
-            "2000-01-01T00:00:02".parse().unwrap()
+            Some("2000-01-01T00:00:02".parse().unwrap())
         );
         assert_eq!(
             Frequency::Minutely
                 .advance_date::<NaiveDateTime>("2000-01-01T00:00:00".parse().unwrap(), 2),
-            "2000-01-01T00:02:00".parse().unwrap()
+            Some("2000-01-01T00:02:00".parse().unwrap())
         );
         assert_eq!(
             Frequency::Hourly
                 .advance_date::<NaiveDateTime>("2000-01-01T00:00:00".parse().unwrap(), 2),
-            "2000-01-01T02:00:00".parse().unwrap()
+            Some("2000-01-01T02:00:00".parse().unwrap())
         );
         assert_eq!(
             Frequency::Daily
                 .advance_date::<NaiveDateTime>("2000-01-01T00:00:00".parse().unwrap(), 2),
-            "2000-01-03T00:00:00".parse().unwrap()
+            Some("2000-01-03T00:00:00".parse().unwrap())
         );
         assert_eq!(
             Frequency::Weekly
                 .advance_date::<NaiveDateTime>("2000-01-01T00:00:00".parse().unwrap(), 2),
-            "2000-01-15T00:00:00".parse().unwrap()
+            Some("2000-01-15T00:00:00".parse().unwrap())
         );
         assert_eq!(
             Frequency::Monthly
                 .advance_date::<NaiveDateTime>("2000-01-01T00:00:00".parse().unwrap(), 2),
-            "2000-03-01T00:00:00".parse().unwrap()
+            Some("2000-03-01T00:00:00".parse().unwrap())
         );
         assert_eq!(
             Frequency::Yearly
                 .advance_date::<NaiveDateTime>("2000-01-01T00:00:00".parse().unwrap(), 2),
-            "2002-01-01T00:00:00".parse().unwrap()
+            Some("2002-01-01T00:00:00".parse().unwrap())
         );
 
         // Test wrap around
         assert_eq!(
             Frequency::Secondly
                 .advance_date::<NaiveDateTime>("2000-01-01T00:00:59".parse().unwrap(), 2),
-            "2000-01-01T00:01:01".parse().unwrap()
+            Some("2000-01-01T00:01:01".parse().unwrap())
         );
         assert_eq!(
             Frequency::Minutely
                 .advance_date::<NaiveDateTime>("2000-01-01T00:59:00".parse().unwrap(), 2),
-            "2000-01-01T01:01:00".parse().unwrap()
+            Some("2000-01-01T01:01:00".parse().unwrap())
         );
         assert_eq!(
             Frequency::Hourly
                 .advance_date::<NaiveDateTime>("2000-01-01T23:00:00".parse().unwrap(), 2),
-            "2000-01-02T01:00:00".parse().unwrap()
+            Some("2000-01-02T01:00:00".parse().unwrap())
         );
         assert_eq!(
             Frequency::Daily
                 .advance_date::<NaiveDateTime>("2000-01-31T00:00:00".parse().unwrap(), 2),
-            "2000-02-02T00:00:00".parse().unwrap()
+            Some("2000-02-02T00:00:00".parse().unwrap())
         );
         assert_eq!(
             Frequency::Weekly
                 .advance_date::<NaiveDateTime>("2000-01-31T00:00:00".parse().unwrap(), 2),
-            "2000-02-14T00:00:00".parse().unwrap()
+            Some("2000-02-14T00:00:00".parse().unwrap())
         );
         assert_eq!(
             Frequency::Monthly
                 .advance_date::<NaiveDateTime>("2000-12-01T00:00:00".parse().unwrap(), 2),
-            "2001-02-01T00:00:00".parse().unwrap()
+            Some("2001-02-01T00:00:00".parse().unwrap())
         );
         assert_eq!(
             Frequency::Monthly
                 .advance_date::<NaiveDateTime>("2000-01-31T00:00:00".parse().unwrap(), 1),
-            "2000-02-01T00:00:00".parse().unwrap()
+            Some("2000-02-01T00:00:00".parse().unwrap())
+        );
+
+        // Test overflow
+        assert_eq!(
+            Frequency::Yearly
+                .advance_date::<NaiveDateTime>("9999-01-01T00:00:00".parse().unwrap(), 1),
+            None
+        );
+        assert_eq!(
+            Frequency::Weekly
+                .advance_date::<NaiveDateTime>("2000-01-01T00:00:00".parse().unwrap(), u64::MAX),
+            None
         );
     }
 
+    fn parse_property(component: &str, line: &str) -> Result<Property, Error> {
+        let input = format!("BEGIN:{0}\r\n{1}\r\nEND:{0}\r\n", component, line);
+        let mut component = parser::Component::from_str_to_stream(&input)
+            .unwrap()
+            .remove(0);
+        Property::try_from(component.properties.remove(0))
+    }
+
     fn make_naive_date(s: &str) -> NaiveDateTime {
         NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
     }
@@ -2146,6 +3953,7 @@ mod tests {
                 },
             ],
             properties: vec![],
+            limits: ParseLimits::default(),
         }
     }
 
@@ -2175,8 +3983,10 @@ mod tests {
                 let timezone = timezone();
 
                 let dates: Vec<_> = rule
-                    .from_date(date_start, &timezone)
-                    .map(|d| timezone.get_offset(d, true).from_local_datetime(&d).earliest().unwrap())
+                    .from_date(date_start, &timezone, &ParseLimits::default())
+                    .unwrap()
+                    .map(Result::unwrap)
+                    .map(|d| timezone.get_offset(d, true).unwrap().from_local_datetime(&d).earliest().unwrap())
                     .map(|d| d.to_rfc3339().to_string())
                     .collect();
 
@@ -2191,9 +4001,11 @@ mod tests {
                 let timezone = timezone();
 
                 let dates: Vec<_> = rule
-                    .from_date(date_start, &timezone)
+                    .from_date(date_start, &timezone, &ParseLimits::default())
+                    .unwrap()
+                    .map(Result::unwrap)
                     .take($expected.len())
-                    .map(|d| timezone.get_offset(d, true).from_local_datetime(&d).earliest().unwrap())
+                    .map(|d| timezone.get_offset(d, true).unwrap().from_local_datetime(&d).earliest().unwrap())
                     .map(|d| d.to_rfc3339().to_string())
                     .collect();
 
@@ -2207,9 +4019,11 @@ mod tests {
                 let timezone = timezone();
 
                 let dates: Vec<_> = rule
-                    .from_date(date_start, &timezone)
+                    .from_date(date_start, &timezone, &ParseLimits::default())
+                    .unwrap()
+                    .map(Result::unwrap)
                     .take(1000)
-                    .map(|d| timezone.get_offset(d, true).from_local_datetime(&d).earliest().unwrap())
+                    .map(|d| timezone.get_offset(d, true).unwrap().from_local_datetime(&d).earliest().unwrap())
                     .map(|d| d.to_rfc3339().to_string())
                     .collect();
 
@@ -2230,7 +4044,9 @@ mod tests {
                 let timezone = timezone();
 
                 let dates: Vec<_> = rule
-                    .from_date(date_start, &timezone)
+                    .from_date(date_start, &timezone, &ParseLimits::default())
+                    .unwrap()
+                    .map(Result::unwrap)
                     .map(|d| d.format("%Y-%m-%dT%H:%M:%S").to_string())
                     .collect();
 
@@ -2436,4 +4252,43 @@ mod tests {
             "2022-11-01T15:00:00-04:00",
         ]
     }
+
+    add_rrule_test! {
+        recur_rule_skips_invalid_month_days, "2020-01-31T09:00:00";
+        finite_naive "FREQ=MONTHLY;COUNT=4;BYMONTHDAY=31" => &[
+            "2020-01-31T09:00:00",
+            "2020-03-31T09:00:00",
+            "2020-05-31T09:00:00",
+            "2020-07-31T09:00:00",
+        ]
+    }
+
+    add_rrule_test! {
+        recur_rule_skips_missing_set_positions, "2020-01-01T09:00:00";
+        finite_naive "FREQ=MONTHLY;COUNT=3;BYDAY=MO;BYSETPOS=5" => &[
+            "2020-03-30T09:00:00",
+            "2020-06-29T09:00:00",
+            "2020-08-31T09:00:00",
+        ]
+    }
+
+    add_rrule_test! {
+        recur_rule_stops_at_year_9999, "9998-01-01T09:00:00";
+        finite_naive "FREQ=YEARLY" => &[
+            "9998-01-01T09:00:00",
+            "9999-01-01T09:00:00",
+        ]
+    }
+
+    #[test]
+    fn recur_rule_invalid() {
+        for rule in &[
+            "FREQ=DAILY;INTERVAL=0",
+            "FREQ=MONTHLY;BYMONTHDAY=-128",
+            "FREQ=YEARLY;BYYEARDAY=-32768",
+            "FREQ=YEARLY;BYDAY=-0SU",
+        ] {
+            assert!(RecurRule::from_str(rule).is_err(), "{}", rule);
+        }
+    }
 }
@@ -0,0 +1,684 @@
+use std::{
+    borrow::Cow,
+    io::{BufRead, Read},
+};
+
+use crate::error::Error;
+use crate::lexer::Lexer;
+use crate::limits::ParseLimits;
+use crate::parser::{Component, Locator, Position, Property, Span};
+
+/// An item produced when reading components from a stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamItem {
+    /// A direct sub-component of the current top-level component (e.g. a
+    /// `VEVENT` in a `VCALENDAR`) has been fully read.
+    ///
+    /// This is only produced if sub-components are being streamed, see
+    /// [`ComponentReader::stream_sub_components`].
+    SubComponent(Component<'static>),
+
+    /// A top-level component has been fully read. If sub-components are being
+    /// streamed then they will have already been returned, and so won't be
+    /// included in the component.
+    Component(Component<'static>),
+}
+
+impl StreamItem {
+    pub fn into_component(self) -> Component<'static> {
+        match self {
+            StreamItem::SubComponent(component) => component,
+            StreamItem::Component(component) => component,
+        }
+    }
+}
+
+/// A single, possibly folded, content line.
+#[derive(Debug, Clone)]
+pub(crate) struct ContentLine {
+    /// The raw text of the line, including any folds and line endings.
+    pub(crate) text: String,
+
+    /// The (one based) line number that the content line starts on.
+    pub(crate) line_number: usize,
+
+    /// The byte offset in the stream that the content line starts at.
+    pub(crate) offset: usize,
+}
+
+impl ContentLine {
+    /// The position in the stream of the start of the line.
+    pub(crate) fn position(&self) -> Position {
+        Position {
+            offset: self.offset,
+            line: self.line_number,
+            column: 1,
+        }
+    }
+}
+
+/// Whether the physical line is empty, ignoring its line ending.
+fn is_blank(line: &str) -> bool {
+    line.trim_end_matches(&['\r', '\n'] as &[_]).is_empty()
+}
+
+/// Whether the physical line continues the previous one.
+fn is_continuation(line: &str) -> bool {
+    line.starts_with(&[' ', '\t'] as &[_])
+}
+
+/// Splits a stream of physical lines into content lines, i.e. joining together
+/// lines that have been folded.
+#[derive(Debug, Default)]
+pub(crate) struct LineAccumulator {
+    current: String,
+    start_line: usize,
+    start_offset: usize,
+    lines_seen: usize,
+    bytes_seen: usize,
+}
+
+impl LineAccumulator {
+    /// Add the next physical line (including its line ending). Returns the
+    /// previous content line if the given line isn't a continuation of it.
+    pub(crate) fn push(&mut self, line: &str) -> Option<ContentLine> {
+        self.lines_seen += 1;
+        let offset = self.bytes_seen;
+        self.bytes_seen += line.len();
+
+        // Blank lines are ignored, but (as in the grammar) a fold can span
+        // them so we can't return the current line just yet.
+        if is_blank(line) {
+            if !self.current.is_empty() {
+                self.current.push_str(line);
+            }
+            return None;
+        }
+
+        if is_continuation(line) && !self.current.is_empty() {
+            self.current.push_str(line);
+            return None;
+        }
+
+        let previous = self.finish();
+
+        self.current.push_str(line);
+        self.start_line = self.lines_seen;
+        self.start_offset = offset;
+
+        previous
+    }
+
+    /// The length of the content line that is currently buffered.
+    pub(crate) fn pending_len(&self) -> usize {
+        self.current.len()
+    }
+
+    /// The position in the stream of the content line that is currently
+    /// buffered.
+    pub(crate) fn pending_position(&self) -> Position {
+        Position {
+            offset: self.start_offset,
+            line: self.start_line,
+            column: 1,
+        }
+    }
+
+    /// Return any buffered content line, e.g. once the end of the stream has
+    /// been reached.
+    pub(crate) fn finish(&mut self) -> Option<ContentLine> {
+        if self.current.is_empty() {
+            return None;
+        }
+
+        Some(ContentLine {
+            text: std::mem::take(&mut self.current),
+            line_number: self.start_line,
+            offset: self.start_offset,
+        })
+    }
+}
+
+/// Splits data into content lines without copying it, c.f.
+/// [`LineAccumulator`].
+pub(crate) struct ContentLines<'a> {
+    data: &'a str,
+    offset: usize,
+    line_number: usize,
+}
+
+impl<'a> ContentLines<'a> {
+    pub(crate) fn new(data: &'a str) -> ContentLines<'a> {
+        ContentLines {
+            data,
+            offset: 0,
+            line_number: 1,
+        }
+    }
+}
+
+impl<'a> Iterator for ContentLines<'a> {
+    type Item = (&'a str, Position);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut start = None;
+        let mut end = self.offset;
+
+        for line in self.data[self.offset..].split_inclusive('\n') {
+            if start.is_some() && !is_blank(line) && !is_continuation(line) {
+                break;
+            }
+
+            if start.is_none() && !is_blank(line) {
+                start = Some(Position {
+                    offset: end,
+                    line: self.line_number,
+                    column: 1,
+                });
+            }
+
+            end += line.len();
+            self.line_number += 1;
+        }
+
+        self.offset = end;
+
+        start.map(|start| (&self.data[start.offset..end], start))
+    }
+}
+
+/// Get the span of a content line, excluding the line ending.
+pub(crate) fn line_span(text: &str, start: Position) -> Span {
+    let trimmed = text.trim_end_matches(&['\r', '\n'] as &[_]);
+
+    Span {
+        start,
+        end: Locator::new(text, start).position(trimmed.len()),
+    }
+}
+
+/// A parsed content line.
+#[derive(Debug, Clone)]
+pub(crate) enum Line<'a> {
+    Begin { name: Cow<'a, str>, span: Span },
+    End { name: Cow<'a, str>, span: Span },
+    Property(Property<'a>),
+}
+
+impl<'a> Line<'a> {
+    /// Parse a single content line that starts at the given position.
+    pub(crate) fn parse(text: &'a str, start: Position) -> Result<Line<'a>, Error> {
+        let property = Lexer::new(text, start).property()?;
+
+        let is_begin = property.name.eq_ignore_ascii_case("BEGIN");
+        if !is_begin && !property.name.eq_ignore_ascii_case("END") {
+            return Ok(Line::Property(property));
+        }
+
+        if property.group.is_some() || !property.parameters.is_empty() {
+            return Err(Error::syntax(format!(
+                "{} lines can't have a group or parameters",
+                property.name.to_ascii_uppercase()
+            )));
+        }
+
+        let name = property.value;
+        if name.is_empty() || !name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-') {
+            return Err(Error::syntax(format!("Invalid component name: {:?}", name)));
+        }
+
+        let span = line_span(text, start);
+        if is_begin {
+            Ok(Line::Begin { name, span })
+        } else {
+            Ok(Line::End { name, span })
+        }
+    }
+
+    /// Convert into a line that doesn't borrow from the parsed data.
+    pub(crate) fn into_owned(self) -> Line<'static> {
+        match self {
+            Line::Begin { name, span } => Line::Begin {
+                name: Cow::Owned(name.into_owned()),
+                span,
+            },
+            Line::End { name, span } => Line::End {
+                name: Cow::Owned(name.into_owned()),
+                span,
+            },
+            Line::Property(property) => Line::Property(property.into_owned()),
+        }
+    }
+}
+
+/// Builds up components from individual content lines.
+#[derive(Debug, Default)]
+pub(crate) struct ComponentBuilder<'a> {
+    stack: Vec<Component<'a>>,
+    stream_sub_components: bool,
+    limits: ParseLimits,
+
+    /// The number of components that have been started.
+    components_seen: usize,
+}
+
+impl<'a> ComponentBuilder<'a> {
+    pub(crate) fn new(limits: ParseLimits) -> ComponentBuilder<'a> {
+        ComponentBuilder {
+            limits,
+            ..Default::default()
+        }
+    }
+
+    /// Handle the next content line, returning a component if it completes
+    /// one that should be returned, i.e. a top-level component or, if
+    /// streaming, a direct sub-component of one.
+    ///
+    /// Use [`ComponentBuilder::depth`] to tell which it is.
+    pub(crate) fn push(&mut self, line: Line<'a>) -> Result<Option<Component<'a>>, Error> {
+        match line {
+            Line::Begin { name, span } => {
+                self.limits
+                    .check_component(self.stack.len(), self.components_seen)?;
+                self.components_seen += 1;
+
+                // The end of the span gets filled in when we see the END line.
+                self.stack.push(Component {
+                    name,
+                    sub_components: Vec::new(),
+                    properties: Vec::new(),
+                    span: Some(span),
+                });
+
+                Ok(None)
+            }
+            Line::End { name, span } => {
+                match self.stack.last() {
+                    Some(component) if component.name.eq_ignore_ascii_case(&name) => {}
+                    Some(component) => {
+                        return Err(Error::syntax(format!(
+                            "Expected END:{} but found END:{}",
+                            component.name, name
+                        )))
+                    }
+                    None => {
+                        return Err(Error::syntax(format!(
+                            "Unexpected END:{} outside of a component",
+                            name
+                        )))
+                    }
+                }
+
+                Ok(self.close(span.end))
+            }
+            Line::Property(property) => match self.stack.last_mut() {
+                Some(parent) => {
+                    self.limits.check_property_count(parent.properties.len())?;
+                    parent.properties.push(property);
+                    Ok(None)
+                }
+                None => Err(Error::syntax(format!(
+                    "Property {} found outside of a component",
+                    property.name
+                ))),
+            },
+        }
+    }
+
+    /// Close the innermost open component, which ends at the given position.
+    pub(crate) fn close(&mut self, end: Position) -> Option<Component<'a>> {
+        let mut component = self.stack.pop()?;
+
+        if let Some(span) = &mut component.span {
+            span.end = end;
+        }
+
+        let depth = self.stack.len();
+        match self.stack.last_mut() {
+            None => Some(component),
+            Some(_) if self.stream_sub_components && depth == 1 => Some(component),
+            Some(parent) => {
+                parent.sub_components.push(component);
+                None
+            }
+        }
+    }
+
+    /// The number of components currently open.
+    pub(crate) fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// The innermost open component, if any.
+    pub(crate) fn current(&self) -> Option<&Component<'a>> {
+        self.stack.last()
+    }
+
+    /// Whether a component with the given name is currently open.
+    pub(crate) fn is_open(&self, name: &str) -> bool {
+        self.stack
+            .iter()
+            .any(|component| component.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Check that there are no unfinished components, e.g. once the end of the
+    /// stream has been reached.
+    pub(crate) fn finish(&mut self) -> Result<(), Error> {
+        if let Some(component) = self.stack.pop() {
+            return Err(
+                Error::syntax(format!("Missing END:{} at end of stream", component.name))
+                    .with_span(component.span),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Decode a physical line read from a stream.
+///
+/// Lines are only read up to a little over the maximum line length, so may
+/// have been cut off part way through a character. In that case the partial
+/// character is dropped, as the line will fail the length check anyway.
+fn decode_line(line: &[u8], max_line_length: usize) -> Result<&str, Error> {
+    match std::str::from_utf8(line) {
+        Ok(text) => Ok(text),
+        Err(e) if line.len() > max_line_length && e.error_len().is_none() => {
+            Ok(std::str::from_utf8(&line[..e.valid_up_to()]).unwrap_or_default())
+        }
+        Err(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "stream did not contain valid UTF-8",
+        )
+        .into()),
+    }
+}
+
+/// The state of reading components from a stream, independent of how the
+/// stream is actually read.
+#[derive(Debug, Default)]
+pub(crate) struct StreamState {
+    lines: LineAccumulator,
+    builder: ComponentBuilder<'static>,
+    limits: ParseLimits,
+}
+
+impl StreamState {
+    pub(crate) fn set_limits(&mut self, limits: ParseLimits) {
+        self.limits = limits;
+        self.builder.limits = limits;
+    }
+
+    pub(crate) fn set_stream_sub_components(&mut self, stream: bool) {
+        self.builder.stream_sub_components = stream;
+    }
+
+    /// The most that should be read of a single physical line.
+    ///
+    /// This is a few bytes over the limit so that a line that is too long can
+    /// be told apart from one that ends part way through a character.
+    pub(crate) fn max_read(&self) -> usize {
+        self.limits.max_line_length.saturating_add(4)
+    }
+
+    /// Handle the next physical line (including its line ending), or `None`
+    /// at the end of the stream.
+    pub(crate) fn push(&mut self, line: Option<&[u8]>) -> Result<Option<StreamItem>, Error> {
+        let line = match line {
+            Some(line) => {
+                let text = decode_line(line, self.limits.max_line_length)?;
+                let line = self.lines.push(text);
+
+                self.limits
+                    .check_line_length(self.lines.pending_len())
+                    .map_err(|e| {
+                        e.with_span(Some(Span {
+                            start: self.lines.pending_position(),
+                            end: self.lines.pending_position(),
+                        }))
+                    })?;
+
+                line
+            }
+            None => self.lines.finish(),
+        };
+
+        let line = match line {
+            Some(line) => line,
+            None => return Ok(None),
+        };
+
+        let component = Line::parse(&line.text, line.position())
+            .and_then(|parsed| self.builder.push(parsed.into_owned()))
+            .map_err(|e| e.with_span(Some(line_span(&line.text, line.position()))))?;
+
+        Ok(component.map(|component| {
+            if self.builder.depth() == 0 {
+                StreamItem::Component(component)
+            } else {
+                StreamItem::SubComponent(component)
+            }
+        }))
+    }
+
+    /// Check that there are no unfinished components once the end of the
+    /// stream has been reached.
+    pub(crate) fn finish(&mut self) -> Result<(), Error> {
+        self.builder.finish()
+    }
+}
+
+/// Reads components from a stream, one content line at a time.
+///
+/// Only the component currently being read is held in memory, so this can be
+/// used to process arbitrarily large calendars. If
+/// [`stream_sub_components`](ComponentReader::stream_sub_components) is set
+/// then memory usage is bounded by the largest sub-component, rather than the
+/// largest top-level component.
+pub struct ComponentReader<R> {
+    reader: R,
+    state: StreamState,
+    buffer: Vec<u8>,
+    finished: bool,
+    failed: bool,
+}
+
+impl<R: BufRead> ComponentReader<R> {
+    pub fn new(reader: R) -> ComponentReader<R> {
+        ComponentReader {
+            reader,
+            state: StreamState::default(),
+            buffer: Vec::new(),
+            finished: false,
+            failed: false,
+        }
+    }
+
+    /// Set the limits on the data that will be accepted, c.f.
+    /// [`ParseLimits`].
+    pub fn limits(mut self, limits: ParseLimits) -> Self {
+        self.state.set_limits(limits);
+        self
+    }
+
+    /// Whether to return the direct sub-components of each top-level
+    /// component as soon as they have been read, rather than as part of the
+    /// top-level component.
+    pub fn stream_sub_components(mut self, stream: bool) -> Self {
+        self.state.set_stream_sub_components(stream);
+        self
+    }
+
+    /// Read the next item from the stream, returning `None` once the end of
+    /// the stream has been reached.
+    pub fn next_item(&mut self) -> Result<Option<StreamItem>, Error> {
+        while !self.finished {
+            self.buffer.clear();
+
+            // Don't read any more of a line than we'd accept.
+            let max_read = self.state.max_read() as u64;
+            let read =
+                Read::take(&mut self.reader, max_read).read_until(b'\n', &mut self.buffer)?;
+
+            let item = if read == 0 {
+                self.finished = true;
+                self.state.push(None)?
+            } else {
+                self.state.push(Some(&self.buffer))?
+            };
+
+            if item.is_some() {
+                return Ok(item);
+            }
+        }
+
+        self.state.finish()?;
+
+        Ok(None)
+    }
+}
+
+impl<R: BufRead> Iterator for ComponentReader<R> {
+    type Item = Result<StreamItem, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // We don't try and carry on after an error.
+        if self.failed {
+            return None;
+        }
+
+        let result = self.next_item().transpose();
+        self.failed = matches!(result, Some(Err(_)));
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_matches_parse() {
+        let input = include_str!("../example.ics");
+
+        let parsed = Component::from_str_to_stream(input).unwrap();
+
+        let read = ComponentReader::new(input.as_bytes())
+            .map(|item| item.map(StreamItem::into_component))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(read, parsed);
+    }
+
+    #[test]
+    fn stream_sub_components() {
+        let input = include_str!("../example.ics");
+
+        let names = ComponentReader::new(input.as_bytes())
+            .stream_sub_components(true)
+            .map(|item| match item.unwrap() {
+                StreamItem::SubComponent(c) => format!("sub {}", c.name),
+                StreamItem::Component(c) => {
+                    assert!(c.sub_components.is_empty());
+                    format!("top {}", c.name)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, &["sub VTIMEZONE", "sub VEVENT", "top VCALENDAR"]);
+    }
+
+    #[test]
+    fn multiple_top_level() {
+        let input = "BEGIN:A\r\nX:1\r\nEND:A\r\n\r\nBEGIN:B\r\nY:\r\n 2\r\nEND:B";
+
+        let components = ComponentReader::new(input.as_bytes())
+            .map(|item| item.map(StreamItem::into_component))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0].properties[0].value, "1");
+        assert_eq!(components[1].name, "B");
+        assert_eq!(components[1].properties[0].value, "2");
+    }
+
+    #[test]
+    fn errors() {
+        let test_cases = [
+            "BEGIN:A\nEND:B\n",
+            "BEGIN:A\nX:1\n",
+            "X:1\n",
+            "BEGIN:A\nX\nEND:A\n",
+            "END:A\n",
+        ];
+
+        for test_case in test_cases {
+            let result = ComponentReader::new(test_case.as_bytes()).collect::<Result<Vec<_>, _>>();
+
+            assert!(result.is_err(), "{:?}", test_case);
+        }
+    }
+
+    #[test]
+    fn limits() {
+        let limits = ParseLimits {
+            max_depth: 2,
+            max_line_length: 20,
+            ..Default::default()
+        };
+        let read = |data: &str| {
+            ComponentReader::new(data.as_bytes())
+                .limits(limits)
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        assert!(read("BEGIN:A\r\nBEGIN:B\r\nEND:B\r\nEND:A\r\n").is_ok());
+
+        for (data, line) in [
+            (
+                "BEGIN:A\r\nBEGIN:B\r\nBEGIN:C\r\nEND:C\r\nEND:B\r\nEND:A\r\n",
+                3,
+            ),
+            ("BEGIN:A\r\nX:01234567890123456789\r\nEND:A\r\n", 2),
+            ("BEGIN:A\r\nX:0123456789\r\n 0123456789\r\nEND:A\r\n", 2),
+            // Cut off part way through a character.
+            ("BEGIN:A\r\nX:aéééééééééééé\r\nEND:A\r\n", 2),
+        ] {
+            let err = read(data).unwrap_err();
+            assert!(matches!(err, Error::LimitExceeded { .. }), "{:?}", data);
+            assert_eq!(err.context().span.unwrap().start.line, line, "{:?}", data);
+        }
+    }
+
+    #[test]
+    fn spans_match_parse() {
+        let input = include_str!("../example.ics");
+
+        fn collect_spans(component: &Component, spans: &mut Vec<Option<Span>>) {
+            spans.push(component.span);
+            for property in &component.properties {
+                spans.push(property.span);
+                spans.extend(property.parameters.iter().map(|p| p.span));
+            }
+            for sub_component in &component.sub_components {
+                collect_spans(sub_component, spans);
+            }
+        }
+
+        let mut parsed = Vec::new();
+        for component in Component::from_str_to_stream(input).unwrap() {
+            collect_spans(&component, &mut parsed);
+        }
+
+        let mut read = Vec::new();
+        for item in ComponentReader::new(input.as_bytes()) {
+            collect_spans(&item.unwrap().into_component(), &mut read);
+        }
+
+        assert!(parsed.iter().all(Option::is_some));
+        assert_eq!(read, parsed);
+    }
+}
@@ -1,28 +1,247 @@
-use std::collections::VecDeque;
+use std::borrow::Cow;
 
-use anyhow::{bail, Error};
+use crate::error::Error;
 
-/// Unescape string.
-pub fn unescape(s: &str) -> Result<String, Error> {
-    let mut queue: VecDeque<_> = String::from(s).chars().collect();
-    let mut s = String::new();
+/// Unescape string, only allocating if there are escape sequences.
+pub fn unescape(s: &str) -> Result<Cow<'_, str>, Error> {
+    if !s.contains('\\') {
+        return Ok(Cow::Borrowed(s));
+    }
+
+    let mut chars = s.chars();
+    let mut s = String::with_capacity(s.len());
 
-    while let Some(c) = queue.pop_front() {
+    while let Some(c) = chars.next() {
         if c != '\\' {
             s.push(c);
             continue;
         }
 
-        match queue.pop_front() {
-            Some('n') => s.push('\n'),
-            Some('N') => s.push('\n'),
-            Some('\\') => s.push('\\'),
-            Some(';') => s.push(';'),
-            Some(',') => s.push(','),
-            Some(c) => bail!("Unexpected escape sequence \\{}", c),
-            None => bail!("String ends up in \\"),
+        match chars.next() {
+            Some(c) => s.push(escaped_char(c).ok_or_else(|| {
+                Error::invalid_value(format!("Unexpected escape sequence \\{}", c))
+            })?),
+            None => return Err(Error::invalid_value("String ends up in \\")),
         };
     }
 
-    Ok(s)
+    Ok(Cow::Owned(s))
+}
+
+/// Unescape string like [`unescape`], but keeping the character after an
+/// unknown escape sequence (e.g. the `\:` emitted by Outlook) rather than
+/// failing. A trailing backslash is kept as is.
+pub fn unescape_lenient(s: &str) -> Cow<'_, str> {
+    if !s.contains('\\') {
+        return Cow::Borrowed(s);
+    }
+
+    let mut chars = s.chars();
+    let mut s = String::with_capacity(s.len());
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            s.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some(c) => s.push(escaped_char(c).unwrap_or(c)),
+            None => s.push('\\'),
+        };
+    }
+
+    Cow::Owned(s)
+}
+
+/// The character represented by the escape sequence `\c`, if it is valid.
+fn escaped_char(c: char) -> Option<char> {
+    match c {
+        'n' | 'N' => Some('\n'),
+        '\\' | ';' | ',' => Some(c),
+        _ => None,
+    }
+}
+
+/// Split a TEXT list on its (unescaped) commas, and unescape each value.
+///
+/// Whitespace around the values is ignored, as lists are often written with
+/// a space after each comma.
+pub fn unescape_list(s: &str) -> Result<Vec<String>, Error> {
+    split_escaped(s, ',')
+        .into_iter()
+        .map(|value| unescape(value.trim()).map(Cow::into_owned))
+        .collect()
+}
+
+/// Split and unescape a TEXT list like [`unescape_list`], but leniently, c.f.
+/// [`unescape_lenient`].
+pub fn unescape_list_lenient(s: &str) -> Vec<String> {
+    split_escaped(s, ',')
+        .into_iter()
+        .map(|value| unescape_lenient(value.trim()).into_owned())
+        .collect()
+}
+
+/// Decode the RFC 6868 caret encoding used in parameter values, only allocating
+/// if there is anything to decode.
+///
+/// `^n` is a newline, `^'` a double quote and `^^` a caret. Any other caret is
+/// left as is.
+pub fn decode_param_value(s: Cow<'_, str>) -> Cow<'_, str> {
+    if !s.contains('^') {
+        return s;
+    }
+
+    let mut chars = s.chars().peekable();
+    let mut decoded = String::with_capacity(s.len());
+
+    while let Some(c) = chars.next() {
+        if c != '^' {
+            decoded.push(c);
+            continue;
+        }
+
+        match chars.peek() {
+            Some('n') => decoded.push('\n'),
+            Some('\'') => decoded.push('"'),
+            Some('^') => decoded.push('^'),
+            _ => {
+                decoded.push('^');
+                continue;
+            }
+        }
+
+        chars.next();
+    }
+
+    Cow::Owned(decoded)
+}
+
+/// Apply the RFC 6868 caret encoding to a parameter value, c.f.
+/// [`decode_param_value`].
+pub fn encode_param_value(s: &str) -> Cow<'_, str> {
+    if !s.contains(&['^', '"', '\r', '\n'] as &[_]) {
+        return Cow::Borrowed(s);
+    }
+
+    let mut chars = s.chars().peekable();
+    let mut encoded = String::with_capacity(s.len() + 2);
+
+    while let Some(c) = chars.next() {
+        match c {
+            '^' => encoded.push_str("^^"),
+            '"' => encoded.push_str("^'"),
+            '\n' => encoded.push_str("^n"),
+            '\r' => {
+                // Treat CRLF as a single newline.
+                if chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                encoded.push_str("^n");
+            }
+            c => encoded.push(c),
+        }
+    }
+
+    Cow::Owned(encoded)
+}
+
+/// Escape text for use as a property value, c.f. [`unescape`]. Only allocates
+/// if there is something to escape.
+pub fn escape(s: &str) -> Cow<'_, str> {
+    if !s.contains(&['\\', ';', ',', '\n', '\r'] as &[_]) {
+        return Cow::Borrowed(s);
+    }
+
+    let mut chars = s.chars().peekable();
+    let mut escaped = String::with_capacity(s.len() + 2);
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {
+                // Treat CRLF as a single newline.
+                if chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                escaped.push_str("\\n");
+            }
+            c => escaped.push(c),
+        }
+    }
+
+    Cow::Owned(escaped)
+}
+
+/// Escape values as a TEXT list, c.f. [`unescape_list`].
+pub fn escape_list<S: AsRef<str>>(values: &[S]) -> String {
+    values
+        .iter()
+        .map(|value| escape(value.as_ref()))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Split an (escaped) value on the separator, ignoring escaped separators.
+/// The parts are not unescaped.
+pub(crate) fn split_escaped(s: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+
+    for (idx, c) in s.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == separator {
+            parts.push(&s[start..idx]);
+            start = idx + c.len_utf8();
+        }
+    }
+
+    parts.push(&s[start..]);
+
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text() {
+        assert_eq!(unescape(r"a\, b\;c\nd\\").unwrap(), "a, b;c\nd\\");
+        assert!(unescape("a\\:b").is_err());
+        assert!(unescape("a\\").is_err());
+
+        assert_eq!(
+            unescape_lenient("mailto\\:a@example.com"),
+            "mailto:a@example.com"
+        );
+        assert_eq!(unescape_lenient("a\\"), "a\\");
+
+        let text = "a,b;c\nd\\";
+        assert_eq!(unescape(&escape(text)).unwrap(), text);
+    }
+
+    #[test]
+    fn text_list() {
+        assert_eq!(
+            unescape_list("Work\\, urgent,Home").unwrap(),
+            ["Work, urgent", "Home"]
+        );
+        assert_eq!(unescape_list("a\\\\,b").unwrap(), ["a\\", "b"]);
+        assert_eq!(unescape_list("a, b").unwrap(), ["a", "b"]);
+        assert!(unescape_list("a\\:b,c").is_err());
+        assert_eq!(unescape_list_lenient("a\\:b,c"), ["a:b", "c"]);
+
+        let values = ["Work, urgent", "a\\", "b;c"];
+        assert_eq!(escape_list(&values), r"Work\, urgent,a\\,b\;c");
+        assert_eq!(unescape_list(&escape_list(&values)).unwrap(), values);
+    }
 }
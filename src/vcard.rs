@@ -0,0 +1,608 @@
+//! vCards, as defined in RFC 6350 (version 4.0) and RFC 2426 (version 3.0).
+//!
+//! vCards use the same content line format as iCalendar, so are parsed with
+//! [`parser::Component`] and then converted into a [`VCard`].
+
+use std::{borrow::Cow, convert::TryFrom};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use crate::{
+    error::{Error, ErrorContext},
+    parameters::{Encoding, ParameterSet},
+    parser,
+    property::{AttachEnum, PropertyValue},
+    unescape::{escape, split_escaped, unescape_lenient},
+};
+
+/// A contact, i.e. a `VCARD` component.
+///
+/// Text values are unescaped, keeping the character after an unknown escape
+/// sequence, whereas URI values (e.g. `UID` and `TEL;VALUE=uri` in version 4.0)
+/// are left as is.
+#[derive(Debug, Clone)]
+pub struct VCard {
+    /// The version of the vCard format, e.g. `4.0` or `3.0`.
+    pub version: String,
+
+    /// `KIND`: The kind of object the vCard represents (version 4.0 only).
+    pub kind: Option<PropertyValue<Kind>>,
+
+    /// `FN`: The formatted names of the object, there must be at least one.
+    pub formatted_names: Vec<PropertyValue<String>>,
+
+    /// `N`: The components of the name of the object.
+    pub name: Option<PropertyValue<StructuredName>>,
+
+    /// `EMAIL`: The email addresses for the object.
+    pub emails: Vec<PropertyValue<String>>,
+
+    /// `TEL`: The telephone numbers for the object, either as text or as a
+    /// `tel:` URI.
+    pub telephones: Vec<PropertyValue<String>>,
+
+    /// `ADR`: The delivery addresses for the object.
+    pub addresses: Vec<PropertyValue<Address>>,
+
+    /// `ORG`: The organizational name and units associated with the object.
+    pub organizations: Vec<PropertyValue<Vec<String>>>,
+
+    /// `PHOTO`: Images or photographs of the object, either inline or
+    /// referenced by URI.
+    pub photos: Vec<PropertyValue<AttachEnum>>,
+
+    /// `UID`: A globally unique identifier for the object.
+    pub uid: Option<PropertyValue<String>>,
+
+    /// `REV`: When the vCard was last revised.
+    pub revision: Option<PropertyValue<DateTime<Utc>>>,
+
+    /// All other properties, as they were parsed.
+    pub properties: Vec<parser::Property<'static>>,
+}
+
+/// The kind of object a vCard represents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Kind {
+    Individual,
+    Group,
+    Org,
+    Location,
+    Other(String),
+}
+
+impl Kind {
+    fn parse(value: &str) -> Kind {
+        match &value.to_ascii_lowercase() as &str {
+            "individual" => Kind::Individual,
+            "group" => Kind::Group,
+            "org" => Kind::Org,
+            "location" => Kind::Location,
+            _ => Kind::Other(value.to_string()),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Kind::Individual => "individual",
+            Kind::Group => "group",
+            Kind::Org => "org",
+            Kind::Location => "location",
+            Kind::Other(value) => value,
+        }
+    }
+}
+
+/// The components of a name, each of which may have multiple values.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StructuredName {
+    pub family_names: Vec<String>,
+    pub given_names: Vec<String>,
+    pub additional_names: Vec<String>,
+    pub honorific_prefixes: Vec<String>,
+    pub honorific_suffixes: Vec<String>,
+}
+
+/// The components of a delivery address, each of which may have multiple
+/// values.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Address {
+    pub po_box: Vec<String>,
+    pub extended_address: Vec<String>,
+    pub street_address: Vec<String>,
+    pub locality: Vec<String>,
+    pub region: Vec<String>,
+    pub postal_code: Vec<String>,
+    pub country: Vec<String>,
+}
+
+impl TryFrom<parser::Component<'_>> for VCard {
+    type Error = Error;
+
+    fn try_from(component: parser::Component<'_>) -> Result<Self, Self::Error> {
+        let context = ErrorContext::for_component(&component);
+
+        VCard::from_component(component).map_err(|e| e.within(&context))
+    }
+}
+
+impl VCard {
+    fn from_component(component: parser::Component<'_>) -> Result<Self, Error> {
+        if component.name.to_ascii_uppercase() != "VCARD" {
+            return Err(Error::invalid_component(format!(
+                "Expected VCARD, found {}",
+                component.name
+            )));
+        }
+
+        let mut vcard = VCard {
+            version: String::new(),
+            kind: None,
+            formatted_names: Vec::new(),
+            name: None,
+            emails: Vec::new(),
+            telephones: Vec::new(),
+            addresses: Vec::new(),
+            organizations: Vec::new(),
+            photos: Vec::new(),
+            uid: None,
+            revision: None,
+            properties: Vec::new(),
+        };
+
+        let mut version = None;
+
+        for property in component.properties {
+            let name = property.name.to_string();
+            let span = property.span;
+
+            vcard
+                .add_property(property, &mut version)
+                .map_err(|e| e.with_property(&name).with_span(span))?;
+        }
+
+        vcard.version = version.ok_or_else(|| Error::missing_property("VERSION"))?;
+
+        if vcard.formatted_names.is_empty() {
+            return Err(Error::missing_property("FN"));
+        }
+
+        Ok(vcard)
+    }
+
+    fn add_property(
+        &mut self,
+        property: parser::Property<'_>,
+        version: &mut Option<String>,
+    ) -> Result<(), Error> {
+        match &property.name.to_ascii_uppercase() as &str {
+            "VERSION" => *version = Some(property.value.to_string()),
+            "KIND" => {
+                self.kind = Some(from_property(
+                    &property,
+                    Kind::parse(&unescape_lenient(&property.value)),
+                ))
+            }
+            "FN" => self.formatted_names.push(from_property(
+                &property,
+                unescape_lenient(&property.value).into_owned(),
+            )),
+            "N" => {
+                let mut parts = split_structured(&property.value).into_iter();
+                let mut next = || parts.next().unwrap_or_default();
+
+                self.name = Some(from_property(
+                    &property,
+                    StructuredName {
+                        family_names: next(),
+                        given_names: next(),
+                        additional_names: next(),
+                        honorific_prefixes: next(),
+                        honorific_suffixes: next(),
+                    },
+                ));
+            }
+            "EMAIL" => self.emails.push(from_property(
+                &property,
+                unescape_lenient(&property.value).into_owned(),
+            )),
+            "TEL" => {
+                let mut telephone = from_property(&property, property.value.to_string());
+
+                // Version 3.0 numbers are always text, whereas 4.0 ones may be
+                // `tel:` URIs.
+                if !is_uri(&telephone) {
+                    telephone.value = unescape_lenient(&telephone.value).into_owned();
+                }
+
+                self.telephones.push(telephone);
+            }
+            "ADR" => {
+                let mut parts = split_structured(&property.value).into_iter();
+                let mut next = || parts.next().unwrap_or_default();
+
+                self.addresses.push(from_property(
+                    &property,
+                    Address {
+                        po_box: next(),
+                        extended_address: next(),
+                        street_address: next(),
+                        locality: next(),
+                        region: next(),
+                        postal_code: next(),
+                        country: next(),
+                    },
+                ));
+            }
+            "ORG" => {
+                let units = split_escaped(&property.value, ';')
+                    .into_iter()
+                    .map(|unit| unescape_lenient(unit).into_owned())
+                    .collect();
+
+                self.organizations.push(from_property(&property, units));
+            }
+            "PHOTO" => {
+                let parameters = ParameterSet::from(property.parameters.clone());
+
+                // Version 3.0 inlines photos with `ENCODING=b`, whereas 4.0
+                // uses `data:` URIs.
+                let photo = match parameters.get_encoding() {
+                    Some(Encoding::Base64) => {
+                        AttachEnum::Binary(base64::decode(property.value.as_bytes())?)
+                    }
+                    Some(Encoding::Other(encoding)) if encoding.eq_ignore_ascii_case("b") => {
+                        AttachEnum::Binary(base64::decode(property.value.as_bytes())?)
+                    }
+                    _ => AttachEnum::Url(property.value.parse()?),
+                };
+
+                self.photos.push(from_property(&property, photo));
+            }
+            "UID" => self.uid = Some(from_property(&property, property.value.to_string())),
+            "REV" => {
+                self.revision = Some(from_property(&property, parse_timestamp(&property.value)?))
+            }
+            _ => self.properties.push(property.into_owned()),
+        }
+
+        Ok(())
+    }
+
+    /// Convert back into a component, e.g. for writing out.
+    pub fn to_component(&self) -> parser::Component<'static> {
+        let mut properties = vec![parser::Property {
+            group: None,
+            name: Cow::Borrowed("VERSION"),
+            value: Cow::Owned(self.version.clone()),
+            parameters: Vec::new(),
+            span: None,
+        }];
+
+        if let Some(kind) = &self.kind {
+            properties.push(to_property("KIND", kind, kind.value.as_str().to_string()));
+        }
+
+        for formatted_name in &self.formatted_names {
+            let text = escape(&formatted_name.value).into_owned();
+            properties.push(to_property("FN", formatted_name, text));
+        }
+
+        if let Some(name) = &self.name {
+            let text = join_structured(&[
+                &name.value.family_names,
+                &name.value.given_names,
+                &name.value.additional_names,
+                &name.value.honorific_prefixes,
+                &name.value.honorific_suffixes,
+            ]);
+            properties.push(to_property("N", name, text));
+        }
+
+        for email in &self.emails {
+            properties.push(to_property(
+                "EMAIL",
+                email,
+                escape(&email.value).into_owned(),
+            ));
+        }
+
+        for telephone in &self.telephones {
+            let text = if is_uri(telephone) {
+                telephone.value.clone()
+            } else {
+                escape(&telephone.value).into_owned()
+            };
+            properties.push(to_property("TEL", telephone, text));
+        }
+
+        for address in &self.addresses {
+            let text = join_structured(&[
+                &address.value.po_box,
+                &address.value.extended_address,
+                &address.value.street_address,
+                &address.value.locality,
+                &address.value.region,
+                &address.value.postal_code,
+                &address.value.country,
+            ]);
+            properties.push(to_property("ADR", address, text));
+        }
+
+        for organization in &self.organizations {
+            let text = organization
+                .value
+                .iter()
+                .map(|unit| escape(unit))
+                .collect::<Vec<_>>()
+                .join(";");
+            properties.push(to_property("ORG", organization, text));
+        }
+
+        for photo in &self.photos {
+            let text = match &photo.value {
+                AttachEnum::Url(url) => url.to_string(),
+                AttachEnum::Binary(data) => base64::encode(data),
+                AttachEnum::Other { value, .. } => value.clone(),
+            };
+            properties.push(to_property("PHOTO", photo, text));
+        }
+
+        if let Some(uid) = &self.uid {
+            properties.push(to_property("UID", uid, uid.value.clone()));
+        }
+
+        if let Some(revision) = &self.revision {
+            let text = revision.value.format("%Y%m%dT%H%M%SZ").to_string();
+            properties.push(to_property("REV", revision, text));
+        }
+
+        properties.extend(self.properties.iter().cloned());
+
+        parser::Component {
+            name: Cow::Borrowed("VCARD"),
+            sub_components: Vec::new(),
+            properties,
+            span: None,
+        }
+    }
+}
+
+fn from_property<T: std::fmt::Debug + Clone>(
+    property: &parser::Property<'_>,
+    value: T,
+) -> PropertyValue<T> {
+    PropertyValue {
+        value,
+        parameters: ParameterSet::from(property.parameters.clone()),
+        group: property.group.as_ref().map(|g| g.to_string()),
+    }
+}
+
+fn to_property<T: std::fmt::Debug + Clone>(
+    name: &'static str,
+    value: &PropertyValue<T>,
+    text: String,
+) -> parser::Property<'static> {
+    parser::Property {
+        group: value.group.clone().map(Cow::Owned),
+        name: Cow::Borrowed(name),
+        value: Cow::Owned(text),
+        parameters: value.parameters.to_parsed(),
+        span: None,
+    }
+}
+
+/// Split a structured value (e.g. `N` or `ADR`) into its components, each of
+/// which is a comma separated list.
+fn split_structured(value: &str) -> Vec<Vec<String>> {
+    split_escaped(value, ';')
+        .into_iter()
+        .map(|component| {
+            if component.is_empty() {
+                return Vec::new();
+            }
+
+            split_escaped(component, ',')
+                .into_iter()
+                .map(|v| unescape_lenient(v).into_owned())
+                .collect()
+        })
+        .collect()
+}
+
+/// Whether the value is a URI, as given by the `VALUE` parameter, rather than
+/// text.
+fn is_uri<T: std::fmt::Debug + Clone>(value: &PropertyValue<T>) -> bool {
+    value
+        .parameters
+        .get_value_data_type()
+        .is_some_and(|data_type| data_type.eq_ignore_ascii_case("uri"))
+}
+
+fn join_structured(components: &[&Vec<String>]) -> String {
+    components
+        .iter()
+        .map(|values| {
+            values
+                .iter()
+                .map(|v| escape(v))
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Parse a timestamp, accepting both the basic (version 4.0) and extended
+/// (version 3.0) formats, as well as plain dates.
+fn parse_timestamp(value: &str) -> Result<DateTime<Utc>, Error> {
+    for format in &["%Y%m%dT%H%M%SZ", "%Y-%m-%dT%H:%M:%SZ"] {
+        if let Ok(timestamp) = Utc.datetime_from_str(value, format) {
+            return Ok(timestamp);
+        }
+    }
+
+    for format in &["%Y%m%d", "%Y-%m-%d"] {
+        if let Ok(date) = NaiveDate::parse_from_str(value, format) {
+            return Ok(Utc.from_utc_datetime(&NaiveDateTime::new(
+                date,
+                chrono::NaiveTime::from_hms(0, 0, 0),
+            )));
+        }
+    }
+
+    Err(Error::invalid_value(format!(
+        "Invalid timestamp: {}",
+        value
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parameters::Parameter;
+
+    const VCARD_4: &str = "BEGIN:VCARD\r
+VERSION:4.0\r
+KIND:individual\r
+FN:Simon Perreault\r
+N:Perreault;Simon;;;ing. jr,M.Sc.\r
+ORG:Viagenie\\, Inc.;Research\r
+EMAIL;TYPE=work:simon.perreault@viagenie.ca\r
+TEL;VALUE=uri;TYPE=\"work,voice\";PREF=1:tel:+1-418-656-9254;ext=102\r
+ADR;TYPE=work:;Suite D2-630;2875 Laurier;Quebec;QC;G1V 2M2;Canada\r
+PHOTO:http://www.example.com/pub/photos/jqpublic.gif\r
+UID:urn:uuid:4fbe8971-0bc3-424c-9c26-36c3e1eff6b1\r
+REV:19951031T222710Z\r
+item1.X-ABLabel:Home\r
+END:VCARD\r
+";
+
+    const VCARD_3: &str = "BEGIN:VCARD\r
+VERSION:3.0\r
+N:Doe;John;;;\r
+FN:John Doe\r
+PHOTO;ENCODING=b;TYPE=JPEG:aGVsbG8=\r
+TEL;TYPE=HOME,VOICE:(111) 555-1212\r
+REV:2008-04-24T19:52:43Z\r
+END:VCARD\r
+";
+
+    fn parse(input: &str) -> VCard {
+        let component = parser::Component::from_str_to_stream(input)
+            .unwrap()
+            .remove(0);
+        VCard::try_from(component).unwrap()
+    }
+
+    #[test]
+    fn parse_version_4() {
+        let vcard = parse(VCARD_4);
+
+        assert_eq!(vcard.version, "4.0");
+        assert_eq!(vcard.kind.as_ref().unwrap().value, Kind::Individual);
+        assert_eq!(vcard.formatted_names[0].value, "Simon Perreault");
+
+        let name = &vcard.name.as_ref().unwrap().value;
+        assert_eq!(name.family_names, &["Perreault"]);
+        assert_eq!(name.given_names, &["Simon"]);
+        assert!(name.additional_names.is_empty());
+        assert_eq!(name.honorific_suffixes, &["ing. jr", "M.Sc."]);
+
+        assert_eq!(
+            vcard.organizations[0].value,
+            &["Viagenie, Inc.", "Research"]
+        );
+        assert_eq!(vcard.emails[0].value, "simon.perreault@viagenie.ca");
+        assert_eq!(vcard.telephones[0].value, "tel:+1-418-656-9254;ext=102");
+        assert!(vcard.telephones[0]
+            .parameters
+            .parameters()
+            .iter()
+            .any(|p| matches!(p, Parameter::Other { name, values } if name == "TYPE" && values == &["work,voice"])));
+
+        let address = &vcard.addresses[0].value;
+        assert_eq!(address.street_address, &["2875 Laurier"]);
+        assert_eq!(address.country, &["Canada"]);
+
+        assert!(matches!(vcard.photos[0].value, AttachEnum::Url(_)));
+        assert_eq!(
+            vcard.uid.as_ref().unwrap().value,
+            "urn:uuid:4fbe8971-0bc3-424c-9c26-36c3e1eff6b1"
+        );
+        assert_eq!(
+            vcard.revision.as_ref().unwrap().value,
+            Utc.ymd(1995, 10, 31).and_hms(22, 27, 10)
+        );
+
+        assert_eq!(vcard.properties.len(), 1);
+        assert_eq!(vcard.properties[0].group.as_deref(), Some("item1"));
+    }
+
+    #[test]
+    fn parse_version_3() {
+        let vcard = parse(VCARD_3);
+
+        assert_eq!(vcard.version, "3.0");
+        assert!(vcard.kind.is_none());
+        assert_eq!(vcard.name.as_ref().unwrap().value.given_names, &["John"]);
+        assert!(matches!(&vcard.photos[0].value, AttachEnum::Binary(data) if data == b"hello"));
+        assert_eq!(vcard.telephones[0].value, "(111) 555-1212");
+        assert_eq!(
+            vcard.revision.as_ref().unwrap().value,
+            Utc.ymd(2008, 4, 24).and_hms(19, 52, 43)
+        );
+    }
+
+    #[test]
+    fn lenient_text() {
+        // Outlook escapes colons, which vCard doesn't allow.
+        let input = "BEGIN:VCARD\r
+VERSION:3.0\r
+FN:Jane\\: Doe\r
+N:Doe;Jane\\:;;;\r
+ORG:Example\\: Inc.;Sales\r
+EMAIL:mailto\\:jane@example.com\r
+TEL;TYPE=WORK:+1 555 0100\\, ext. 12\r
+END:VCARD\r
+";
+
+        let vcard = parse(input);
+        assert_eq!(vcard.formatted_names[0].value, "Jane: Doe");
+        assert_eq!(vcard.name.as_ref().unwrap().value.given_names, &["Jane:"]);
+        assert_eq!(vcard.organizations[0].value, &["Example: Inc.", "Sales"]);
+        assert_eq!(vcard.emails[0].value, "mailto:jane@example.com");
+        assert_eq!(vcard.telephones[0].value, "+1 555 0100, ext. 12");
+
+        let component = vcard.to_component();
+        let telephone = component.properties.iter().find(|p| p.name == "TEL");
+        assert_eq!(telephone.unwrap().value, "+1 555 0100\\, ext. 12");
+    }
+
+    #[test]
+    fn round_trip() {
+        for input in &[VCARD_4, VCARD_3] {
+            let component = parse(input).to_component();
+
+            let mut writer = crate::writer::ComponentWriter::new(Vec::new());
+            writer.write_component(&component).unwrap();
+            let output = String::from_utf8(writer.into_inner()).unwrap();
+
+            assert_eq!(parse(&output).to_component(), component);
+        }
+    }
+
+    #[test]
+    fn missing_properties() {
+        let input = "BEGIN:VCARD\r\nVERSION:4.0\r\nEMAIL:a@example.com\r\nEND:VCARD\r\n";
+        let component = parser::Component::from_str_to_stream(input)
+            .unwrap()
+            .remove(0);
+
+        let err = VCard::try_from(component).unwrap_err();
+        assert!(matches!(err, Error::MissingProperty { .. }));
+        assert_eq!(err.context().property.as_deref(), Some("FN"));
+        assert_eq!(err.context().component.as_deref(), Some("VCARD"));
+    }
+}
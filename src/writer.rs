@@ -0,0 +1,315 @@
+use std::io::Write;
+
+use crate::error::Error;
+use crate::parser::{needs_quoting, Component, Parameter, Property};
+use crate::unescape::encode_param_value;
+
+/// The maximum length of a line in octets, excluding the line break.
+const MAX_LINE_LENGTH: usize = 75;
+
+/// Writes components out as RFC 5545 content lines.
+///
+/// Lines are terminated with CRLF and folded so that no line is longer than 75
+/// octets. Each content line is written out as soon as it has been built, so
+/// the full output is never held in memory.
+pub struct ComponentWriter<W> {
+    writer: W,
+
+    /// The content line currently being built, reused between lines.
+    line: String,
+}
+
+impl<W: Write> ComponentWriter<W> {
+    pub fn new(writer: W) -> ComponentWriter<W> {
+        ComponentWriter {
+            writer,
+            line: String::new(),
+        }
+    }
+
+    /// Write the component, including all of its sub-components.
+    pub fn write_component(&mut self, component: &Component<'_>) -> Result<(), Error> {
+        self.write_begin(&component.name)?;
+
+        for property in &component.properties {
+            self.write_property(property)?;
+        }
+
+        for sub_component in &component.sub_components {
+            self.write_component(sub_component)?;
+        }
+
+        self.write_end(&component.name)
+    }
+
+    /// Write the `BEGIN` line of a component.
+    pub(crate) fn write_begin(&mut self, name: &str) -> Result<(), Error> {
+        self.line.clear();
+        self.line.push_str("BEGIN:");
+        self.line.push_str(name);
+        self.write_line()
+    }
+
+    /// Write the `END` line of a component.
+    pub(crate) fn write_end(&mut self, name: &str) -> Result<(), Error> {
+        self.line.clear();
+        self.line.push_str("END:");
+        self.line.push_str(name);
+        self.write_line()
+    }
+
+    /// Write out previously read text as is, including any folds and line
+    /// endings.
+    pub(crate) fn write_raw(&mut self, text: &str) -> Result<(), Error> {
+        self.writer.write_all(text.as_bytes())?;
+        Ok(())
+    }
+
+    /// Write a single property.
+    pub fn write_property(&mut self, property: &Property<'_>) -> Result<(), Error> {
+        self.line.clear();
+        if let Some(group) = &property.group {
+            self.line.push_str(group);
+            self.line.push('.');
+        }
+        self.line.push_str(&property.name);
+
+        for parameter in &property.parameters {
+            self.line.push(';');
+            push_parameter(&mut self.line, parameter);
+        }
+
+        if property.value.contains(&['\r', '\n'] as &[_]) {
+            return Err(
+                Error::invalid_value("Property values cannot contain line breaks")
+                    .with_property(&property.name),
+            );
+        }
+
+        self.line.push(':');
+        self.line.push_str(&property.value);
+
+        self.write_line()
+    }
+
+    /// Flush the underlying writer.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Get the underlying writer back.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Write out the current content line, folding it as necessary.
+    fn write_line(&mut self) -> Result<(), Error> {
+        let mut rest = self.line.as_str();
+
+        // Continuation lines start with a space, which counts towards the
+        // limit.
+        let mut limit = MAX_LINE_LENGTH;
+
+        while rest.len() > limit {
+            // We mustn't split in the middle of a UTF-8 sequence.
+            let mut split = limit;
+            while !rest.is_char_boundary(split) {
+                split -= 1;
+            }
+
+            self.writer.write_all(&rest.as_bytes()[..split])?;
+            self.writer.write_all(b"\r\n ")?;
+
+            rest = &rest[split..];
+            limit = MAX_LINE_LENGTH - 1;
+        }
+
+        self.writer.write_all(rest.as_bytes())?;
+        self.writer.write_all(b"\r\n")?;
+
+        Ok(())
+    }
+}
+
+/// Append the parameter to the line, encoding and quoting values as necessary.
+fn push_parameter(line: &mut String, parameter: &Parameter<'_>) {
+    line.push_str(&parameter.name);
+    line.push('=');
+
+    for (idx, value) in parameter.values.iter().enumerate() {
+        if idx > 0 {
+            line.push(',');
+        }
+
+        let value = encode_param_value(value);
+
+        if needs_quoting(&value) {
+            line.push('"');
+            line.push_str(&value);
+            line.push('"');
+        } else {
+            line.push_str(&value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::*;
+
+    fn write(component: &Component) -> Result<String, Error> {
+        let mut writer = ComponentWriter::new(Vec::new());
+        writer.write_component(component)?;
+        Ok(String::from_utf8(writer.into_inner()).unwrap())
+    }
+
+    fn property(
+        name: &'static str,
+        value: String,
+        parameters: Vec<Parameter<'static>>,
+    ) -> Property<'static> {
+        Property {
+            group: None,
+            name: Cow::Borrowed(name),
+            value: Cow::Owned(value),
+            parameters,
+            span: None,
+        }
+    }
+
+    fn component(properties: Vec<Property<'static>>) -> Component<'static> {
+        Component {
+            name: Cow::Borrowed("VEVENT"),
+            sub_components: Vec::new(),
+            properties,
+            span: None,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let input = include_str!("../example.ics");
+
+        let parsed = Component::from_str_to_stream(input).unwrap();
+
+        let mut writer = ComponentWriter::new(Vec::new());
+        for component in &parsed {
+            writer.write_component(component).unwrap();
+        }
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+
+        for line in output.split_terminator("\r\n") {
+            assert!(line.len() <= MAX_LINE_LENGTH, "{:?}", line);
+            assert!(!line.contains('\n'));
+        }
+
+        assert_eq!(Component::from_str_to_stream(&output).unwrap(), parsed);
+    }
+
+    #[test]
+    fn group() {
+        let input = "BEGIN:VCARD\r\nitem1.URL;TYPE=pref:https://example.com\r\nitem1.X-ABLabel:Home\r\nEND:VCARD\r\n";
+
+        let parsed = Component::from_str_to_stream(input).unwrap().remove(0);
+        assert_eq!(parsed.properties[0].group.as_deref(), Some("item1"));
+        assert_eq!(parsed.properties[0].name, "URL");
+        assert_eq!(parsed.properties[1].name, "X-ABLabel");
+
+        assert_eq!(write(&parsed).unwrap(), input);
+    }
+
+    #[test]
+    fn folding() {
+        // Use multibyte characters so that the fold points don't line up with
+        // character boundaries.
+        let value = "aé€".repeat(40);
+        let event = component(vec![property("DESCRIPTION", value.clone(), Vec::new())]);
+
+        let output = write(&event).unwrap();
+        assert!(output.ends_with("END:VEVENT\r\n"));
+
+        let lines = output.split_terminator("\r\n").collect::<Vec<_>>();
+        assert_eq!(lines[0], "BEGIN:VEVENT");
+        assert!(lines[1].len() <= MAX_LINE_LENGTH);
+        assert!(lines[1].len() > MAX_LINE_LENGTH - 4);
+        for line in &lines[2..lines.len() - 1] {
+            assert!(line.starts_with(' '));
+            assert!(line.len() <= MAX_LINE_LENGTH);
+        }
+
+        let parsed = Component::from_str_to_stream(&output).unwrap().remove(0);
+        assert_eq!(parsed.properties[0].value, value);
+    }
+
+    #[test]
+    fn quoting() {
+        let parameters = vec![
+            Parameter {
+                name: Cow::Borrowed("CN"),
+                values: vec![Cow::Borrowed("Doe, Jane")],
+                span: None,
+            },
+            Parameter {
+                name: Cow::Borrowed("DELEGATED-TO"),
+                values: vec![
+                    Cow::Borrowed("mailto:a@example.com"),
+                    Cow::Borrowed("b;c"),
+                    Cow::Borrowed("plain"),
+                ],
+                span: None,
+            },
+        ];
+        let event = component(vec![property(
+            "ATTENDEE",
+            "mailto:jane@example.com".to_string(),
+            parameters,
+        )]);
+
+        let output = write(&event).unwrap();
+        assert_eq!(
+            output,
+            "BEGIN:VEVENT\r\n\
+            ATTENDEE;CN=\"Doe, Jane\";DELEGATED-TO=\"mailto:a@example.com\",\"b;c\",plain:mai\r\n \
+            lto:jane@example.com\r\n\
+            END:VEVENT\r\n"
+        );
+
+        assert_eq!(Component::from_str_to_stream(&output).unwrap()[0], event);
+    }
+
+    #[test]
+    fn invalid() {
+        let event = component(vec![property(
+            "SUMMARY",
+            "Two\nlines".to_string(),
+            Vec::new(),
+        )]);
+        assert!(matches!(write(&event), Err(Error::InvalidValue { .. })));
+    }
+
+    #[test]
+    fn caret_encoding() {
+        let parameters = vec![Parameter {
+            name: Cow::Borrowed("CN"),
+            values: vec![Cow::Borrowed("O'Brien \"Bob\"\nLine ^2")],
+            span: None,
+        }];
+        let event = component(vec![property(
+            "ATTENDEE",
+            "mailto:bob@example.com".to_string(),
+            parameters,
+        )]);
+
+        let output = write(&event).unwrap();
+        assert!(
+            output.contains("CN=O'Brien ^'Bob^'^nLine ^^2:"),
+            "{}",
+            output
+        );
+
+        assert_eq!(Component::from_str_to_stream(&output).unwrap()[0], event);
+    }
+}
@@ -0,0 +1,592 @@
+//! xCal, the XML format for iCalendar data defined in RFC 6321.
+//!
+//! Values use the same representations as jCal, so properties are converted
+//! via [`JcalProperty`] and then mapped to and from XML elements.
+
+use std::borrow::Cow;
+
+use quick_xml::{
+    events::{attributes::Attribute, BytesDecl, BytesEnd, BytesStart, BytesText, Event},
+    Reader, Writer,
+};
+use serde_json::{Map, Value};
+
+use crate::{error::Error, jcal::JcalProperty, limits::ParseLimits, parser};
+
+/// The XML namespace of xCal elements.
+pub const NAMESPACE: &str = "urn:ietf:params:xml:ns:icalendar-2.0";
+
+/// Convert the components into an xCal document.
+pub fn to_xcal(components: &[parser::Component<'_>]) -> Result<String, Error> {
+    let mut writer = Writer::new(Vec::new());
+
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("utf-8"), None)))?;
+    writer.write_event(Event::Start(
+        BytesStart::new("icalendar").with_attributes([("xmlns", NAMESPACE)]),
+    ))?;
+
+    for component in components {
+        write_component(&mut writer, component)?;
+    }
+
+    writer.write_event(Event::End(BytesEnd::new("icalendar")))?;
+
+    String::from_utf8(writer.into_inner()).map_err(Error::syntax)
+}
+
+/// Parse an xCal document back into components.
+///
+/// The document is checked against the default [`ParseLimits`].
+pub fn from_xcal(xml: &str) -> Result<Vec<parser::Component<'static>>, Error> {
+    from_xcal_with_limits(xml, &ParseLimits::default())
+}
+
+/// Parse an xCal document back into components, failing if it exceeds the
+/// given limits.
+pub fn from_xcal_with_limits(
+    xml: &str,
+    limits: &ParseLimits,
+) -> Result<Vec<parser::Component<'static>>, Error> {
+    // Each level of components takes two elements: the component and its
+    // `components` wrapper. On top of that comes the `icalendar` root, and
+    // below the deepest component up to five more: `properties`, the
+    // property, `parameters`, the parameter and its value.
+    let max_depth = limits.max_depth.saturating_mul(2).saturating_add(6);
+    let root = Element::parse(xml, max_depth)?;
+
+    if root.name != "icalendar" || root.namespace.as_deref() != Some(NAMESPACE) {
+        return Err(Error::syntax(format!(
+            "Expected an icalendar element in the {} namespace",
+            NAMESPACE
+        )));
+    }
+
+    let components = root
+        .children
+        .iter()
+        .map(component_from_xcal)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for component in &components {
+        limits.check_tree(component)?;
+    }
+
+    Ok(components)
+}
+
+fn start<W: std::io::Write>(writer: &mut Writer<W>, name: &str) -> Result<(), Error> {
+    writer.write_event(Event::Start(BytesStart::new(name)))?;
+    Ok(())
+}
+
+fn end<W: std::io::Write>(writer: &mut Writer<W>, name: &str) -> Result<(), Error> {
+    writer.write_event(Event::End(BytesEnd::new(name)))?;
+    Ok(())
+}
+
+fn text_element<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    name: &str,
+    text: &str,
+) -> Result<(), Error> {
+    start(writer, name)?;
+    writer.write_event(Event::Text(BytesText::new(text)))?;
+    end(writer, name)
+}
+
+fn write_component<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    component: &parser::Component<'_>,
+) -> Result<(), Error> {
+    let name = component.name.to_ascii_lowercase();
+
+    start(writer, &name)?;
+
+    if !component.properties.is_empty() {
+        start(writer, "properties")?;
+        for property in &component.properties {
+            write_property(writer, property)
+                .map_err(|e| e.with_component(&component.name.to_ascii_uppercase()))?;
+        }
+        end(writer, "properties")?;
+    }
+
+    if !component.sub_components.is_empty() {
+        start(writer, "components")?;
+        for sub_component in &component.sub_components {
+            write_component(writer, sub_component)?;
+        }
+        end(writer, "components")?;
+    }
+
+    end(writer, &name)
+}
+
+fn write_property<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    property: &parser::Property<'_>,
+) -> Result<(), Error> {
+    let property = JcalProperty::from_property(property)?;
+    let name = property.name.to_ascii_lowercase();
+
+    start(writer, &name)?;
+
+    if !property.parameters.is_empty() {
+        start(writer, "parameters")?;
+        for (parameter_name, value) in &property.parameters {
+            let value_type = parameter_value_type(parameter_name);
+            let values = match value {
+                Value::Array(values) => values.iter().collect(),
+                value => vec![value],
+            };
+
+            start(writer, parameter_name)?;
+            for value in values {
+                let text = value.as_str().unwrap_or_default();
+                if value_type == "boolean" {
+                    text_element(writer, value_type, &text.to_ascii_lowercase())?;
+                } else {
+                    text_element(writer, value_type, text)?;
+                }
+            }
+            end(writer, parameter_name)?;
+        }
+        end(writer, "parameters")?;
+    }
+
+    let value_type = property.value_type.to_ascii_lowercase();
+
+    for value in &property.values {
+        match (&property.name as &str, value) {
+            ("GEO", Value::Array(parts)) => {
+                for (part, element) in parts.iter().zip(&["latitude", "longitude"]) {
+                    text_element(writer, element, &scalar_text(part))?;
+                }
+            }
+            ("REQUEST-STATUS", Value::Array(parts)) => {
+                for (part, element) in parts.iter().zip(&["code", "description", "data"]) {
+                    text_element(writer, element, &scalar_text(part))?;
+                }
+            }
+            (_, Value::Array(parts)) if value_type == "period" => {
+                start(writer, "period")?;
+                for (idx, part) in parts.iter().enumerate() {
+                    let text = scalar_text(part);
+                    let element = match idx {
+                        0 => "start",
+                        _ if text
+                            .trim_start_matches(&['+', '-'] as &[_])
+                            .starts_with('P') =>
+                        {
+                            "duration"
+                        }
+                        _ => "end",
+                    };
+                    text_element(writer, element, &text)?;
+                }
+                end(writer, "period")?;
+            }
+            (_, Value::Object(rule)) => {
+                start(writer, "recur")?;
+                for (part, value) in rule {
+                    let values = match value {
+                        Value::Array(values) => values.iter().collect(),
+                        value => vec![value],
+                    };
+                    for value in values {
+                        text_element(writer, part, &scalar_text(value))?;
+                    }
+                }
+                end(writer, "recur")?;
+            }
+            (_, value) => text_element(writer, &value_type, &scalar_text(value))?,
+        }
+    }
+
+    end(writer, &name)
+}
+
+/// The value type of the parameter, as given in RFC 6321 section 3.5.
+fn parameter_value_type(name: &str) -> &'static str {
+    match name {
+        "altrep" | "dir" => "uri",
+        "delegated-from" | "delegated-to" | "member" | "sent-by" => "cal-address",
+        "rsvp" => "boolean",
+        _ => "text",
+    }
+}
+
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn component_from_xcal(element: &Element) -> Result<parser::Component<'static>, Error> {
+    let name = element.name.to_ascii_uppercase();
+
+    let mut properties = Vec::new();
+    let mut sub_components = Vec::new();
+
+    for child in &element.children {
+        match &child.name as &str {
+            "properties" => {
+                for property in &child.children {
+                    properties
+                        .push(property_from_xcal(property).map_err(|e| e.with_component(&name))?);
+                }
+            }
+            "components" => {
+                for sub_component in &child.children {
+                    sub_components.push(component_from_xcal(sub_component)?);
+                }
+            }
+            other => {
+                return Err(
+                    Error::syntax(format!("Unexpected element {}", other)).with_component(&name)
+                )
+            }
+        }
+    }
+
+    Ok(parser::Component {
+        name: Cow::Owned(name),
+        sub_components,
+        properties,
+        span: None,
+    })
+}
+
+fn property_from_xcal(element: &Element) -> Result<parser::Property<'static>, Error> {
+    let name = element.name.to_ascii_uppercase();
+
+    let mut parameters = Map::new();
+    let mut value_type = None;
+    let mut values = Vec::new();
+
+    // `GEO` and `REQUEST-STATUS` have their parts directly in the property
+    // element, rather than in a value element.
+    let mut parts = Vec::new();
+
+    for child in &element.children {
+        match &child.name as &str {
+            "parameters" => {
+                for parameter in &child.children {
+                    let mut parameter_values = parameter
+                        .children
+                        .iter()
+                        .map(|v| match &v.name as &str {
+                            "boolean" => Value::String(v.text.to_ascii_uppercase()),
+                            _ => Value::String(v.text.clone()),
+                        })
+                        .collect::<Vec<_>>();
+
+                    let value = if parameter_values.len() == 1 {
+                        parameter_values.remove(0)
+                    } else {
+                        Value::Array(parameter_values)
+                    };
+
+                    parameters.insert(parameter.name.clone(), value);
+                }
+            }
+            "latitude" | "longitude" => {
+                value_type = Some("FLOAT".to_string());
+                parts.push(Value::String(child.text.clone()));
+            }
+            "code" | "description" | "data" => {
+                value_type = Some("TEXT".to_string());
+                parts.push(Value::String(child.text.clone()));
+            }
+            "period" => {
+                value_type = Some("PERIOD".to_string());
+                values.push(Value::Array(
+                    child
+                        .children
+                        .iter()
+                        .map(|part| Value::String(part.text.clone()))
+                        .collect(),
+                ));
+            }
+            "recur" => {
+                value_type = Some("RECUR".to_string());
+
+                let mut rule = Map::new();
+                for part in &child.children {
+                    let value = Value::String(part.text.clone());
+                    match rule.get_mut(&part.name) {
+                        Some(Value::Array(existing)) => existing.push(value),
+                        Some(existing) => *existing = Value::Array(vec![existing.take(), value]),
+                        None => {
+                            rule.insert(part.name.clone(), value);
+                        }
+                    }
+                }
+
+                values.push(Value::Object(rule));
+            }
+            other => {
+                value_type = Some(other.to_ascii_uppercase());
+                values.push(match other {
+                    "boolean" => Value::Bool(child.text.eq_ignore_ascii_case("true")),
+                    _ => Value::String(child.text.clone()),
+                });
+            }
+        }
+    }
+
+    if !parts.is_empty() {
+        values.push(Value::Array(parts));
+    }
+
+    let value_type = value_type.ok_or_else(|| {
+        Error::syntax(format!("Property {} has no value", element.name)).with_property(&name)
+    })?;
+
+    JcalProperty {
+        name,
+        parameters,
+        value_type,
+        values,
+    }
+    .into_property()
+}
+
+/// A minimal XML element tree, which is all that is needed for xCal.
+#[derive(Debug, Default)]
+struct Element {
+    /// The local name of the element, i.e. without any namespace prefix.
+    name: String,
+    /// The namespace declared on the element, if any.
+    namespace: Option<String>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    /// Parse the document's root element, failing if elements are nested more
+    /// than `max_depth` deep, as both converting and dropping the tree
+    /// recurse.
+    fn parse(xml: &str, max_depth: usize) -> Result<Element, Error> {
+        let mut reader = Reader::from_str(xml);
+        let mut stack: Vec<Element> = Vec::new();
+
+        loop {
+            match reader.read_event()? {
+                Event::Start(start) => {
+                    if stack.len() >= max_depth {
+                        return Err(Error::limit_exceeded(format!(
+                            "Elements are nested more than {} deep",
+                            max_depth
+                        )));
+                    }
+                    stack.push(Element::from_start(&start)?)
+                }
+                Event::Empty(start) => {
+                    let element = Element::from_start(&start)?;
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => return Ok(element),
+                    }
+                }
+                Event::End(_) => {
+                    let element = stack
+                        .pop()
+                        .ok_or_else(|| Error::syntax("Unexpected closing tag"))?;
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => return Ok(element),
+                    }
+                }
+                Event::Text(text) => {
+                    if let Some(element) = stack.last_mut() {
+                        element.text.push_str(&text.unescape()?);
+                    }
+                }
+                Event::CData(data) => {
+                    if let Some(element) = stack.last_mut() {
+                        element
+                            .text
+                            .push_str(&String::from_utf8_lossy(&data.into_inner()));
+                    }
+                }
+                Event::Eof => return Err(Error::syntax("Unexpected end of document")),
+                _ => {}
+            }
+        }
+    }
+
+    fn from_start(start: &BytesStart<'_>) -> Result<Element, Error> {
+        let name = String::from_utf8_lossy(start.local_name().as_ref()).into_owned();
+
+        let mut namespace = None;
+        for attribute in start.attributes() {
+            let Attribute { key, value } = attribute.map_err(quick_xml::Error::from)?;
+            let key = key.as_ref();
+
+            let prefix = start.name().prefix().map(|p| p.as_ref().to_vec());
+            let declares = match &prefix {
+                Some(prefix) => key.strip_prefix(b"xmlns:") == Some(prefix.as_slice()),
+                None => key == b"xmlns",
+            };
+
+            if declares {
+                namespace = Some(String::from_utf8_lossy(&value).into_owned());
+            }
+        }
+
+        Ok(Element {
+            name,
+            namespace,
+            ..Element::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let input = include_str!("../example.ics");
+
+        let parsed = parser::Component::from_str_to_stream(input).unwrap();
+
+        let xml = to_xcal(&parsed).unwrap();
+        assert!(xml
+            .contains("<recur><freq>YEARLY</freq><byday>-1SU</byday><bymonth>3</bymonth></recur>"));
+        assert!(xml.contains("<tzoffsetto><utc-offset>+01:00</utc-offset></tzoffsetto>"));
+        assert!(xml.contains(
+            "<dtstart><parameters><tzid><text>Europe/London</text></tzid></parameters>\
+            <date-time>2020-07-22T14:00:00</date-time></dtstart>"
+        ));
+
+        assert_eq!(from_xcal(&xml).unwrap(), parsed);
+    }
+
+    #[test]
+    fn values() {
+        let input = "BEGIN:VEVENT\r
+ATTENDEE;RSVP=TRUE;DELEGATED-TO=\"mailto:a@example.com\",\"mailto:b@example.com\":mailto:c@example.com\r
+GEO:37.386013;-122.082932\r
+FREEBUSY:19970308T160000Z/PT3H,19970308T200000Z/19970308T210000Z\r
+REQUEST-STATUS:2.0;Success\r
+SUMMARY:Fish & <chips>\r
+END:VEVENT\r
+";
+
+        let parsed = parser::Component::from_str_to_stream(input).unwrap();
+
+        let xml = to_xcal(&parsed).unwrap();
+        assert!(xml.contains("<rsvp><boolean>true</boolean></rsvp>"));
+        assert!(xml.contains(
+            "<delegated-to><cal-address>mailto:a@example.com</cal-address>\
+            <cal-address>mailto:b@example.com</cal-address></delegated-to>"
+        ));
+        assert!(xml.contains(
+            "<geo><latitude>37.386013</latitude><longitude>-122.082932</longitude></geo>"
+        ));
+        assert!(xml.contains(
+            "<period><start>1997-03-08T16:00:00Z</start><duration>PT3H</duration></period>\
+            <period><start>1997-03-08T20:00:00Z</start><end>1997-03-08T21:00:00Z</end></period>"
+        ));
+        assert!(xml.contains(
+            "<request-status><code>2.0</code><description>Success</description></request-status>"
+        ));
+        assert!(xml.contains("<text>Fish &amp; &lt;chips&gt;</text>"));
+
+        assert_eq!(from_xcal(&xml).unwrap(), parsed);
+    }
+
+    #[test]
+    fn lenient_text() {
+        // Outlook escapes colons, which RFC 5545 doesn't allow.
+        let parsed = parser::Component::from_str_to_stream(
+            "BEGIN:VEVENT\r\nSUMMARY:Call mailto\\:a@example.com\r\nEND:VEVENT\r\n",
+        )
+        .unwrap();
+
+        let xml = to_xcal(&parsed).unwrap();
+        assert!(xml.contains("<summary><text>Call mailto:a@example.com</text></summary>"));
+
+        let components = from_xcal(&xml).unwrap();
+        assert_eq!(
+            components[0].properties[0].value,
+            "Call mailto:a@example.com"
+        );
+    }
+
+    #[test]
+    fn prefixed_namespace() {
+        let xml = "<?xml version=\"1.0\"?>\n\
+            <xc:icalendar xmlns:xc=\"urn:ietf:params:xml:ns:icalendar-2.0\">\n\
+              <xc:vcalendar>\n\
+                <xc:properties>\n\
+                  <xc:version><xc:text>2.0</xc:text></xc:version>\n\
+                </xc:properties>\n\
+              </xc:vcalendar>\n\
+            </xc:icalendar>";
+
+        let components = from_xcal(xml).unwrap();
+        assert_eq!(components[0].name, "VCALENDAR");
+        assert_eq!(components[0].properties[0].name, "VERSION");
+        assert_eq!(components[0].properties[0].value, "2.0");
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(matches!(
+            from_xcal("<icalendar><vcalendar/></icalendar>"),
+            Err(Error::Syntax { .. })
+        ));
+
+        let err = from_xcal(
+            "<icalendar xmlns=\"urn:ietf:params:xml:ns:icalendar-2.0\">\
+            <vcalendar><properties><version/></properties></vcalendar></icalendar>",
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::Syntax { .. }));
+        assert_eq!(err.context().property.as_deref(), Some("VERSION"));
+        assert_eq!(err.context().component.as_deref(), Some("VCALENDAR"));
+    }
+
+    #[test]
+    fn limits() {
+        let nested = |depth: usize| {
+            format!(
+                "<icalendar xmlns=\"urn:ietf:params:xml:ns:icalendar-2.0\">{}{}</icalendar>",
+                "<vcalendar><components>".repeat(depth),
+                "</components></vcalendar>".repeat(depth)
+            )
+        };
+
+        assert!(from_xcal(&nested(10)).is_ok());
+        assert!(matches!(
+            from_xcal(&nested(100_000)),
+            Err(Error::LimitExceeded { .. })
+        ));
+
+        let limits = ParseLimits {
+            max_depth: 2,
+            max_properties_per_component: 1,
+            ..Default::default()
+        };
+        assert!(from_xcal_with_limits(&nested(2), &limits).is_ok());
+        assert!(matches!(
+            from_xcal_with_limits(&nested(3), &limits),
+            Err(Error::LimitExceeded { .. })
+        ));
+
+        let xml =
+            "<icalendar xmlns=\"urn:ietf:params:xml:ns:icalendar-2.0\"><vcalendar><properties>\
+            <version><text>2.0</text></version><prodid><text>x</text></prodid>\
+            </properties></vcalendar></icalendar>";
+        assert!(from_xcal(xml).is_ok());
+        assert!(matches!(
+            from_xcal_with_limits(xml, &limits),
+            Err(Error::LimitExceeded { .. })
+        ));
+    }
+}
@@ -62,7 +62,8 @@ END:VCALENDAR
         .unwrap();
 
     let mut summaries = Vec::new();
-    for (_, vevent) in collection.recur_iter(&vcalendar)?.take(3) {
+    for instance in collection.recur_iter(&vcalendar)?.take(3) {
+        let (_, vevent) = instance?;
         summaries.push(vevent.summary.as_deref().unwrap());
     }
 